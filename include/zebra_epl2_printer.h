@@ -0,0 +1,86 @@
+#ifndef ZEBRA_EPL2_PRINTER_H
+#define ZEBRA_EPL2_PRINTER_H
+
+/* Generated with cbindgen from src/ffi.rs; do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define ZEBRA_OK 0
+
+// A required pointer was null or a string was not UTF-8.
+#define ZEBRA_ERR_ARGUMENT -1
+
+// The font could not be parsed.
+#define ZEBRA_ERR_FONT -2
+
+// The printer could not be reached or refused the job.
+#define ZEBRA_ERR_SEND -3
+
+// Unexpected internal failure (a caught panic).
+#define ZEBRA_ERR_INTERNAL -4
+
+// Bytes owned by the library.
+typedef struct ZebraBuffer {
+  uint8_t *data;
+  size_t len;
+} ZebraBuffer;
+
+// One product cell; all strings are NUL-terminated UTF-8.
+typedef struct ZebraProduct {
+  const char *name;
+  const char *price;
+  const char *barcode;
+} ZebraProduct;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Build the two-product label (`build_two_product_job`) into `out`.
+//
+// # Safety
+// `font` must point to `font_len` readable bytes, `brand` to a NUL-terminated
+// string, `products` to 2 `ZebraProduct`s and `out` to a writable `ZebraBuffer`.
+int32_t zebra_build_two_product_label(const uint8_t *font,
+                                      size_t font_len,
+                                      const char *brand,
+                                      const struct ZebraProduct *products,
+                                      struct ZebraBuffer *out);
+
+// Build the four-product label (`build_four_product_job`) into `out`.
+//
+// # Safety
+// As `zebra_build_two_product_label`, with `products` pointing to 4 entries.
+int32_t zebra_build_four_product_label(const uint8_t *font,
+                                       size_t font_len,
+                                       const char *brand,
+                                       const struct ZebraProduct *products,
+                                       struct ZebraBuffer *out);
+
+// Send `len` bytes to `printer`: a device path such as `/dev/usb/lp0`, a
+// network printer `ip` or `host:port` (raw port 9100 by default), or any other
+// name as a Windows printer (with the `windows-spooler` feature) or host name.
+//
+// # Safety
+// `printer` must be a NUL-terminated string and `data` point to `len` readable bytes.
+int32_t zebra_send_raw(const char *printer, const uint8_t *data, size_t len);
+
+// Release a buffer returned by the library; null buffers are ignored.
+//
+// # Safety
+// `buffer` must come from this library and not have been freed before.
+void zebra_free_buffer(struct ZebraBuffer buffer);
+
+// Message of the last failed call on this thread (NUL-terminated UTF-8), or
+// null. Valid until the next failing call on the same thread.
+const char *zebra_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* ZEBRA_EPL2_PRINTER_H */
@@ -0,0 +1,117 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{Cursor, Read, Seek, Write};
+use std::path::Path;
+use std::time::SystemTime;
+
+use image::ImageFormat;
+use serde_json::{json, Value};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::preview::render_preview;
+use crate::stamp::utc_timestamp;
+
+/// Archive layout version written to `manifest.json`.
+pub const FORMAT_VERSION: u64 = 1;
+
+const JOB: &str = "job.epl";
+const DATA: &str = "data.json";
+const PREVIEW: &str = "preview.png";
+const MANIFEST: &str = "manifest.json";
+
+/// Everything needed to reproduce a printed label: an `.eplz` file (zip) holding
+/// the exact job bytes, the source data, a PNG preview and the versions used.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LabelArchive {
+    /// Job exactly as sent to the printer.
+    pub job: Vec<u8>,
+    /// Source data the job was built from (products, brand, ...).
+    pub data: Value,
+    /// PNG bytes of `preview::render_preview(job)`.
+    pub preview: Vec<u8>,
+    pub crate_version: String,
+    /// Caller's template/layout revision.
+    pub template_version: String,
+    /// UTC `YYYY-MM-DD HH:MM` the archive was created.
+    pub created: String,
+}
+
+impl LabelArchive {
+    /// Archive `job` with its source `data`; the preview is rendered now.
+    pub fn new(job: Vec<u8>, data: Value, template_version: &str) -> Result<Self, Box<dyn Error>> {
+        let mut preview = Vec::new();
+        render_preview(&job).write_to(&mut Cursor::new(&mut preview), ImageFormat::Png)?;
+        Ok(LabelArchive {
+            job,
+            data,
+            preview,
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            template_version: template_version.to_string(),
+            created: utc_timestamp(SystemTime::now()),
+        })
+    }
+
+    pub fn write<W: Write + Seek>(&self, w: W) -> Result<(), Box<dyn Error>> {
+        let mut zip = ZipWriter::new(w);
+        let deflate = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        // PNG is already compressed
+        let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+
+        let manifest = json!({
+            "format": FORMAT_VERSION,
+            "crate_version": self.crate_version,
+            "template_version": self.template_version,
+            "created": self.created,
+            "job_bytes": self.job.len(),
+        });
+        zip.start_file(MANIFEST, deflate)?;
+        zip.write_all(serde_json::to_string_pretty(&manifest)?.as_bytes())?;
+        zip.start_file(JOB, deflate)?;
+        zip.write_all(&self.job)?;
+        zip.start_file(DATA, deflate)?;
+        zip.write_all(serde_json::to_string_pretty(&self.data)?.as_bytes())?;
+        zip.start_file(PREVIEW, stored)?;
+        zip.write_all(&self.preview)?;
+        zip.finish()?;
+        Ok(())
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        self.write(File::create(path)?)
+    }
+
+    /// Read an archive, checking the format version and the job length.
+    pub fn read<R: Read + Seek>(r: R) -> Result<Self, Box<dyn Error>> {
+        let mut zip = ZipArchive::new(r)?;
+        let manifest: Value = serde_json::from_slice(&entry(&mut zip, MANIFEST)?)?;
+        let format = manifest["format"].as_u64().ok_or("manifest without format")?;
+        if format > FORMAT_VERSION {
+            return Err(format!("archive format {} is newer than supported {}", format, FORMAT_VERSION).into());
+        }
+        let job = entry(&mut zip, JOB)?;
+        if manifest["job_bytes"].as_u64() != Some(job.len() as u64) {
+            return Err("job.epl length does not match the manifest".into());
+        }
+        let text = |key: &str| manifest[key].as_str().unwrap_or_default().to_string();
+        Ok(LabelArchive {
+            data: serde_json::from_slice(&entry(&mut zip, DATA)?)?,
+            preview: entry(&mut zip, PREVIEW).unwrap_or_default(),
+            crate_version: text("crate_version"),
+            template_version: text("template_version"),
+            created: text("created"),
+            job,
+        })
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        Self::read(File::open(path)?)
+    }
+}
+
+fn entry<R: Read + Seek>(zip: &mut ZipArchive<R>, name: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut f = zip.by_name(name).map_err(|e| format!("{}: {}", name, e))?;
+    let mut buf = Vec::new();
+    f.read_to_end(&mut buf)?;
+    Ok(buf)
+}
@@ -21,7 +21,7 @@ pub fn normalize_ean13(mut code: String) -> Result<String, String> {
     }
 }
 
-fn compute_ean13_checksum(digits: &str) -> Result<u8, String> {
+pub fn compute_ean13_checksum(digits: &str) -> Result<u8, String> {
     if digits.len() != 12 || !digits.chars().all(|c| c.is_ascii_digit()) {
         return Err("EAN13 checksum requires 12 digits".into());
     }
@@ -38,3 +38,499 @@ fn compute_ean13_checksum(digits: &str) -> Result<u8, String> {
     let check = if modulo == 0 { 0 } else { 10 - modulo };
     Ok(check as u8)
 }
+
+// Ensure barcode is valid 12-digit EAN-13 (without check digit)
+pub fn ensure_valid_ean13(barcode: &str) -> String {
+    let digits: String = barcode.chars().filter(|c| c.is_ascii_digit()).collect();
+    
+    if digits.len() >= 12 {
+        // Take first 12 digits (EPL2 will calculate check digit)
+        digits[..12].to_string()
+    } else if digits.len() == 13 {
+        // If 13 digits provided, use first 12 (remove check digit)
+        digits[..12].to_string()
+    } else {
+        // Pad with zeros to make 12 digits
+        format!("{:0<12}", digits)
+    }
+}
+
+/// Field layout of a variable-measure EAN-13 (GS1 restricted circulation numbers).
+/// National rules differ, so prefix and price width are configurable:
+/// `prefix | item code | [price check digit] | price | check digit`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PriceEmbedding {
+    /// "2" or "20".."29".
+    pub prefix: String,
+    /// 4 or 5 price digits (in the smallest currency unit).
+    pub price_digits: usize,
+    /// Insert the GS1 price verifier digit in front of the price.
+    pub price_check: bool,
+}
+
+impl Default for PriceEmbedding {
+    fn default() -> Self {
+        // 2 + 6-digit item + 5-digit price + check
+        PriceEmbedding { prefix: "2".into(), price_digits: 5, price_check: false }
+    }
+}
+
+impl PriceEmbedding {
+    /// Digits available for the item code.
+    pub fn item_digits(&self) -> usize {
+        12usize.saturating_sub(self.prefix.len() + self.price_digits + self.price_check as usize)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let p = self.prefix.as_bytes();
+        if p.is_empty() || p.len() > 2 || p[0] != b'2' || !p.iter().all(u8::is_ascii_digit) {
+            return Err("price-embedded prefix must be 2 or 20..29".into());
+        }
+        if !(4..=5).contains(&self.price_digits) {
+            return Err("price must use 4 or 5 digits".into());
+        }
+        if self.item_digits() == 0 {
+            return Err("no digits left for the item code".into());
+        }
+        Ok(())
+    }
+}
+
+/// Variable-price EAN-13 with the default layout (`2` + 6-digit item + 5-digit price).
+pub fn ean13_price_embedded(item_code: &str, price_cents: u32) -> Result<String, String> {
+    ean13_price_embedded_with(item_code, price_cents, &PriceEmbedding::default())
+}
+
+/// Build a full 13-digit variable-price EAN-13. Short item codes are zero-padded on the left.
+pub fn ean13_price_embedded_with(item_code: &str, price_cents: u32, layout: &PriceEmbedding) -> Result<String, String> {
+    layout.validate()?;
+    if item_code.is_empty() || !item_code.chars().all(|c| c.is_ascii_digit()) {
+        return Err("item code must be digits".into());
+    }
+    let item_len = layout.item_digits();
+    if item_code.len() > item_len {
+        return Err(format!("item code has {} digits, layout allows {}", item_code.len(), item_len));
+    }
+    let price = format!("{:0w$}", price_cents, w = layout.price_digits);
+    if price.len() > layout.price_digits {
+        return Err(format!("price {} does not fit in {} digits", price_cents, layout.price_digits));
+    }
+
+    let mut code = format!("{}{:0>w$}", layout.prefix, item_code, w = item_len);
+    if layout.price_check {
+        code.push(char::from(b'0' + price_check_digit(&price)));
+    }
+    code.push_str(&price);
+    normalize_ean13(code)
+}
+
+/// Validate a variable-price EAN-13 and split it into (item code, price in cents).
+pub fn parse_price_embedded(code: &str, layout: &PriceEmbedding) -> Result<(String, u32), String> {
+    layout.validate()?;
+    let code = normalize_ean13(code.to_string())?;
+    if code.len() != 13 || !code.starts_with(&layout.prefix) {
+        return Err(format!("not a {}-prefixed EAN-13", layout.prefix));
+    }
+    let item_start = layout.prefix.len();
+    let item_end = item_start + layout.item_digits();
+    let price_start = item_end + layout.price_check as usize;
+    let price = &code[price_start..12];
+    if layout.price_check && code.as_bytes()[item_end] - b'0' != price_check_digit(price) {
+        return Err("invalid price check digit".into());
+    }
+    Ok((code[item_start..item_end].to_string(), price.parse().map_err(|_| "bad price")?))
+}
+
+// GS1 price verifier digit weighting tables (General Specifications 7.9.3)
+const W2_MINUS: [u8; 10] = [0, 2, 4, 6, 8, 9, 1, 3, 5, 7];
+const W3: [u8; 10] = [0, 3, 6, 9, 2, 5, 8, 1, 4, 7];
+const W5_PLUS: [u8; 10] = [0, 5, 1, 6, 2, 7, 3, 8, 4, 9];
+const W5_MINUS: [u8; 10] = [0, 5, 9, 4, 8, 3, 7, 2, 6, 1];
+
+fn price_check_digit(price: &str) -> u8 {
+    let d: Vec<usize> = price.bytes().map(|b| (b - b'0') as usize).collect();
+    if d.len() == 4 {
+        let sum = W2_MINUS[d[0]] + W2_MINUS[d[1]] + W3[d[2]] + W5_MINUS[d[3]];
+        (sum as u32 * 3 % 10) as u8
+    } else {
+        let sum = W5_PLUS[d[0]] + W2_MINUS[d[1]] + W5_MINUS[d[2]] + W5_PLUS[d[3]] + W2_MINUS[d[4]];
+        let target = (10 - sum % 10) % 10;
+        // check digit is the one whose 5- product equals the target
+        W5_MINUS.iter().position(|&v| v == target).unwrap_or(0) as u8
+    }
+}
+
+/// GS1 mod-10 check digit (weights 3,1,3,... from the rightmost data digit).
+pub fn gs1_check_digit(digits: &str) -> Result<u8, String> {
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err("GS1 check digit requires digits".into());
+    }
+    let sum: u32 = digits.bytes().rev().enumerate()
+        .map(|(i, b)| (b - b'0') as u32 * if i % 2 == 0 { 3 } else { 1 })
+        .sum();
+    Ok(((10 - sum % 10) % 10) as u8)
+}
+
+/// ITF-14 (GTIN-14 on cartons): 13 digits get the check digit appended, 14 are validated.
+pub fn normalize_itf14(mut code: String) -> Result<String, String> {
+    code.retain(|c| c.is_ascii_digit());
+    match code.len() {
+        13 => {
+            let check = gs1_check_digit(&code)?;
+            code.push(char::from(b'0' + check));
+            Ok(code)
+        }
+        14 => {
+            let check = gs1_check_digit(&code[..13])?;
+            if code.as_bytes()[13] - b'0' == check {
+                Ok(code)
+            } else {
+                Err("invalid ITF-14 checksum".into())
+            }
+        }
+        _ => Err("ITF-14 must have 13 or 14 digits".into()),
+    }
+}
+
+/// GS1 group separator, the FNC1 stand-in between element strings.
+pub const GS: char = '\x1D';
+
+// Two-digit AI prefixes with a predefined total length (AI + data)
+const GS1_PREDEFINED: [(&str, usize); 22] = [
+    ("00", 20), ("01", 16), ("02", 16), ("03", 16), ("04", 18),
+    ("11", 8), ("12", 8), ("13", 8), ("14", 8), ("15", 8), ("16", 8), ("17", 8), ("18", 8), ("19", 8),
+    ("20", 4),
+    ("31", 10), ("32", 10), ("33", 10), ("34", 10), ("35", 10), ("36", 10),
+    ("41", 16),
+];
+
+/// Convert bracketed GS1 element strings (`"(01)09501101530003(17)260101(10)AB12"`)
+/// into the encoded form: AIs and data concatenated, `GS` after every variable-length
+/// field except the last. Predefined lengths and GTIN check digits are verified.
+/// Input without brackets is assumed to be encoded already and returned as is.
+pub fn gs1_element_string(input: &str) -> Result<String, String> {
+    if !input.starts_with('(') {
+        return Ok(input.to_string());
+    }
+    let mut out = String::new();
+    let mut needs_gs = false;
+    for part in input.split('(').skip(1) {
+        let (ai, value) = part.split_once(')').ok_or_else(|| format!("unclosed AI in {:?}", part))?;
+        if !(2..=4).contains(&ai.len()) || !ai.chars().all(|c| c.is_ascii_digit()) {
+            return Err(format!("invalid AI ({})", ai));
+        }
+        if value.is_empty() || !value.is_ascii() {
+            return Err(format!("AI ({}) needs ASCII data", ai));
+        }
+        let fixed = GS1_PREDEFINED.iter().find(|(p, _)| ai.starts_with(p)).map(|&(_, n)| n);
+        if let Some(n) = fixed {
+            if ai.len() + value.len() != n {
+                return Err(format!("AI ({}) must have {} data characters", ai, n - ai.len()));
+            }
+        }
+        if ai == "01" || ai == "02" {
+            let check = gs1_check_digit(&value[..13])?;
+            if value.as_bytes()[13] != b'0' + check {
+                return Err(format!("invalid GTIN check digit in ({})", ai));
+            }
+        }
+        if needs_gs {
+            out.push(GS);
+        }
+        out.push_str(ai);
+        out.push_str(value);
+        needs_gs = fixed.is_none();
+    }
+    if out.is_empty() {
+        return Err("empty GS1 element string".into());
+    }
+    Ok(out)
+}
+
+/// Code 39 / Code 93 character set in check-value order (0-9, A-Z, then symbols).
+pub(crate) const CODE39_CHARS: &str = "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ-. $/+%";
+
+/// Validate Code 39 data (uppercase letters, digits, `- . $ / + %` and space).
+/// With `check` the mod-43 check character is appended.
+pub fn normalize_code39(data: &str, check: bool) -> Result<String, String> {
+    validate_code39_chars(data, "Code 39")?;
+    let mut out = data.to_string();
+    if check {
+        out.push(code39_mod43(data)?);
+    }
+    Ok(out)
+}
+
+/// Mod-43 check character for Code 39 data.
+pub fn code39_mod43(data: &str) -> Result<char, String> {
+    let mut sum = 0usize;
+    for c in data.chars() {
+        sum += CODE39_CHARS.find(c).ok_or_else(|| format!("invalid Code 39 character {:?}", c))?;
+    }
+    Ok(CODE39_CHARS.as_bytes()[sum % 43] as char)
+}
+
+/// Verify the trailing mod-43 check character of Code 39 data.
+pub fn verify_code39_mod43(data: &str) -> Result<(), String> {
+    let (body, last) = data.char_indices().last()
+        .map(|(i, c)| (&data[..i], c))
+        .ok_or("empty Code 39 data")?;
+    if code39_mod43(body)? == last {
+        Ok(())
+    } else {
+        Err("invalid Code 39 mod-43 check character".into())
+    }
+}
+
+/// Validate Code 93 data (same character set as Code 39; the printer adds both check characters).
+pub fn normalize_code93(data: &str) -> Result<String, String> {
+    validate_code39_chars(data, "Code 93")?;
+    Ok(data.to_string())
+}
+
+fn validate_code39_chars(data: &str, symbology: &str) -> Result<(), String> {
+    if data.is_empty() {
+        return Err(format!("{} data is empty", symbology));
+    }
+    match data.chars().find(|&c| !CODE39_CHARS.contains(c)) {
+        Some(c) => Err(format!("invalid {} character {:?}", symbology, c)),
+        None => Ok(()),
+    }
+}
+
+/// `len` digits (check digit included) from `len - 1` (check digit appended)
+/// or `len` (check digit verified), for the GS1 mod-10 family (EAN-8, UPC-A).
+fn normalize_gs1(mut code: String, len: usize, name: &str) -> Result<String, String> {
+    code.retain(|c| c.is_ascii_digit());
+    if code.len() == len - 1 {
+        code.push(char::from(b'0' + gs1_check_digit(&code)?));
+        Ok(code)
+    } else if code.len() == len {
+        if code.as_bytes()[len - 1] - b'0' == gs1_check_digit(&code[..len - 1])? {
+            Ok(code)
+        } else {
+            Err(format!("invalid {} checksum", name))
+        }
+    } else {
+        Err(format!("{} must have {} or {} digits", name, len - 1, len))
+    }
+}
+
+/// EAN-8 with its check digit, from 7 digits (appended) or 8 (verified).
+pub fn normalize_ean8(code: String) -> Result<String, String> {
+    normalize_gs1(code, 8, "EAN-8")
+}
+
+/// UPC-A with its check digit, from 11 digits (appended) or 12 (verified).
+pub fn normalize_upca(code: String) -> Result<String, String> {
+    normalize_gs1(code, 12, "UPC-A")
+}
+
+/// Validate Code 128 data: printable ASCII (the printer picks subsets A/B/C).
+pub fn normalize_code128(data: &str) -> Result<String, String> {
+    if data.is_empty() {
+        return Err("Code 128 data is empty".into());
+    }
+    match data.chars().find(|c| !(' '..='~').contains(c)) {
+        Some(c) => Err(format!("invalid Code 128 character {:?}", c)),
+        None => Ok(data.to_string()),
+    }
+}
+
+/// Data plus the symbology to print it with, see `Barcode::auto`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Barcode {
+    pub symbology: Symbology,
+    /// As sent in the `B` command: EAN/UPC without the check digit, which the printer adds.
+    pub data: String,
+    /// Why the obvious choice was not taken, e.g. a wrong EAN-13 check digit.
+    pub note: Option<String>,
+}
+
+impl Barcode {
+    /// Pick the symbology from the data:
+    ///
+    /// - 7 or 8 digits: EAN-8
+    /// - 11 digits, or 12 with a valid UPC check digit: UPC-A
+    /// - 12 digits otherwise, or 13: EAN-13
+    /// - anything else in printable ASCII: Code 128
+    ///
+    /// Check digits are verified; a number whose check digit is wrong is
+    /// printed as Code 128 (it scans as given) and `note` says why. Spaces
+    /// and dashes between digits are ignored.
+    pub fn auto(data: &str) -> Result<Barcode, String> {
+        let data = data.trim();
+        let digits: String = data.chars().filter(|c| !matches!(c, ' ' | '-')).collect();
+        if !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()) {
+            let gs1 = match digits.len() {
+                7 | 8 => Some((Symbology::Ean8, normalize_ean8(digits.clone()))),
+                11 => Some((Symbology::UpcA, normalize_upca(digits.clone()))),
+                // 12 digits are UPC-A when they carry its check digit, else an EAN-13 body
+                12 => match normalize_upca(digits.clone()) {
+                    Ok(code) => Some((Symbology::UpcA, Ok(code))),
+                    Err(_) => Some((Symbology::Ean13, normalize_ean13(digits.clone()))),
+                },
+                13 => Some((Symbology::Ean13, normalize_ean13(digits.clone()))),
+                _ => None,
+            };
+            match gs1 {
+                Some((symbology, Ok(code))) => {
+                    let data = code[..code.len() - 1].to_string();
+                    return Ok(Barcode { symbology, data, note: None });
+                }
+                // Lengths match above, so only the check digit can be wrong
+                Some((symbology, Err(_))) => {
+                    let note = Some(format!("{} has a wrong {} check digit; printed as Code 128", digits, symbology.name()));
+                    return Ok(Barcode { symbology: Symbology::Code128, data: digits, note });
+                }
+                None => return Ok(Barcode { symbology: Symbology::Code128, data: digits, note: None }),
+            }
+        }
+        Ok(Barcode { symbology: Symbology::Code128, data: normalize_code128(data)?, note: None })
+    }
+
+    /// What a scanner reads (check digit included).
+    pub fn scanned(&self) -> String {
+        self.symbology.with_check_digit(&self.data)
+    }
+}
+
+/// Barcode symbologies the label builders know how to place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Symbology {
+    Ean13,
+    Ean8,
+    UpcA,
+    /// Interleaved 2 of 5 carrying a GTIN-14.
+    Itf14,
+    /// Code 39; with `check` the printer appends the mod-43 character.
+    Code39 { check: bool },
+    Code93,
+    /// Code 128 with automatic subset selection.
+    Code128,
+}
+
+impl Symbology {
+    /// EPL2 `B` command barcode selection (p4).
+    pub fn epl_code(self) -> &'static str {
+        match self {
+            Symbology::Ean13 => "E30",
+            Symbology::Ean8 => "E80",
+            Symbology::UpcA => "UA0",
+            Symbology::Itf14 => "2",
+            Symbology::Code39 { check: false } => "3",
+            Symbology::Code39 { check: true } => "3C",
+            Symbology::Code93 => "9",
+            Symbology::Code128 => "1",
+        }
+    }
+
+    /// Name for messages ("EAN-13", "Code 128", ...).
+    pub fn name(self) -> &'static str {
+        match self {
+            Symbology::Ean13 => "EAN-13",
+            Symbology::Ean8 => "EAN-8",
+            Symbology::UpcA => "UPC-A",
+            Symbology::Itf14 => "ITF-14",
+            Symbology::Code39 { .. } => "Code 39",
+            Symbology::Code93 => "Code 93",
+            Symbology::Code128 => "Code 128",
+        }
+    }
+
+    /// Inverse of `epl_code`.
+    pub fn from_epl_code(code: &str) -> Option<Self> {
+        Some(match code {
+            "E30" => Symbology::Ean13,
+            "E80" => Symbology::Ean8,
+            "UA0" => Symbology::UpcA,
+            "2" => Symbology::Itf14,
+            "3" => Symbology::Code39 { check: false },
+            "3C" => Symbology::Code39 { check: true },
+            "9" => Symbology::Code93,
+            "1" => Symbology::Code128,
+            _ => return None,
+        })
+    }
+
+    /// Wide element width the builders use with `narrow` (ignored by EAN/UPC).
+    pub fn default_wide(self, narrow: u32) -> u32 {
+        match self {
+            Symbology::Ean13 | Symbology::Ean8 | Symbology::UpcA | Symbology::Code93 | Symbology::Code128 => 3,
+            Symbology::Itf14 => narrow * 5 / 2, // 2.5:1
+            Symbology::Code39 { .. } => narrow * 3, // 3:1
+        }
+    }
+
+    /// Printed width in dots (bars only, no quiet zone) for `data` at `narrow` dots per module.
+    pub fn width(self, data: &str, narrow: u32) -> u32 {
+        self.width_with(data, narrow, self.default_wide(narrow))
+    }
+
+    /// Like `width`, with an explicit wide element width for two-width symbologies.
+    pub fn width_with(self, data: &str, narrow: u32, wide: u32) -> u32 {
+        match self {
+            Symbology::Ean13 | Symbology::UpcA => 95 * narrow, // 95 modules
+            Symbology::Ean8 => 67 * narrow,
+            Symbology::Itf14 => {
+                // start NNNN, 2 wide + 3 narrow per digit, stop WNN
+                let digits = data.chars().filter(|c| c.is_ascii_digit()).count() as u32;
+                4 * narrow + digits * (2 * wide + 3 * narrow) + wide + 2 * narrow
+            }
+            Symbology::Code39 { check } => {
+                // 3 wide + 6 narrow elements per char (incl. * start/stop), narrow gap between chars
+                let chars = data.chars().count() as u32 + 2 + check as u32;
+                chars * (3 * wide + 6 * narrow) + (chars - 1) * narrow
+            }
+            Symbology::Code93 => {
+                // 9 modules per char + 2 check chars + start/stop, 1-module termination bar
+                let chars = data.chars().count() as u32 + 4;
+                (chars * 9 + 1) * narrow
+            }
+            Symbology::Code128 => {
+                // 11 modules per symbol: start, data, check; stop is 13. Runs of 4+
+                // digits go two per symbol in subset C (plus switching in and out)
+                let mut symbols = 0;
+                let mut rest = data;
+                while let Some(c) = rest.chars().next() {
+                    let run = rest.chars().take_while(char::is_ascii_digit).count();
+                    if run >= 4 {
+                        symbols += run as u32 / 2 + run as u32 % 2 + 2;
+                        rest = &rest[run..];
+                    } else {
+                        symbols += 1;
+                        rest = &rest[c.len_utf8()..];
+                    }
+                }
+                ((symbols + 2) * 11 + 13) * narrow
+            }
+        }
+    }
+
+    /// `data` as a scanner reads it: EAN/UPC get the check digit the printer
+    /// adds (data that doesn't validate is returned as is).
+    pub fn with_check_digit(self, data: &str) -> String {
+        let full = match self {
+            Symbology::Ean13 => normalize_ean13(data.to_string()),
+            Symbology::Ean8 => normalize_ean8(data.to_string()),
+            Symbology::UpcA => normalize_upca(data.to_string()),
+            _ => Ok(data.to_string()),
+        };
+        full.unwrap_or_else(|_| data.to_string())
+    }
+
+    /// Left x that centers the bars of `data` in a column `column_w` dots wide.
+    pub fn center_x(self, data: &str, narrow: u32, column_w: u32) -> u32 {
+        column_w.saturating_sub(self.width(data, narrow)) / 2
+    }
+
+    /// Minimum quiet zone (left, right) in modules.
+    pub fn quiet_zone(self) -> (u32, u32) {
+        match self {
+            Symbology::Ean13 => (11, 7),
+            Symbology::Ean8 => (7, 7),
+            Symbology::UpcA => (9, 9),
+            Symbology::Itf14 | Symbology::Code39 { .. } | Symbology::Code93 | Symbology::Code128 => (10, 10),
+        }
+    }
+}
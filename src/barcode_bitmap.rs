@@ -0,0 +1,470 @@
+use image::{GrayImage, Luma};
+
+use crate::barcode::{
+    normalize_code128, normalize_code39, normalize_code93, normalize_ean13, normalize_ean8, normalize_itf14, normalize_upca, CODE39_CHARS,
+};
+use crate::font::FontContext;
+
+/// Module layout produced by a barcode encoder.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Symbol {
+    /// 1D symbol: one entry per module, left to right, `true` = bar.
+    Linear(Vec<bool>),
+    /// 2D symbol: rows of modules, `true` = dark.
+    Matrix(Vec<Vec<bool>>),
+}
+
+/// Output of `BarcodeRenderer::encode`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Encoded {
+    pub symbol: Symbol,
+    /// Human readable text to print under the symbol (`None` = none).
+    pub hri: Option<String>,
+}
+
+/// Barcode encoder for symbologies the printer firmware can't (or shouldn't) draw.
+///
+/// Implementations only turn data into modules; the crate scales them to dots,
+/// adds the HRI line in the label font and packs the result as a `GW` graphic.
+/// Wrapping `barcoders` is a few lines:
+///
+/// ```ignore
+/// struct Code128;
+/// impl BarcodeRenderer for Code128 {
+///     fn name(&self) -> &str { "code128" }
+///     fn encode(&self, data: &str) -> Result<Encoded, String> {
+///         let bars = barcoders::sym::code128::Code128::new(data).map_err(|e| e.to_string())?.encode();
+///         Ok(Encoded { symbol: Symbol::Linear(bars.iter().map(|&b| b == 1).collect()), hri: None })
+///     }
+/// }
+/// ```
+pub trait BarcodeRenderer: Send + Sync {
+    /// Short symbology name, used in errors and reports.
+    fn name(&self) -> &str;
+    fn encode(&self, data: &str) -> Result<Encoded, String>;
+}
+
+/// Scaling and HRI options for `render_barcode`.
+#[derive(Debug, Clone)]
+pub struct BitmapBarcodeOptions {
+    /// Dots per module (the `narrow` of the `B` command).
+    pub module: u32,
+    /// Bar height in dots (1D only; 2D symbols use square modules).
+    pub height: u32,
+    /// Print the encoder's HRI text under the symbol.
+    pub hri: bool,
+    pub hri_px: f32,
+    /// Dots between the bars and the HRI line.
+    pub hri_gap: u32,
+}
+
+impl Default for BitmapBarcodeOptions {
+    fn default() -> Self {
+        BitmapBarcodeOptions { module: 2, height: 35, hri: true, hri_px: 22.0, hri_gap: 2 }
+    }
+}
+
+/// Encode `data` and rasterize it (bars + optional HRI) into a 1-bit image for `Label::graphic`.
+/// `ctx` supplies the HRI font; without it no HRI is drawn.
+pub fn render_barcode(
+    renderer: &dyn BarcodeRenderer,
+    data: &str,
+    opts: &BitmapBarcodeOptions,
+    ctx: Option<&FontContext>,
+) -> Result<GrayImage, String> {
+    let encoded = renderer.encode(data).map_err(|e| format!("{}: {}", renderer.name(), e))?;
+    let m = opts.module.max(1);
+
+    let bars = match &encoded.symbol {
+        Symbol::Linear(modules) => {
+            let mut img = GrayImage::from_pixel(modules.len() as u32 * m, opts.height, Luma([255]));
+            for (i, _) in modules.iter().enumerate().filter(|(_, &bar)| bar) {
+                for x in i as u32 * m..(i as u32 + 1) * m {
+                    for y in 0..opts.height {
+                        img.put_pixel(x, y, Luma([0]));
+                    }
+                }
+            }
+            img
+        }
+        Symbol::Matrix(rows) => {
+            let cols = rows.iter().map(|r| r.len()).max().unwrap_or(0) as u32;
+            let mut img = GrayImage::from_pixel(cols * m, rows.len() as u32 * m, Luma([255]));
+            for (r, row) in rows.iter().enumerate() {
+                for (c, _) in row.iter().enumerate().filter(|(_, &dark)| dark) {
+                    for y in r as u32 * m..(r as u32 + 1) * m {
+                        for x in c as u32 * m..(c as u32 + 1) * m {
+                            img.put_pixel(x, y, Luma([0]));
+                        }
+                    }
+                }
+            }
+            img
+        }
+    };
+
+    let hri = match (opts.hri, ctx, encoded.hri.as_deref()) {
+        (true, Some(ctx), Some(text)) if !text.is_empty() => ctx.render_line(text, opts.hri_px, 0),
+        _ => return Ok(bars),
+    };
+
+    // Bars on top, HRI centered underneath
+    let w = bars.width().max(hri.width());
+    let h = bars.height() + opts.hri_gap + hri.height();
+    let mut img = GrayImage::from_pixel(w, h, Luma([255]));
+    image::imageops::replace(&mut img, &bars, ((w - bars.width()) / 2) as i64, 0);
+    image::imageops::replace(&mut img, &hri, ((w - hri.width()) / 2) as i64, (bars.height() + opts.hri_gap) as i64);
+    Ok(img)
+}
+
+/// Built-in EAN-13 encoder (12 or 13 digits; the check digit is added/verified).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Ean13Renderer;
+
+const EAN_L: [u8; 10] = [0x0D, 0x19, 0x13, 0x3D, 0x23, 0x31, 0x2F, 0x3B, 0x37, 0x0B];
+const EAN_G: [u8; 10] = [0x27, 0x33, 0x1B, 0x21, 0x1D, 0x39, 0x05, 0x11, 0x09, 0x17];
+// Parity of the left half (bit set = G code), selected by the first digit
+const EAN_PARITY: [u8; 10] = [0x00, 0x0B, 0x0D, 0x0E, 0x13, 0x19, 0x1C, 0x15, 0x16, 0x1A];
+
+impl BarcodeRenderer for Ean13Renderer {
+    fn name(&self) -> &str {
+        "ean13"
+    }
+
+    fn encode(&self, data: &str) -> Result<Encoded, String> {
+        let code = normalize_ean13(data.to_string())?;
+        let d: Vec<usize> = code.bytes().map(|b| (b - b'0') as usize).collect();
+
+        let mut m = Vec::with_capacity(95);
+        push_bits(&mut m, 0b101, 3);
+        for i in 0..6 {
+            let g = EAN_PARITY[d[0]] & (1 << (5 - i)) != 0;
+            push_bits(&mut m, if g { EAN_G[d[i + 1]] } else { EAN_L[d[i + 1]] }, 7);
+        }
+        push_bits(&mut m, 0b01010, 5);
+        for &digit in &d[7..13] {
+            push_bits(&mut m, !EAN_L[digit] & 0x7F, 7); // R code = inverted L code
+        }
+        push_bits(&mut m, 0b101, 3);
+
+        Ok(Encoded { symbol: Symbol::Linear(m), hri: Some(code) })
+    }
+}
+
+fn push_bits(out: &mut Vec<bool>, bits: u8, n: u32) {
+    for i in (0..n).rev() {
+        out.push(bits & (1 << i) != 0);
+    }
+}
+
+// Bars and spaces of `widths` (digits, bar first) as modules
+fn push_widths(out: &mut Vec<bool>, widths: &str) {
+    for (i, w) in widths.bytes().enumerate() {
+        out.extend(std::iter::repeat_n(i % 2 == 0, (w - b'0') as usize));
+    }
+}
+
+/// Built-in EAN-8 encoder (7 or 8 digits; the check digit is added/verified).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Ean8Renderer;
+
+impl BarcodeRenderer for Ean8Renderer {
+    fn name(&self) -> &str {
+        "ean8"
+    }
+
+    fn encode(&self, data: &str) -> Result<Encoded, String> {
+        let code = normalize_ean8(data.to_string())?;
+        let d: Vec<usize> = code.bytes().map(|b| (b - b'0') as usize).collect();
+
+        let mut m = Vec::with_capacity(67);
+        push_bits(&mut m, 0b101, 3);
+        for &digit in &d[..4] {
+            push_bits(&mut m, EAN_L[digit], 7);
+        }
+        push_bits(&mut m, 0b01010, 5);
+        for &digit in &d[4..] {
+            push_bits(&mut m, !EAN_L[digit] & 0x7F, 7);
+        }
+        push_bits(&mut m, 0b101, 3);
+
+        Ok(Encoded { symbol: Symbol::Linear(m), hri: Some(code) })
+    }
+}
+
+/// Built-in UPC-A encoder (11 or 12 digits): the EAN-13 of the code with a leading 0.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UpcARenderer;
+
+impl BarcodeRenderer for UpcARenderer {
+    fn name(&self) -> &str {
+        "upca"
+    }
+
+    fn encode(&self, data: &str) -> Result<Encoded, String> {
+        let code = normalize_upca(data.to_string())?;
+        let ean = Ean13Renderer.encode(&format!("0{}", code))?;
+        Ok(Encoded { hri: Some(code), ..ean })
+    }
+}
+
+// Code 128 symbols 0..=105 as bar/space widths; the stop adds a 2-module bar
+const CODE128: [&str; 106] = [
+    "212222", "222122", "222221", "121223", "121322", "131222", "122213", "122312", "132212", "221213",
+    "221312", "231212", "112232", "122132", "122231", "113222", "123122", "123221", "223211", "221132",
+    "221231", "213212", "223112", "312131", "311222", "321122", "321221", "312212", "322112", "322211",
+    "212123", "212321", "232121", "111323", "131123", "131321", "112313", "132113", "132311", "211313",
+    "231113", "231311", "112133", "112331", "132131", "113123", "113321", "133121", "313121", "211331",
+    "231131", "213113", "213311", "213131", "311123", "311321", "331121", "312113", "312311", "332111",
+    "314111", "221411", "431111", "111224", "111422", "121124", "121421", "141122", "141221", "112214",
+    "112412", "122114", "122411", "142112", "142211", "241211", "221114", "413111", "241112", "134111",
+    "111242", "121142", "121241", "114212", "124112", "124211", "411212", "421112", "421211", "212141",
+    "214121", "412121", "111143", "111341", "131141", "114113", "114311", "411113", "411311", "113141",
+    "114131", "311141", "411131", "211412", "211214", "211232",
+];
+const CODE128_STOP: &str = "2331112";
+const CODE128_CODE_B: u32 = 100;
+const CODE128_CODE_C: u32 = 99;
+const CODE128_START_B: u32 = 104;
+
+/// Built-in Code 128 encoder for printable ASCII. Starts in subset B and
+/// packs runs of 4+ digits two per symbol in subset C, the encoding
+/// `Symbology::width` measures.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Code128Renderer;
+
+impl BarcodeRenderer for Code128Renderer {
+    fn name(&self) -> &str {
+        "code128"
+    }
+
+    fn encode(&self, data: &str) -> Result<Encoded, String> {
+        let data = normalize_code128(data)?;
+        let b = |c: u8| (c - b' ') as u32;
+        let mut symbols = vec![CODE128_START_B];
+        let mut rest = data.as_bytes();
+        while let Some(&c) = rest.first() {
+            let run = rest.iter().take_while(|c| c.is_ascii_digit()).count();
+            if run < 4 {
+                symbols.push(b(c));
+                rest = &rest[1..];
+                continue;
+            }
+            // An odd digit goes first, in subset B
+            let (odd, pairs) = rest[..run].split_at(run % 2);
+            symbols.extend(odd.iter().map(|&c| b(c)));
+            symbols.push(CODE128_CODE_C);
+            symbols.extend(pairs.chunks(2).map(|p| ((p[0] - b'0') * 10 + p[1] - b'0') as u32));
+            symbols.push(CODE128_CODE_B);
+            rest = &rest[run..];
+        }
+        let check = symbols.iter().enumerate().map(|(i, &s)| s * (i as u32).max(1)).sum::<u32>() % 103;
+        symbols.push(check);
+
+        let mut m = Vec::with_capacity((symbols.len() * 11 + 13) as usize);
+        for s in symbols {
+            push_widths(&mut m, CODE128[s as usize]);
+        }
+        push_widths(&mut m, CODE128_STOP);
+        Ok(Encoded { symbol: Symbol::Linear(m), hri: Some(data) })
+    }
+}
+
+// Code 93 values 0..=46 (`CODE39_CHARS` order, then the 4 shifts) as widths
+const CODE93: [&str; 47] = [
+    "131112", "111213", "111312", "111411", "121113", "121212", "121311", "111114", "131211", "141111",
+    "211113", "211212", "211311", "221112", "221211", "231111", "112113", "112212", "112311", "122112",
+    "132111", "111123", "111222", "111321", "121122", "131121", "212112", "212211", "211122", "211221",
+    "221121", "222111", "112122", "112221", "122121", "123111", "121131", "311112", "311211", "321111",
+    "112131", "113121", "211131", "121221", "312111", "311121", "122211",
+];
+const CODE93_START_STOP: &str = "111141";
+
+/// Built-in Code 93 encoder (Code 39 character set) with both check characters.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Code93Renderer;
+
+impl BarcodeRenderer for Code93Renderer {
+    fn name(&self) -> &str {
+        "code93"
+    }
+
+    fn encode(&self, data: &str) -> Result<Encoded, String> {
+        let data = normalize_code93(data)?;
+        let mut values: Vec<usize> = data.chars().map(|c| CODE39_CHARS.find(c).unwrap_or(0)).collect();
+        // C (weights 1..=20) then K (1..=15, over the data and C), from the right
+        for max_weight in [20, 15] {
+            let sum: usize = values.iter().rev().enumerate().map(|(i, &v)| v * (i % max_weight + 1)).sum();
+            values.push(sum % 47);
+        }
+
+        let mut m = Vec::with_capacity((values.len() + 2) * 9 + 1);
+        push_widths(&mut m, CODE93_START_STOP);
+        for v in values {
+            push_widths(&mut m, CODE93[v]);
+        }
+        push_widths(&mut m, CODE93_START_STOP);
+        m.push(true); // termination bar
+        Ok(Encoded { symbol: Symbol::Linear(m), hri: Some(data) })
+    }
+}
+
+// Code 39 characters (`CODE39_CHARS` order, then the `*` start/stop), wide elements as 1
+const CODE39: [&str; 44] = [
+    "000110100", "100100001", "001100001", "101100000", "000110001", "100110000", "001110000", "000100101", "100100100", "001100100",
+    "100001001", "001001001", "101001000", "000011001", "100011000", "001011000", "000001101", "100001100", "001001100", "000011100",
+    "100000011", "001000011", "101000010", "000010011", "100010010", "001010010", "000000111", "100000110", "001000110", "000010110",
+    "110000001", "011000001", "111000000", "010010001", "110010000", "011010000", "010000101", "110000100", "011000100", "010101000",
+    "010100010", "010001010", "000101010", "010010100",
+];
+
+/// Built-in Code 39 encoder. Narrow and wide elements are `narrow` and `wide`
+/// modules (render at 1 dot per module for the dot widths of a `B` command);
+/// with `check` the mod-43 character is appended.
+#[derive(Debug, Clone, Copy)]
+pub struct Code39Renderer {
+    pub check: bool,
+    pub narrow: u32,
+    pub wide: u32,
+}
+
+impl Default for Code39Renderer {
+    fn default() -> Self {
+        Code39Renderer { check: false, narrow: 1, wide: 3 }
+    }
+}
+
+impl BarcodeRenderer for Code39Renderer {
+    fn name(&self) -> &str {
+        "code39"
+    }
+
+    fn encode(&self, data: &str) -> Result<Encoded, String> {
+        let data = normalize_code39(data, self.check)?;
+        let (n, w) = (self.narrow.max(1) as usize, self.wide.max(1) as usize);
+        let chars = std::iter::once(43).chain(data.chars().map(|c| CODE39_CHARS.find(c).unwrap_or(0))).chain([43]);
+
+        let mut m = Vec::new();
+        for (i, c) in chars.enumerate() {
+            if i > 0 {
+                m.extend(std::iter::repeat_n(false, n)); // gap between characters
+            }
+            for (e, wide) in CODE39[c].bytes().enumerate() {
+                m.extend(std::iter::repeat_n(e % 2 == 0, if wide == b'1' { w } else { n }));
+            }
+        }
+        Ok(Encoded { symbol: Symbol::Linear(m), hri: Some(data) })
+    }
+}
+
+// Interleaved 2 of 5 digits, wide elements as 1
+const ITF: [u8; 10] = [0b00110, 0b10001, 0b01001, 0b11000, 0b00101, 0b10100, 0b01100, 0b00011, 0b10010, 0b01010];
+
+/// Built-in ITF-14 encoder (13 or 14 digits; the check digit is added/verified),
+/// bars only: the bearer frame is `label::Itf14`'s. Narrow and wide elements
+/// are `narrow` and `wide` modules, as for `Code39Renderer`.
+#[derive(Debug, Clone, Copy)]
+pub struct Itf14Renderer {
+    pub narrow: u32,
+    pub wide: u32,
+}
+
+impl Default for Itf14Renderer {
+    fn default() -> Self {
+        Itf14Renderer { narrow: 2, wide: 5 }
+    }
+}
+
+impl BarcodeRenderer for Itf14Renderer {
+    fn name(&self) -> &str {
+        "itf14"
+    }
+
+    fn encode(&self, data: &str) -> Result<Encoded, String> {
+        let code = normalize_itf14(data.to_string())?;
+        let (n, w) = (self.narrow.max(1) as usize, self.wide.max(1) as usize);
+        let width = |wide: bool| if wide { w } else { n };
+        let d: Vec<usize> = code.bytes().map(|b| (b - b'0') as usize).collect();
+
+        let mut m = Vec::new();
+        for bar in [true, false, true, false] {
+            m.extend(std::iter::repeat_n(bar, n));
+        }
+        // Each pair: first digit in the bars, second in the spaces between them
+        for pair in d.chunks(2) {
+            for i in (0..5).rev() {
+                m.extend(std::iter::repeat_n(true, width(ITF[pair[0]] & (1 << i) != 0)));
+                m.extend(std::iter::repeat_n(false, width(ITF[pair[1]] & (1 << i) != 0)));
+            }
+        }
+        m.extend(std::iter::repeat_n(true, w));
+        m.extend(std::iter::repeat_n(false, n));
+        m.extend(std::iter::repeat_n(true, n));
+        Ok(Encoded { symbol: Symbol::Linear(m), hri: Some(code) })
+    }
+}
+
+/// DataMatrix (ECC 200) encoder; the smallest square symbol that fits is chosen.
+///
+/// With `gs1` the data is taken as GS1 element strings (bracketed
+/// `"(01)...(17)..."` or already `GS`-separated) and encoded behind FNC1;
+/// the bracketed form is then returned as HRI. Needs the `datamatrix` feature.
+#[cfg(feature = "datamatrix")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DataMatrixRenderer {
+    pub gs1: bool,
+}
+
+#[cfg(feature = "datamatrix")]
+impl BarcodeRenderer for DataMatrixRenderer {
+    fn name(&self) -> &str {
+        if self.gs1 { "gs1-datamatrix" } else { "datamatrix" }
+    }
+
+    fn encode(&self, data: &str) -> Result<Encoded, String> {
+        let symbols = datamatrix::SymbolList::default().enforce_square();
+        let (code, hri) = if self.gs1 {
+            let encoded = crate::barcode::gs1_element_string(data)?;
+            let hri = data.starts_with('(').then(|| data.to_string());
+            (datamatrix::DataMatrix::encode_gs1(encoded.as_bytes(), symbols), hri)
+        } else {
+            (datamatrix::DataMatrix::encode_str(data, symbols), None)
+        };
+        let bitmap = code.map_err(|e| format!("{:?}", e))?.bitmap();
+
+        let mut rows = vec![vec![false; bitmap.width()]; bitmap.height()];
+        for (x, y) in bitmap.pixels() {
+            rows[y][x] = true;
+        }
+        Ok(Encoded { symbol: Symbol::Matrix(rows), hri })
+    }
+}
+
+/// QR Code encoder, smallest version that fits `data` at `ec_level`. Give
+/// its `MatrixCode` a 4-module quiet zone. Needs the `qr` feature.
+#[cfg(feature = "qr")]
+#[derive(Debug, Clone, Copy)]
+pub struct QrRenderer {
+    pub ec_level: qrcode::EcLevel,
+}
+
+#[cfg(feature = "qr")]
+impl Default for QrRenderer {
+    fn default() -> Self {
+        QrRenderer { ec_level: qrcode::EcLevel::M }
+    }
+}
+
+#[cfg(feature = "qr")]
+impl BarcodeRenderer for QrRenderer {
+    fn name(&self) -> &str {
+        "qr"
+    }
+
+    fn encode(&self, data: &str) -> Result<Encoded, String> {
+        let code = qrcode::QrCode::with_error_correction_level(data, self.ec_level).map_err(|e| e.to_string())?;
+        let dark: Vec<bool> = code.to_colors().into_iter().map(|c| c == qrcode::Color::Dark).collect();
+        let rows = dark.chunks(code.width()).map(<[bool]>::to_vec).collect();
+        Ok(Encoded { symbol: Symbol::Matrix(rows), hri: None })
+    }
+}
@@ -0,0 +1,221 @@
+use std::error::Error;
+
+use crate::epl::{count_labels, with_copies};
+use crate::font::FontContext;
+use crate::label::LabelJob;
+use crate::layout::BuildOptions;
+use crate::product::Product;
+use crate::transport::Transport;
+
+/// Gang layout of one physical label.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// Two products stacked vertically.
+    TwoUp,
+    /// Four products in a 2x2 grid.
+    FourUp,
+}
+
+impl Layout {
+    pub fn cells(self) -> usize {
+        match self {
+            Layout::TwoUp => 2,
+            Layout::FourUp => 4,
+        }
+    }
+}
+
+/// A product and how many stickers of it to print.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchItem {
+    pub product: Product,
+    pub quantity: u32,
+}
+
+impl BatchItem {
+    pub fn new(product: Product, quantity: u32) -> Self {
+        BatchItem { product, quantity }
+    }
+}
+
+/// One label form of the plan: a batch item index per cell (`None` = blank cell),
+/// printed `copies` times with a single `P<copies>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sheet {
+    pub cells: Vec<Option<usize>>,
+    pub copies: u32,
+}
+
+/// Expand quantities into sheets.
+///
+/// Whole sheets of a single product are emitted first as one form with `copies`,
+/// then the remainders are packed together so only the very last sheet can have
+/// blank cells. Identical consecutive sheets are merged.
+pub fn plan(items: &[BatchItem], layout: Layout) -> Vec<Sheet> {
+    let n = layout.cells();
+    let mut sheets: Vec<Sheet> = Vec::new();
+
+    for (i, item) in items.iter().enumerate() {
+        let full = item.quantity / n as u32;
+        if full > 0 {
+            push_sheet(&mut sheets, vec![Some(i); n], full);
+        }
+    }
+
+    let mut current: Vec<Option<usize>> = Vec::with_capacity(n);
+    for (i, item) in items.iter().enumerate() {
+        for _ in 0..item.quantity % n as u32 {
+            current.push(Some(i));
+            if current.len() == n {
+                push_sheet(&mut sheets, std::mem::take(&mut current), 1);
+            }
+        }
+    }
+    if !current.is_empty() {
+        current.resize(n, None);
+        push_sheet(&mut sheets, current, 1);
+    }
+    sheets
+}
+
+fn push_sheet(sheets: &mut Vec<Sheet>, cells: Vec<Option<usize>>, copies: u32) {
+    match sheets.last_mut() {
+        Some(last) if last.cells == cells => last.copies += copies,
+        _ => sheets.push(Sheet { cells, copies }),
+    }
+}
+
+/// Outcome of `optimize`, with the naive baseline for comparison.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlanReport {
+    pub sheets: Vec<Sheet>,
+    /// Physical labels fed through the printer.
+    pub labels: u32,
+    pub wasted_cells: u32,
+    /// Labels if every product were printed on its own sheets.
+    pub naive_labels: u32,
+    /// Blank cells if every product were printed on its own sheets.
+    pub naive_wasted_cells: u32,
+}
+
+/// Like `plan`, but packs the remainders so that each product's leftover stickers
+/// share a sheet whenever possible (first-fit decreasing), and only the last sheet
+/// may contain blank cells. Reports waste against printing products one by one.
+pub fn optimize(items: &[BatchItem], layout: Layout) -> PlanReport {
+    let n = layout.cells();
+    let mut sheets: Vec<Sheet> = Vec::new();
+    for (i, item) in items.iter().enumerate() {
+        let full = item.quantity / n as u32;
+        if full > 0 {
+            push_sheet(&mut sheets, vec![Some(i); n], full);
+        }
+    }
+
+    // First-fit decreasing over the remainders (each < n, so each fits an empty bin)
+    let mut rest: Vec<(usize, usize)> = items.iter().enumerate()
+        .map(|(i, item)| (i, (item.quantity % n as u32) as usize))
+        .filter(|&(_, r)| r > 0)
+        .collect();
+    rest.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    let mut bins: Vec<Vec<usize>> = Vec::new();
+    for (i, r) in rest {
+        match bins.iter_mut().find(|b| b.len() + r <= n) {
+            Some(bin) => bin.extend(std::iter::repeat_n(i, r)),
+            None => bins.push(vec![i; r]),
+        }
+    }
+
+    // Re-flow partially filled bins so that at most one sheet has blanks
+    let (mut full_bins, partial): (Vec<_>, Vec<_>) = bins.into_iter().partition(|b| b.len() == n);
+    let spill: Vec<usize> = partial.into_iter().flatten().collect();
+    full_bins.extend(spill.chunks(n).map(|c| c.to_vec()));
+
+    for mut bin in full_bins {
+        bin.sort_unstable();
+        let mut cells: Vec<Option<usize>> = bin.into_iter().map(Some).collect();
+        cells.resize(n, None);
+        push_sheet(&mut sheets, cells, 1);
+    }
+
+    let labels: u32 = sheets.iter().map(|s| s.copies).sum();
+    let total: u32 = items.iter().map(|i| i.quantity).sum();
+    let naive_labels: u32 = items.iter().map(|i| i.quantity.div_ceil(n as u32)).sum();
+    PlanReport {
+        sheets,
+        labels,
+        wasted_cells: labels * n as u32 - total,
+        naive_labels,
+        naive_wasted_cells: naive_labels * n as u32 - total,
+    }
+}
+
+/// Optimize and build the batch: one job per sheet, in print order, each
+/// built with `opts` like `build_*_product_job_with`.
+pub fn build_batch(ctx: &FontContext, brand: &str, items: &[BatchItem], layout: Layout, opts: &BuildOptions) -> Vec<LabelJob> {
+    optimize(items, layout).sheets.iter().map(|sheet| build_sheet(ctx, brand, items, layout, sheet, opts)).collect()
+}
+
+/// `build_batch` with the sheets built on the rayon thread pool. The font
+/// context (and its render cache) is shared by all threads; jobs come back in
+/// print order.
+#[cfg(feature = "rayon")]
+pub fn build_parallel(ctx: &FontContext, brand: &str, items: &[BatchItem], layout: Layout, opts: &BuildOptions) -> Vec<LabelJob> {
+    use rayon::prelude::*;
+
+    optimize(items, layout).sheets.par_iter().map(|sheet| build_sheet(ctx, brand, items, layout, sheet, opts)).collect()
+}
+
+/// Build one planned sheet with `opts`.
+pub fn build_sheet(ctx: &FontContext, brand: &str, items: &[BatchItem], layout: Layout, sheet: &Sheet, opts: &BuildOptions) -> LabelJob {
+    let cell = |i: usize| sheet.cells.get(i).copied().flatten().map(|idx| &items[idx].product);
+    let mut built = match layout {
+        Layout::TwoUp => crate::two_up_label(ctx, [Some(brand); 2], [cell(0), cell(1)], opts),
+        Layout::FourUp => crate::four_up_label(ctx, [Some(brand); 4], [cell(0), cell(1), cell(2), cell(3)], opts),
+    };
+    built.label.copies = sheet.copies;
+    built.job()
+}
+
+/// Result of `print_with_first_article`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FirstArticle {
+    /// Operator approved; `labels` physical labels were printed in total.
+    Released { labels: u32 },
+    /// Operator rejected the first label; nothing else was sent.
+    Rejected,
+}
+
+/// Split a batch into its first physical label and the remainder: the first
+/// job is printed once, and its remaining copies (if any) lead the rest.
+pub fn split_first_article(jobs: &[LabelJob]) -> Option<(Vec<u8>, Vec<Vec<u8>>)> {
+    let (first, others) = jobs.split_first()?;
+    let copies = count_labels(&first.bytes);
+    let mut rest = Vec::with_capacity(jobs.len());
+    if copies > 1 {
+        rest.push(with_copies(&first.bytes, copies - 1));
+    }
+    rest.extend(others.iter().map(|j| j.bytes.clone()));
+    Some((with_copies(&first.bytes, 1), rest))
+}
+
+/// Print a single first label, wait for `confirm` (operator checked it visually
+/// or scanned it), and only then release the remainder of the batch.
+pub fn print_with_first_article<T, F>(jobs: &[LabelJob], transport: &mut T, confirm: F) -> Result<FirstArticle, Box<dyn Error>>
+where
+    T: Transport + ?Sized,
+    F: FnOnce() -> bool,
+{
+    let Some((first, rest)) = split_first_article(jobs) else {
+        return Ok(FirstArticle::Released { labels: 0 });
+    };
+    transport.send(&first)?;
+    if !confirm() {
+        return Ok(FirstArticle::Rejected);
+    }
+    let mut labels = 1;
+    for job in &rest {
+        transport.send(job)?;
+        labels += count_labels(job);
+    }
+    Ok(FirstArticle::Released { labels })
+}
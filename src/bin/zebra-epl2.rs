@@ -0,0 +1,66 @@
+//! Command line front end (`cli` feature):
+//!
+//! ```text
+//! zebra-epl2 build <font.ttf> <brand> (<name> <price> <barcode>){2 or 4} > job.epl
+//! zebra-epl2 dump <job.epl>
+//! zebra-epl2 preview <job.epl> <out.png>
+//! ```
+
+use std::error::Error;
+use std::io::Write;
+use std::{env, fs, io, process};
+
+use zebra_epl2_printer::epl::parse;
+use zebra_epl2_printer::{build_four_product_job, build_two_product_job, render_preview, FontContext, Product};
+
+const USAGE: &str = "usage:
+  zebra-epl2 build <font.ttf> <brand> (<name> <price> <barcode>){2 or 4} > job.epl
+  zebra-epl2 dump <job.epl>
+  zebra-epl2 preview <job.epl> <out.png>";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Err(e) = run(&args) {
+        eprintln!("zebra-epl2: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    match args.first().map(String::as_str) {
+        Some("build") if args.len() >= 3 => build(&args[1], &args[2], &args[3..]),
+        Some("dump") if args.len() == 2 => {
+            let mut out = io::stdout().lock();
+            for cmd in parse(&fs::read(&args[1])?) {
+                writeln!(out, "{}", cmd)?;
+            }
+            Ok(())
+        }
+        Some("preview") if args.len() == 3 => {
+            render_preview(&fs::read(&args[1])?).save(&args[2])?;
+            Ok(())
+        }
+        _ => Err(USAGE.into()),
+    }
+}
+
+fn build(font: &str, brand: &str, fields: &[String]) -> Result<(), Box<dyn Error>> {
+    let bytes = fs::read(font)?;
+    let ctx = FontContext::new(&bytes)?;
+    let products: Vec<Product> = fields.chunks(3)
+        .map(|p| match p {
+            [name, price, barcode] => Ok(Product::new(name, price, barcode)),
+            _ => Err(USAGE),
+        })
+        .collect::<Result<_, _>>()?;
+
+    let job = match <[Product; 2]>::try_from(products.clone()) {
+        Ok(two) => build_two_product_job(&ctx, brand, &two),
+        Err(_) => build_four_product_job(&ctx, brand, &<[Product; 4]>::try_from(products).map_err(|_| USAGE)?),
+    };
+    for w in &job.warnings {
+        eprintln!("warning: {}", w);
+    }
+    io::stdout().write_all(&job.bytes)?;
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use image::GrayImage;
+
+use crate::font::{BoldStrength, TextStyle};
+
+/// Default number of rendered lines a `FontContext` keeps.
+pub const DEFAULT_CAPACITY: usize = 256;
+
+/// A rasterized line as stored in the render cache. Only the bitmap is kept:
+/// it is packed into `GW` rows when the label is serialized.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderedText {
+    pub image: GrayImage,
+}
+
+impl RenderedText {
+    pub fn new(image: GrayImage) -> Self {
+        RenderedText { image }
+    }
+}
+
+/// Counters of a `FontContext` render cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub entries: usize,
+    pub capacity: usize,
+}
+
+impl CacheStats {
+    /// Share of lookups served from the cache (0.0 before the first lookup).
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 { 0.0 } else { self.hits as f64 / total as f64 }
+    }
+}
+
+/// What a rendered line depends on besides the font: which renderer drew it
+/// (`kind`), the text, size, padding and style. Floats are keyed by bit pattern.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct RenderKey {
+    kind: &'static str,
+    text: String,
+    font_px: u32,
+    pad: u32,
+    threshold: u32,
+    bold: BoldStrength,
+    italic: bool,
+    letter_spacing: u32,
+    outline: Option<u32>,
+}
+
+impl RenderKey {
+    pub(crate) fn new(kind: &'static str, text: &str, font_px: f32, pad: u32, style: &TextStyle) -> Self {
+        RenderKey {
+            kind,
+            text: text.to_string(),
+            font_px: font_px.to_bits(),
+            pad,
+            threshold: style.threshold.to_bits(),
+            bold: style.bold_strength,
+            italic: style.synthetic_italic,
+            letter_spacing: style.letter_spacing.to_bits(),
+            outline: style.outline,
+        }
+    }
+}
+
+/// Least-recently-used map of rendered lines.
+pub(crate) struct RenderCache {
+    capacity: usize,
+    entries: HashMap<RenderKey, (Arc<RenderedText>, u64)>,
+    clock: u64,
+    stats: CacheStats,
+}
+
+impl RenderCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        RenderCache { capacity, entries: HashMap::new(), clock: 0, stats: CacheStats { capacity, ..Default::default() } }
+    }
+
+    pub(crate) fn get(&mut self, key: &RenderKey) -> Option<Arc<RenderedText>> {
+        self.clock += 1;
+        match self.entries.get_mut(key) {
+            Some((hit, used)) => {
+                *used = self.clock;
+                self.stats.hits += 1;
+                Some(hit.clone())
+            }
+            None => {
+                self.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub(crate) fn insert(&mut self, key: RenderKey, value: Arc<RenderedText>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+            // Few hundred entries: a scan for the oldest beats keeping a list in order
+            if let Some(oldest) = self.entries.iter().min_by_key(|(_, (_, used))| *used).map(|(k, _)| k.clone()) {
+                self.entries.remove(&oldest);
+                self.stats.evictions += 1;
+            }
+        }
+        self.entries.insert(key, (value, self.clock));
+    }
+
+    pub(crate) fn stats(&self) -> CacheStats {
+        CacheStats { entries: self.entries.len(), ..self.stats }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
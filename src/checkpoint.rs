@@ -0,0 +1,125 @@
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::status::{PrinterStatus, StatusSource};
+use crate::transport::Transport;
+
+/// Progress of a named batch: every job before `next` was sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub batch_id: String,
+    pub next: usize,
+}
+
+impl Checkpoint {
+    /// Read `path` (`<batch id>\n<next index>\n`); `None` if it doesn't exist.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Option<Self>> {
+        let text = match fs::read_to_string(path) {
+            Ok(t) => t,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let mut lines = text.lines();
+        let bad = || io::Error::new(io::ErrorKind::InvalidData, "malformed checkpoint");
+        let batch_id = lines.next().ok_or_else(bad)?.to_string();
+        let next = lines.next().and_then(|l| l.trim().parse().ok()).ok_or_else(bad)?;
+        Ok(Some(Checkpoint { batch_id, next }))
+    }
+
+    /// Write atomically (temp file + rename) so a crash never leaves a torn checkpoint.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, format!("{}\n{}\n", self.batch_id, self.next))?;
+        fs::rename(tmp, path)
+    }
+}
+
+/// How a `BatchRunner::run` call ended.
+#[derive(Debug)]
+pub enum BatchOutcome {
+    /// All jobs confirmed; the checkpoint file was removed.
+    Completed { sent: usize },
+    /// The printer stopped (paper out, head open, ...). Fix it and call `run` again.
+    /// `unconfirmed` is the job sent just before, which may or may not have printed.
+    Paused { next: usize, status: PrinterStatus, unconfirmed: Option<usize> },
+    /// Transport or status query failed; `next` is the first job not sent.
+    /// `unconfirmed` is a job that was sent but whose status couldn't be read.
+    Failed { next: usize, error: Box<dyn Error>, unconfirmed: Option<usize> },
+}
+
+/// Sends a batch job by job, polling the printer after each one and persisting
+/// the index of the last confirmed label.
+///
+/// A sent job is never resent: `^ee` can't tell a busy printer from one that
+/// didn't print, so when the status after a job isn't `Ready` the checkpoint
+/// moves past it anyway and the outcome names it `unconfirmed`. Check that
+/// label after fixing the printer and reprint it by hand if it is missing.
+pub struct BatchRunner {
+    batch_id: String,
+    path: PathBuf,
+}
+
+impl BatchRunner {
+    /// `batch_id` must be stable across restarts (e.g. the order number); a
+    /// checkpoint of a different batch at `path` is ignored and overwritten.
+    pub fn new(batch_id: &str, path: impl AsRef<Path>) -> Self {
+        BatchRunner { batch_id: batch_id.to_string(), path: path.as_ref().to_path_buf() }
+    }
+
+    /// Index of the first job still to print.
+    pub fn resume_index(&self) -> io::Result<usize> {
+        Ok(match Checkpoint::load(&self.path)? {
+            Some(cp) if cp.batch_id == self.batch_id => cp.next,
+            _ => 0,
+        })
+    }
+
+    pub fn run<T, S>(&self, jobs: &[Vec<u8>], transport: &mut T, status: &mut S) -> io::Result<BatchOutcome>
+    where
+        T: Transport + ?Sized,
+        S: StatusSource + ?Sized,
+    {
+        let start = self.resume_index()?.min(jobs.len());
+        let mut next = start;
+
+        // Don't push anything into a printer that is already stopped
+        match status.status() {
+            Ok(PrinterStatus::Ready) => {}
+            Ok(s) => return self.stop(next, BatchOutcome::Paused { next, status: s, unconfirmed: None }),
+            Err(error) => return self.stop(next, BatchOutcome::Failed { next, error, unconfirmed: None }),
+        }
+
+        while next < jobs.len() {
+            if let Err(error) = transport.send(&jobs[next]) {
+                return self.stop(next, BatchOutcome::Failed { next, error, unconfirmed: None });
+            }
+            // Sent: move past it whatever the printer says, so it is never sent twice
+            next += 1;
+            let unconfirmed = Some(next - 1);
+            match status.status() {
+                Ok(PrinterStatus::Ready) => self.checkpoint(next)?,
+                Ok(s) => return self.stop(next, BatchOutcome::Paused { next, status: s, unconfirmed }),
+                Err(error) => return self.stop(next, BatchOutcome::Failed { next, error, unconfirmed }),
+            }
+        }
+
+        match fs::remove_file(&self.path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        Ok(BatchOutcome::Completed { sent: next - start })
+    }
+
+    fn checkpoint(&self, next: usize) -> io::Result<()> {
+        Checkpoint { batch_id: self.batch_id.clone(), next }.save(&self.path)
+    }
+
+    fn stop(&self, next: usize, outcome: BatchOutcome) -> io::Result<BatchOutcome> {
+        self.checkpoint(next)?;
+        Ok(outcome)
+    }
+}
@@ -1,14 +1,45 @@
 // Label and printer tuning constants
-pub const LABEL_W: u32 = 440;
-pub const LABEL_H: u32 = 320;
+pub const LABEL_W: u32 = 440;          // dots (≈55 mm)
+pub const LABEL_H: u32 = 320;          // dots (≈40 mm)
+pub const LABEL_GAP: u32 = 24;         // gap between labels (Q second parameter)
+pub const SHELF_W: u32 = 480;          // shelf-edge label, dots (60 mm)
+pub const SHELF_H: u32 = 240;          // dots (30 mm)
 
 pub const PAD_RIGHT: u32 = 10;
-pub const FONT_PX: f32 = 44.0;   // larger
-pub const DARKNESS: u8 = 5;      // reduce banding
-pub const SPEED: u8 = 3;
+pub const FONT_PX: f32 = 36.0;         // larger for better readability in 4-product layout
+pub const BRANDLESS_NAME_SCALE: f32 = 1.4; // name/price line of a product printed without a brand
+pub const BOLD_STROKE: bool = true;    // light synthetic bold on the price
+pub const BRAND_LETTER_SPACING: f32 = 0.0; // default BuildOptions::brand_letter_spacing
+pub const JUSTIFY_NAMES: bool = false; // default BuildOptions::justify_names (kashida)
 
-pub const NARROW: u32 = 2;       // EAN-13 module width (2–3)
-pub const HEIGHT: u32 = 50;      // bar height
+pub const DARKNESS: u8 = 8;            // D0..D15 (darker for better contrast like reference)
+pub const SPEED: u8 = 2;               // S1..S6 (slower for better quality)
+
+pub const NARROW: u32 = 2;             // EAN13 module width (back to 2 like reference)
+pub const HEIGHT: u32 = 35;            // barcode bar height (smaller for 4-product layout)
+pub const MIN_BAR_HEIGHT: u32 = 20;    // bars never get shorter than this to make room for dates
+pub const DATE_GAP: u32 = 2;           // dots between name/price and date line, and date line and bars
+pub const HRI_PX: f32 = 22.0;          // rendered barcode digits (Hri::Rendered)
+pub const HRI_GAP: u32 = 2;            // dots between bars and rendered digits
+pub const PLU_PX: f32 = 16.0;          // PLU line under the barcode digits (Product::plu)
+pub const PLU_GAP: u32 = 1;            // dots between barcode digits and the PLU line
+pub const SEPARATOR_DOTS: u32 = 2;     // thickness of the 4-up grid separators (GridStyle)
+
+// Product cell spacing; negative gaps tuck a block up under the one above it
+pub const TWO_UP_TOP_PAD: u32 = 8;     // dots kept clear above each half of the 2-up label
+pub const TWO_UP_TEXT_GAP: i32 = -6;   // 2-up name/price line pulled up under the brand
+pub const TWO_UP_ROW_GAP: i32 = 4;     // extra drop of the bottom half's name/price line under its brand
+pub const TWO_UP_BARCODE_GAP: i32 = 4; // 2-up bars below the name/price (or dates) line
+pub const FOUR_UP_GRID_Y: u32 = 18;    // 2x2 grid moved down to clear the top edge of the stock
+pub const FOUR_UP_TOP_PAD: u32 = 4;    // dots kept clear above each quadrant's brand
+pub const FOUR_UP_TEXT_GAP: i32 = -4;  // 4-up name/price line pulled up under the brand
+pub const FOUR_UP_BARCODE_GAP: i32 = 3; // 4-up bars below the name/price (or dates) line
+pub const FOUR_UP_LEFT_BARCODE_SHIFT: u32 = 4; // left column's bars sit right of center, away from the label edge
 
 pub const FORCE_LANDSCAPE: bool = true; // rotate content in code if driver prints landscape
 pub const INVERT_BITS: bool = true;     // flip GW bits → black text on white
+pub const MIRROR: bool = false;         // default for Label::mirror (mirror-image labels for glass)
+
+pub const MAX_DOT_DENSITY_PERCENT: u32 = 50; // bitmaps darker than this trigger a warning
+pub const MIN_MATRIX_MODULE: u32 = 3;   // 2D module size floor (≈0.375 mm, scannable on LP-2824)
+pub const MAX_JOB_BYTES: usize = 64 * 1024; // one send the LP-2824 buffers safely (JobLimit default)
@@ -0,0 +1,73 @@
+use crate::epl::{count_labels, with_copies};
+use crate::font::FontContext;
+use crate::stamp::Stamper;
+
+/// In-crate serial number for bitmap labels, where the printer's `C` counter
+/// can't reach: the number is rendered into each label through a
+/// `Label::stamp` region (see `number_job`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SerialCounter {
+    /// Next value handed out; `None` once stepping would overflow `i64`,
+    /// which ends the iterator.
+    pub value: Option<i64>,
+    pub step: i64,
+    /// Zero-pad to at least this many digits.
+    pub width: usize,
+    pub prefix: String,
+    pub suffix: String,
+}
+
+impl SerialCounter {
+    pub fn new(start: i64) -> Self {
+        SerialCounter { value: Some(start), step: 1, width: 0, prefix: String::new(), suffix: String::new() }
+    }
+
+    pub fn step(mut self, step: i64) -> Self {
+        self.step = step;
+        self
+    }
+
+    pub fn pad(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.to_string();
+        self
+    }
+
+    pub fn suffix(mut self, suffix: &str) -> Self {
+        self.suffix = suffix.to_string();
+        self
+    }
+
+    /// `value` formatted with this counter's padding, prefix and suffix.
+    pub fn format(&self, value: i64) -> String {
+        let sign = if value < 0 { "-" } else { "" };
+        format!("{}{}{:0w$}{}", self.prefix, sign, value.unsigned_abs(), self.suffix, w = self.width)
+    }
+
+    /// Split `job` into one single-copy job per label it prints (its `P` count),
+    /// each with the `key` stamp filled with the next number. Errors if the
+    /// counter runs out before every label has one.
+    pub fn number_job(&mut self, ctx: &FontContext, job: &[u8], key: &str) -> Result<Vec<Vec<u8>>, String> {
+        let single = with_copies(job, 1);
+        (0..count_labels(job))
+            .map(|i| {
+                let value = self.next().ok_or_else(|| format!("serial counter exhausted after {} labels", i))?;
+                Ok(Stamper::new(ctx).value(key, &value).apply(&single))
+            })
+            .collect()
+    }
+}
+
+impl Iterator for SerialCounter {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        let value = self.value?;
+        self.value = value.checked_add(self.step);
+        Some(self.format(value))
+    }
+}
@@ -0,0 +1,212 @@
+use std::fmt;
+
+use crate::script::{convert_digits, Digits};
+
+/// Calendar date (proleptic Gregorian) for production / expiry fields.
+/// Converts from `chrono::NaiveDate` and `time::Date` with the matching features.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    pub year: i32,
+    pub month: u8,
+    pub day: u8,
+}
+
+impl Date {
+    pub fn new(year: i32, month: u8, day: u8) -> Result<Self, String> {
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            return Err(format!("invalid date {:04}-{:02}-{:02}", year, month, day));
+        }
+        Ok(Date { year, month, day })
+    }
+
+    /// Date `days` after 1970-01-01.
+    pub fn from_unix_days(days: i64) -> Self {
+        // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z - era * 146_097;
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + (month <= 2) as i64;
+        Date { year: year as i32, month: month as u8, day: day as u8 }
+    }
+
+    /// Days since 1970-01-01 (negative before).
+    pub fn unix_days(self) -> i64 {
+        let y = self.year as i64 - (self.month <= 2) as i64;
+        let era = y.div_euclid(400);
+        let yoe = y - era * 400;
+        let m = self.month as i64;
+        let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + self.day as i64 - 1;
+        let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+        era * 146_097 + doe - 719_468
+    }
+
+    /// E.g. expiry from production date and shelf life.
+    pub fn plus_days(self, days: i64) -> Self {
+        Self::from_unix_days(self.unix_days() + days)
+    }
+}
+
+fn days_in_month(year: i32, month: u8) -> u8 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<chrono::NaiveDate> for Date {
+    fn from(d: chrono::NaiveDate) -> Self {
+        use chrono::Datelike;
+        Date { year: d.year(), month: d.month() as u8, day: d.day() as u8 }
+    }
+}
+
+#[cfg(feature = "time")]
+impl From<time::Date> for Date {
+    fn from(d: time::Date) -> Self {
+        Date { year: d.year(), month: d.month() as u8, day: d.day() }
+    }
+}
+
+/// Month names for the `mmmm` pattern token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MonthNames {
+    #[default]
+    English,
+    /// يناير, فبراير, ... (Egypt, Gulf)
+    Arabic,
+    /// كانون الثاني, شباط, ... (Levant, Iraq)
+    Levantine,
+}
+
+const ENGLISH: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June",
+    "July", "August", "September", "October", "November", "December",
+];
+const ARABIC: [&str; 12] = [
+    "يناير", "فبراير", "مارس", "أبريل", "مايو", "يونيو",
+    "يوليو", "أغسطس", "سبتمبر", "أكتوبر", "نوفمبر", "ديسمبر",
+];
+const LEVANTINE: [&str; 12] = [
+    "كانون الثاني", "شباط", "آذار", "نيسان", "أيار", "حزيران",
+    "تموز", "آب", "أيلول", "تشرين الأول", "تشرين الثاني", "كانون الأول",
+];
+
+impl MonthNames {
+    pub fn name(self, month: u8) -> &'static str {
+        let names = match self {
+            MonthNames::English => &ENGLISH,
+            MonthNames::Arabic => &ARABIC,
+            MonthNames::Levantine => &LEVANTINE,
+        };
+        names[(month.clamp(1, 12) - 1) as usize]
+    }
+}
+
+/// Date pattern: `dd`/`d` day, `mm`/`m` month number, `mmmm` month name,
+/// `yyyy`/`yy` year; anything else is copied as is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DateFormat {
+    pub pattern: String,
+    pub month_names: MonthNames,
+    /// Write digits as Arabic-Indic (٠١٢...).
+    pub arabic_digits: bool,
+}
+
+impl Default for DateFormat {
+    fn default() -> Self {
+        DateFormat::new("dd/mm/yyyy")
+    }
+}
+
+impl DateFormat {
+    pub fn new(pattern: &str) -> Self {
+        DateFormat { pattern: pattern.to_string(), month_names: MonthNames::English, arabic_digits: false }
+    }
+
+    pub fn month_names(mut self, names: MonthNames) -> Self {
+        self.month_names = names;
+        self
+    }
+
+    pub fn arabic_digits(mut self, on: bool) -> Self {
+        self.arabic_digits = on;
+        self
+    }
+
+    pub fn format(&self, date: Date) -> String {
+        let mut out = String::new();
+        let mut rest = self.pattern.as_str();
+        while !rest.is_empty() {
+            let tokens: [(&str, String); 7] = [
+                ("yyyy", format!("{:04}", date.year)),
+                ("yy", format!("{:02}", date.year.rem_euclid(100))),
+                ("mmmm", self.month_names.name(date.month).to_string()),
+                ("mm", format!("{:02}", date.month)),
+                ("m", date.month.to_string()),
+                ("dd", format!("{:02}", date.day)),
+                ("d", date.day.to_string()),
+            ];
+            match tokens.iter().find(|(t, _)| rest.starts_with(t)) {
+                Some((t, value)) => {
+                    out.push_str(value);
+                    rest = &rest[t.len()..];
+                }
+                None => {
+                    let c = rest.chars().next().unwrap_or_default();
+                    out.push(c);
+                    rest = &rest[c.len_utf8()..];
+                }
+            }
+        }
+        if self.arabic_digits {
+            out = convert_digits(&out, Digits::ArabicIndic);
+        }
+        out
+    }
+}
+
+/// How the builders print a product's production / expiry dates: one small
+/// line between the name/price line and the barcode.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DateStyle {
+    pub format: DateFormat,
+    pub produced_prefix: String,
+    pub expires_prefix: String,
+    pub font_px: f32,
+}
+
+impl Default for DateStyle {
+    fn default() -> Self {
+        DateStyle {
+            format: DateFormat::default(),
+            produced_prefix: "إنتاج".to_string(),
+            expires_prefix: "انتهاء".to_string(),
+            font_px: 20.0,
+        }
+    }
+}
+
+impl DateStyle {
+    /// Line text for the given dates, `None` if there are none.
+    pub fn line(&self, produced: Option<Date>, expires: Option<Date>) -> Option<String> {
+        let parts: Vec<String> = [(&self.produced_prefix, produced), (&self.expires_prefix, expires)]
+            .into_iter()
+            .filter_map(|(prefix, d)| d.map(|d| format!("{} {}", prefix, self.format.format(d)).trim().to_string()))
+            .collect();
+        (!parts.is_empty()).then(|| parts.join("   "))
+    }
+}
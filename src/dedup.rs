@@ -0,0 +1,47 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::queue::JobId;
+use crate::snapshot::fnv1a;
+
+/// Hashes of recently sent jobs, each forgotten `window` after it was sent.
+/// Backs `transport::dedup` and `JobQueue::dedup_window`.
+#[derive(Debug, Clone)]
+pub struct RecentJobs {
+    window: Duration,
+    sent: VecDeque<(u64, Instant, JobId)>,
+}
+
+impl RecentJobs {
+    pub fn new(window: Duration) -> Self {
+        RecentJobs { window, sent: VecDeque::new() }
+    }
+
+    /// Remember `job` as sent now under `id`.
+    pub fn record(&mut self, job: &[u8], id: JobId) {
+        self.forget_old();
+        self.sent.push_back((fnv1a(job), Instant::now(), id));
+    }
+
+    /// Id of an identical job sent less than `window` ago, and how long ago.
+    pub fn find(&mut self, job: &[u8]) -> Option<(JobId, Duration)> {
+        self.forget_old();
+        let hash = fnv1a(job);
+        self.sent.iter().rev().find(|(h, _, _)| *h == hash).map(|&(_, at, id)| (id, at.elapsed()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.sent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sent.is_empty()
+    }
+
+    // Entries are in send order, so the expired ones are at the front
+    fn forget_old(&mut self) {
+        while self.sent.front().is_some_and(|(_, at, _)| at.elapsed() >= self.window) {
+            self.sent.pop_front();
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use crate::consts::{DARKNESS, HEIGHT, LABEL_GAP, LABEL_H, LABEL_W, NARROW, SPEED};
+use crate::label::{BarcodeCmd, Element, Label, TextCmd};
+
+use image::{GrayImage, Luma};
+
+/// Dots per millimetre at 203 dpi.
+const DOTS_PER_MM: u32 = 8;
+
+// 4x4 ordered-dither thresholds; a cell is black when its threshold < level
+const BAYER: [[u32; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Stock and print settings for the calibration labels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlignmentConfig {
+    pub width: u32,
+    pub height: u32,
+    pub gap: u32,
+    pub darkness: u8,
+    pub speed: u8,
+    /// Grid pitch in dots (default 80 = 10 mm).
+    pub grid_step: u32,
+    /// Printed with the firmware EAN-13 at `NARROW` / `HEIGHT` (12 digits, the printer adds the check digit).
+    pub sample_ean: String,
+}
+
+impl Default for AlignmentConfig {
+    fn default() -> Self {
+        AlignmentConfig {
+            width: LABEL_W,
+            height: LABEL_H,
+            gap: LABEL_GAP,
+            darkness: DARKNESS,
+            speed: SPEED,
+            grid_step: 10 * DOTS_PER_MM,
+            sample_ean: "622123456789".to_string(),
+        }
+    }
+}
+
+/// Test print for new stock: a border box on the label edge, a grid with
+/// millimetre rulers along the top and left, a sample EAN-13 and a strip of
+/// 16 density patches.
+///
+/// EPL2 has a single darkness per form, so the patches are coverage steps
+/// (1/16 .. 16/16 of the dots, numbered 0..15) printed at `config.darkness`:
+/// a good setting keeps the light steps visible and the dark ones apart.
+/// Use `darkness_sweep` to compare the D values themselves.
+pub fn alignment_label(config: &AlignmentConfig) -> Vec<u8> {
+    alignment_form(config).to_epl()
+}
+
+/// `alignment_label` printed once per darkness D0..D15 (16 labels), each
+/// tagged with its `D` value.
+pub fn darkness_sweep(config: &AlignmentConfig) -> Vec<u8> {
+    (0..16u8)
+        .flat_map(|d| {
+            let mut label = alignment_form(&AlignmentConfig { darkness: d, ..config.clone() });
+            let tag = TextCmd { h_mult: 2, v_mult: 2, ..TextCmd::new(config.width / 2 + 40, 3 * DOTS_PER_MM, 3, &format!("D{}", d)) };
+            label.elements.push(Element::Text(tag));
+            label.to_epl()
+        })
+        .collect()
+}
+
+fn alignment_form(config: &AlignmentConfig) -> Label {
+    let (w, h) = (config.width, config.height);
+    let mut label = Label::new(w, h);
+    label.gap = config.gap;
+    label.darkness = Some(config.darkness);
+    label.speed = Some(config.speed);
+
+    // Border on the nominal label edge: any missing side shows the offset
+    label.elements.push(Element::Frame { x: 0, y: 0, x1: w, y1: h, thickness: 2 });
+
+    let bc = BarcodeCmd::ean13(0, 0, NARROW, HEIGHT, &config.sample_ean);
+    let (bc_x, bc_y) = (w.saturating_sub(bc.width()) / 2, (h / 2).saturating_sub(HEIGHT / 2));
+    let margin = 3 * DOTS_PER_MM;
+    let patch_w = (w.saturating_sub(2 * margin) / 16).max(4);
+    let patch_h = 3 * DOTS_PER_MM;
+    let strip_y = h.saturating_sub(margin + patch_h);
+
+    // Grid lines stay out of the barcode (with quiet zones and HRI) and the strip
+    let clear = [
+        (bc_x.saturating_sub(12 * NARROW), bc_y.saturating_sub(4), bc.width() + 24 * NARROW, HEIGHT + 30),
+        (margin, strip_y.saturating_sub(16), 16 * patch_w, patch_h + 16),
+    ];
+
+    // Grid, numbered in mm along the top and left
+    let step = config.grid_step.max(DOTS_PER_MM);
+    for x in (step..w).step_by(step as usize) {
+        line_around(&mut label, x, 0, 1, h, &clear);
+        label.elements.push(Element::Text(TextCmd::new(x + 3, 20, 1, &(x / DOTS_PER_MM).to_string())));
+    }
+    for y in (step..h).step_by(step as usize) {
+        line_around(&mut label, 0, y, w, 1, &clear);
+        label.elements.push(Element::Text(TextCmd::new(20, y + 3, 1, &(y / DOTS_PER_MM).to_string())));
+    }
+
+    // Rulers: 1 mm ticks, longer every 5 mm
+    for mm in 1..w / DOTS_PER_MM {
+        label.line(mm * DOTS_PER_MM, 0, 1, if mm % 5 == 0 { 16 } else { 8 });
+    }
+    for mm in 1..h / DOTS_PER_MM {
+        label.line(0, mm * DOTS_PER_MM, if mm % 5 == 0 { 16 } else { 8 }, 1);
+    }
+
+    label.barcode(BarcodeCmd { x: bc_x, y: bc_y, ..bc });
+
+    // Density strip along the bottom
+    for level in 0..16 {
+        let x = margin + level * patch_w;
+        label.graphic(x, strip_y, density_patch(patch_w - 2, patch_h, level + 1));
+        label.elements.push(Element::Text(TextCmd::new(x, strip_y.saturating_sub(14), 1, &level.to_string())));
+    }
+    label
+}
+
+// Horizontal or vertical line split into `LO` segments that skip the `clear` boxes
+fn line_around(label: &mut Label, x: u32, y: u32, w: u32, h: u32, clear: &[(u32, u32, u32, u32)]) {
+    let vertical = h > w;
+    let (start, end) = if vertical { (y, y + h) } else { (x, x + w) };
+    let mut cuts: Vec<(u32, u32)> = clear.iter()
+        .filter(|&&(cx, cy, cw, ch)| if vertical { (cx..cx + cw).contains(&x) } else { (cy..cy + ch).contains(&y) })
+        .map(|&(cx, cy, cw, ch)| if vertical { (cy, cy + ch) } else { (cx, cx + cw) })
+        .collect();
+    cuts.sort_unstable();
+    let mut pos = start;
+    for (a, b) in cuts.into_iter().chain([(end, end)]) {
+        if a.min(end) > pos {
+            let len = a.min(end) - pos;
+            if vertical { label.line(x, pos, w, len) } else { label.line(pos, y, len, h) }
+        }
+        pos = pos.max(b);
+    }
+}
+
+// `level` of every 16 dots black (4x4 ordered dither)
+fn density_patch(w: u32, h: u32, level: u32) -> GrayImage {
+    GrayImage::from_fn(w, h, |x, y| {
+        Luma([if BAYER[(y % 4) as usize][(x % 4) as usize] < level { 0 } else { 255 }])
+    })
+}
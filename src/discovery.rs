@@ -0,0 +1,93 @@
+use std::collections::HashSet;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+
+use crate::transport::tcp::{TcpPrinter, RAW_PORT};
+
+/// UDP port Zebra print servers listen on for discovery broadcasts.
+pub const DISCOVERY_PORT: u16 = 4201;
+
+// ZebraNet discovery request (v1 "advanced" query)
+const DISCOVERY_PACKET: [u8; 6] = [0x2e, 0x2c, 0x3a, 0x01, 0x00, 0x00];
+
+// Field offsets in the discovery reply
+const PRODUCT_NAME: (usize, usize) = (12, 32);
+const FIRMWARE: (usize, usize) = (39, 49);
+const MAC: (usize, usize) = (54, 60);
+const SERIAL: (usize, usize) = (60, 70);
+const IP: (usize, usize) = (72, 76);
+const SYSTEM_NAME: (usize, usize) = (84, 109);
+
+/// A print server that answered the discovery broadcast.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredPrinter {
+    pub ip: IpAddr,
+    pub model: String,
+    pub serial: String,
+    pub firmware: String,
+    pub mac: String,
+    /// Name configured on the print server (may be empty).
+    pub name: String,
+}
+
+impl DiscoveredPrinter {
+    /// Raw TCP transport on port 9100 of this printer.
+    pub fn transport(&self) -> TcpPrinter {
+        TcpPrinter::new(SocketAddr::new(self.ip, RAW_PORT))
+    }
+}
+
+/// Broadcast a discovery request and collect replies until `timeout` elapses.
+/// Each printer is reported once even if several interfaces answer.
+pub fn find_printers(timeout: Duration) -> io::Result<Vec<DiscoveredPrinter>> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+    socket.set_broadcast(true)?;
+    socket.send_to(&DISCOVERY_PACKET, (Ipv4Addr::BROADCAST, DISCOVERY_PORT))?;
+
+    let deadline = Instant::now() + timeout;
+    let mut seen = HashSet::new();
+    let mut found = Vec::new();
+    let mut buf = [0u8; 1024];
+
+    while let Some(left) = deadline.checked_duration_since(Instant::now()).filter(|d| !d.is_zero()) {
+        socket.set_read_timeout(Some(left))?;
+        let (n, from) = match socket.recv_from(&mut buf) {
+            Ok(r) => r,
+            Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => break,
+            Err(e) => return Err(e),
+        };
+        if let Some(p) = parse_reply(&buf[..n], from.ip()) {
+            if seen.insert((p.serial.clone(), p.ip)) {
+                found.push(p);
+            }
+        }
+    }
+    Ok(found)
+}
+
+/// Decode one discovery reply; `from` is used when the packet carries no IP.
+pub fn parse_reply(data: &[u8], from: IpAddr) -> Option<DiscoveredPrinter> {
+    if data.len() < IP.1 {
+        return None;
+    }
+    let ip = match &data[IP.0..IP.1] {
+        [0, 0, 0, 0] => from,
+        b => IpAddr::V4(Ipv4Addr::new(b[0], b[1], b[2], b[3])),
+    };
+    let mac = data[MAC.0..MAC.1].iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(":");
+    Some(DiscoveredPrinter {
+        ip,
+        model: field(data, PRODUCT_NAME),
+        serial: field(data, SERIAL),
+        firmware: field(data, FIRMWARE),
+        mac,
+        name: field(data, SYSTEM_NAME),
+    })
+}
+
+// NUL/space padded ASCII field; missing bytes (short packets) read as empty
+fn field(data: &[u8], (start, end): (usize, usize)) -> String {
+    let bytes = data.get(start..end.min(data.len())).unwrap_or(&[]);
+    String::from_utf8_lossy(bytes).trim_matches(|c: char| c == '\0' || c.is_whitespace()).to_string()
+}
@@ -0,0 +1,128 @@
+use image::GrayImage;
+use rusttype::Scale;
+
+use crate::font::{FontContext, TextStyle};
+use crate::label::Label;
+use crate::layout::{Block, Cell};
+
+/// What a `RenderElement` is drawn with: the label fonts and the space it
+/// may fill.
+#[derive(Clone, Copy)]
+pub struct RenderCtx<'c, 'a> {
+    pub font: &'c FontContext<'a>,
+    /// Room available to the element in dots (the label, or a cell's padded area).
+    pub max_width: u32,
+    pub max_height: u32,
+}
+
+impl<'c, 'a> RenderCtx<'c, 'a> {
+    pub fn new(font: &'c FontContext<'a>, max_width: u32, max_height: u32) -> Self {
+        RenderCtx { font, max_width, max_height }
+    }
+}
+
+/// App-specific widget (nutrition grid, tax stamp, ...) drawn in Rust and
+/// sent as a `GW` bitmap next to the crate's own text and barcodes.
+///
+/// ```no_run
+/// # use image::{GrayImage, Luma};
+/// # use zebra_epl2_printer::element::{RenderCtx, RenderElement};
+/// struct Checkbox(u32);
+/// impl RenderElement for Checkbox {
+///     fn size(&self, _: &RenderCtx) -> (u32, u32) { (self.0, self.0) }
+///     fn raster(&self, _: &RenderCtx) -> GrayImage {
+///         let n = self.0;
+///         GrayImage::from_fn(n, n, |x, y| Luma([if x < 2 || y < 2 || x + 2 >= n || y + 2 >= n { 0 } else { 255 }]))
+///     }
+/// }
+/// ```
+pub trait RenderElement: Send + Sync {
+    /// Size `raster` will draw at (w, h), used to lay the element out.
+    fn size(&self, ctx: &RenderCtx) -> (u32, u32);
+    /// 1-bit image (luma < 128 is black), `size` large.
+    fn raster(&self, ctx: &RenderCtx) -> GrayImage;
+
+    /// Baseline offset from the top, for `AlignV::Baseline` cells.
+    fn baseline(&self, _ctx: &RenderCtx) -> Option<u32> {
+        None
+    }
+}
+
+/// A ready bitmap.
+impl RenderElement for GrayImage {
+    fn size(&self, _: &RenderCtx) -> (u32, u32) {
+        self.dimensions()
+    }
+
+    fn raster(&self, _: &RenderCtx) -> GrayImage {
+        self.clone()
+    }
+}
+
+/// One line of text in the label font (`FontContext::render_line_styled`).
+#[derive(Debug, Clone)]
+pub struct TextLine {
+    pub text: String,
+    pub font_px: f32,
+    pub style: TextStyle,
+}
+
+impl TextLine {
+    pub fn new(text: &str, font_px: f32) -> Self {
+        TextLine { text: text.to_string(), font_px, style: TextStyle::default() }
+    }
+
+    pub fn style(mut self, style: TextStyle) -> Self {
+        self.style = style;
+        self
+    }
+}
+
+impl RenderElement for TextLine {
+    fn size(&self, ctx: &RenderCtx) -> (u32, u32) {
+        self.raster(ctx).dimensions()
+    }
+
+    fn raster(&self, ctx: &RenderCtx) -> GrayImage {
+        ctx.font.render_line_cached(&self.text, self.font_px, 0, &self.style).image.clone()
+    }
+
+    fn baseline(&self, ctx: &RenderCtx) -> Option<u32> {
+        Some(ctx.font.font().v_metrics(Scale::uniform(self.font_px)).ascent.ceil() as u32)
+    }
+}
+
+impl Label {
+    /// Rasterize `element` with the whole label as its room and place it at
+    /// `(x, y)`. Returns the placed size.
+    pub fn element(&mut self, x: u32, y: u32, element: &dyn RenderElement, font: &FontContext) -> (u32, u32) {
+        let ctx = RenderCtx::new(font, self.width.saturating_sub(x), self.height.saturating_sub(y));
+        let image = element.raster(&ctx);
+        let size = image.dimensions();
+        self.graphic(x, y, image);
+        size
+    }
+
+    /// Stack `elements` in `cell` (see `Cell::place`), each given the cell's
+    /// padded area as its room. Returns their top-left corners; elements
+    /// moved onto the label are recorded in `clamped`.
+    pub fn stack(&mut self, cell: &Cell, elements: &[&dyn RenderElement], font: &FontContext) -> Vec<(u32, u32)> {
+        let (_, _, w, h) = cell.inner();
+        let ctx = RenderCtx::new(font, w, h);
+        let blocks: Vec<Block> = elements.iter()
+            .map(|e| {
+                let (ew, eh) = e.size(&ctx);
+                let block = Block::new(ew, eh);
+                match e.baseline(&ctx) {
+                    Some(b) => block.with_baseline(b),
+                    None => block,
+                }
+            })
+            .collect();
+        let at = cell.offsets(&blocks);
+        for (e, &(x, y)) in elements.iter().zip(&at) {
+            self.graphic_at(x, y, e.raster(&ctx));
+        }
+        at.into_iter().map(|(x, y)| (x.max(0) as u32, y.max(0) as u32)).collect()
+    }
+}
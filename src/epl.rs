@@ -1,5 +1,9 @@
 use image::ImageBuffer;
 use image::Luma;
+use std::fmt;
+use std::ops::Range;
+
+use crate::barcode::Symbology;
 use crate::consts::INVERT_BITS;
 
 /// Helper to append an EPL ASCII command line terminated with CRLF
@@ -13,7 +17,7 @@ pub fn epl_line(buf: &mut Vec<u8>, s: &str) {
 pub fn image_to_row_bytes(img: &ImageBuffer<Luma<u8>, Vec<u8>>) -> (u32, u32, Vec<u8>) {
     let w = img.width();
     let h = img.height();
-    let bpr = ((w + 7) / 8) as usize;
+    let bpr = w.div_ceil(8) as usize;
     let mut out = vec![0u8; bpr * h as usize];
 
     for y in 0..h as usize {
@@ -33,7 +37,7 @@ pub fn image_to_row_bytes(img: &ImageBuffer<Luma<u8>, Vec<u8>>) -> (u32, u32, Ve
 
 /// Append GW header + raw binary rows + CRLF
 pub fn gw_bytes(buf: &mut Vec<u8>, x: u32, y: u32, w: u32, h: u32, rows: &[u8]) {
-    let bpr = ((w + 7) / 8) as usize;
+    let bpr = w.div_ceil(8) as usize;
     epl_line(buf, &format!("GW{},{},{},{}", x, y, bpr, h));
     buf.extend_from_slice(rows);
     buf.extend_from_slice(b"\r\n");
@@ -47,7 +51,7 @@ pub fn image_to_gw(x: u32, y: u32, img: &GrayImage) -> Vec<u8> {
     let height = img.height();
 
     // bytes per row (8 pixels per byte)
-    let bytes_per_row = ((width + 7) / 8) as usize;
+    let bytes_per_row = width.div_ceil(8) as usize;
     let mut data: Vec<u8> = Vec::new();
 
     // Header: GWx,y,bytes_per_row,height (EPL expects bytes-per-row then height)
@@ -74,3 +78,356 @@ pub fn image_to_gw(x: u32, y: u32, img: &GrayImage) -> Vec<u8> {
 
     data
 }
+
+/// Split a job into its ASCII command lines (without CRLF), skipping the binary
+/// payload that follows each `GW` header.
+pub fn command_lines(job: &[u8]) -> Vec<String> {
+    command_spans(job).into_iter().map(|(_, line)| line).collect()
+}
+
+/// Like `command_lines`, with the byte range each command occupies in `job`
+/// (CRLF and, for `GW`, the binary payload included).
+pub fn command_spans(job: &[u8]) -> Vec<(Range<usize>, String)> {
+    let mut out = Vec::new();
+    let mut pos = 0;
+    while pos < job.len() {
+        let start = pos;
+        // `ES` has no line break before its binary glyphs; take it whole
+        if let Some(len) = crate::softfont::command_len(&job[pos..]) {
+            pos = (pos + len).min(job.len());
+            out.push((start..pos, String::from_utf8_lossy(&job[start..start + 3]).into_owned()));
+            continue;
+        }
+        let end = job[pos..].windows(2).position(|w| w == b"\r\n").map_or(job.len(), |i| pos + i);
+        let line = String::from_utf8_lossy(&job[pos..end]).into_owned();
+        pos = (end + 2).min(job.len());
+
+        if let Some(args) = line.strip_prefix("GW") {
+            let p: Vec<usize> = args.split(',').filter_map(|a| a.trim().parse().ok()).collect();
+            if p.len() == 4 {
+                // payload + its trailing CRLF
+                pos = (pos + p[2] * p[3] + 2).min(job.len());
+            }
+        }
+        out.push((start..pos, line));
+    }
+    out
+}
+
+/// Copy of `job` with every `GW` bitmap redrawn as solid `LO` rectangles, so
+/// the job is plain 7-bit ASCII and survives print paths that mangle binary
+/// data. EPL2 has no hex form of `GW` (that's ZPL's `^GF`), hence the lines:
+/// horizontal runs of black dots, merged with identical runs on the rows below.
+/// Text-heavy bitmaps produce many commands; solid areas stay compact.
+pub fn gw_to_lines(job: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(job.len());
+    for (span, line) in command_spans(job) {
+        let Some(args) = line.strip_prefix("GW") else {
+            out.extend_from_slice(&job[span]);
+            continue;
+        };
+        let p: Vec<usize> = args.split(',').filter_map(|a| a.trim().parse().ok()).collect();
+        let start = span.start + line.len() + 2;
+        let (x, y, bpr, rows) = match p[..] {
+            [x, y, bpr, rows] if start + bpr * rows <= job.len() => (x, y, bpr, rows),
+            _ => {
+                out.extend_from_slice(&job[span]);
+                continue;
+            }
+        };
+        let payload = &job[start..start + bpr * rows];
+
+        // Open rectangles: (x0, x1) run -> first row
+        let mut open: Vec<((usize, usize), usize)> = Vec::new();
+        for row in 0..=rows {
+            let runs = if row < rows { black_runs(&payload[row * bpr..(row + 1) * bpr]) } else { Vec::new() };
+            open.retain(|&(run, top)| {
+                let continues = runs.contains(&run);
+                if !continues {
+                    epl_line(&mut out, &format!("LO{},{},{},{}", x + run.0, y + top, run.1 - run.0, row - top));
+                }
+                continues
+            });
+            for run in runs {
+                if !open.iter().any(|&(r, _)| r == run) {
+                    open.push((run, row));
+                }
+            }
+        }
+    }
+    out
+}
+
+// Black dot runs [x0, x1) of one packed GW row (bits inverted when INVERT_BITS is set)
+fn black_runs(row: &[u8]) -> Vec<(usize, usize)> {
+    let black = |i: usize| (row[i / 8] & (0x80 >> (i % 8)) != 0) != INVERT_BITS;
+    let mut runs = Vec::new();
+    let mut i = 0;
+    while i < row.len() * 8 {
+        if black(i) {
+            let x0 = i;
+            while i < row.len() * 8 && black(i) {
+                i += 1;
+            }
+            runs.push((x0, i));
+        }
+        i += 1;
+    }
+    runs
+}
+
+/// Top-of-form backup (`JF`/`JB`/`JC`). With backup enabled the printer pulls
+/// each finished label back to the print line before the next one; disabling it
+/// lets a multi-label job feed continuously.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormBackup {
+    /// `JF`, the printer default.
+    Enabled,
+    /// `JB`: no backup in normal (tear-off) mode.
+    Disabled,
+    /// `JC`: no backup in any mode, including peel and cutter.
+    DisabledAllModes,
+}
+
+impl FormBackup {
+    pub fn command(self) -> &'static str {
+        match self {
+            FormBackup::Enabled => "JF",
+            FormBackup::Disabled => "JB",
+            FormBackup::DisabledAllModes => "JC",
+        }
+    }
+}
+
+/// What happens to a printed label (`O` hardware options). `O` replaces all
+/// options at once, so a job sends exactly one of these.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Delivery {
+    /// `O`: cutter and dispenser off; labels stop at the tear bar.
+    #[default]
+    TearOff,
+    /// `OP`: peel each label off the liner at the dispenser; the next one
+    /// prints once it has been taken.
+    Dispense,
+    /// `OC`: cut after every label.
+    Cut,
+    /// `OC<n>`: cut after every `n` labels (1..=255).
+    CutEvery(u8),
+    /// `OCb`: cut after the last label of each `P` batch.
+    CutBatch,
+}
+
+impl Delivery {
+    pub fn command(self) -> String {
+        match self {
+            Delivery::TearOff => "O".to_string(),
+            Delivery::Dispense => "OP".to_string(),
+            Delivery::Cut => "OC".to_string(),
+            Delivery::CutEvery(n) => format!("OC{}", n.max(1)),
+            Delivery::CutBatch => "OCb".to_string(),
+        }
+    }
+
+    fn from_command(line: &str) -> Option<Delivery> {
+        Some(match line.strip_prefix('O')? {
+            "" => Delivery::TearOff,
+            "P" => Delivery::Dispense,
+            "C" => Delivery::Cut,
+            "Cb" => Delivery::CutBatch,
+            n => Delivery::CutEvery(n.strip_prefix('C')?.parse().ok()?),
+        })
+    }
+}
+
+/// Range of the `f` stop position; 100 is the printer's own position, lower
+/// values stop the label short of the tear bar / cutter, higher ones past it.
+pub const STOP_POSITION: std::ops::RangeInclusive<u8> = 70..=130;
+
+/// Copy of `job` starting with the `mode` command (replacing any `J` command
+/// already at its start), for jobs from the builders or a batch.
+pub fn with_form_backup(job: &[u8], mode: FormBackup) -> Vec<u8> {
+    let skip = command_spans(job).into_iter()
+        .take_while(|(_, l)| matches!(l.as_str(), "JF" | "JB" | "JC"))
+        .last()
+        .map_or(0, |(span, _)| span.end);
+    let mut out = Vec::with_capacity(job.len() + 4);
+    epl_line(&mut out, mode.command());
+    out.extend_from_slice(&job[skip..]);
+    out
+}
+
+/// Number of physical labels the job prints (sum of its `P` commands).
+pub fn count_labels(job: &[u8]) -> u32 {
+    command_lines(job).iter()
+        .filter_map(|l| l.strip_prefix('P'))
+        .filter_map(|n| n.split(',').next()?.trim().parse::<u32>().ok())
+        .sum()
+}
+
+/// Copy of `job` with its last `P` command changed to print `copies` labels.
+/// Jobs without a `P` command are returned unchanged.
+pub fn with_copies(job: &[u8], copies: u32) -> Vec<u8> {
+    let spans = command_spans(job);
+    let Some((span, _)) = spans.iter().rev().find(|(_, l)| l.starts_with('P')) else {
+        return job.to_vec();
+    };
+    let mut out = job[..span.start].to_vec();
+    epl_line(&mut out, &format!("P{}", copies));
+    out.extend_from_slice(&job[span.end..]);
+    out
+}
+
+/// One decoded command of a job (see `parse`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EplCommand {
+    /// `N`: clear the image buffer.
+    Clear,
+    /// `q`: label width in dots.
+    Width(u32),
+    /// `Q`: form length and gap (0 = continuous stock).
+    Length { height: u32, gap: u32 },
+    /// `D`: darkness 0..15.
+    Density(u32),
+    /// `S`: speed.
+    Speed(u32),
+    FormBackup(FormBackup),
+    /// `O` hardware options.
+    Delivery(Delivery),
+    /// `f`: stop position for tear-off, dispenser and cutter.
+    StopPosition(u32),
+    /// `GW` header; the payload itself is skipped, `payload_len` is what the job holds.
+    Graphic { x: u32, y: u32, bytes_per_row: u32, rows: u32, payload_len: usize },
+    /// `B`; `symbology` is `None` for codes the crate doesn't emit.
+    Barcode { x: u32, y: u32, rotation: u32, code: String, symbology: Option<Symbology>, narrow: u32, wide: u32, height: u32, hri: bool, data: String },
+    /// `A` in a resident font.
+    Text { x: u32, y: u32, rotation: u32, font: String, h_mult: u32, v_mult: u32, reverse: bool, data: String },
+    /// `LO` solid rectangle.
+    Line { x: u32, y: u32, width: u32, height: u32 },
+    /// `X` box outline.
+    Frame { x: u32, y: u32, thickness: u32, x1: u32, y1: u32 },
+    /// `P`: print the form `copies` times.
+    Print { copies: u32 },
+    /// `;` line, e.g. an unresolved stamp placeholder.
+    Comment(String),
+    /// Anything else (stored forms, counters, ...), as sent.
+    Other(String),
+}
+
+impl fmt::Display for EplCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EplCommand::Clear => write!(f, "N"),
+            EplCommand::Width(w) => write!(f, "q{}", w),
+            EplCommand::Length { height, gap } => write!(f, "Q{},{}", height, gap),
+            EplCommand::Density(d) => write!(f, "D{}", d),
+            EplCommand::Speed(s) => write!(f, "S{}", s),
+            EplCommand::FormBackup(m) => write!(f, "{}", m.command()),
+            EplCommand::Delivery(d) => write!(f, "{}", d.command()),
+            EplCommand::StopPosition(p) => write!(f, "f{}", p),
+            EplCommand::Graphic { x, y, bytes_per_row, rows, payload_len } =>
+                write!(f, "GW{},{},{},{} <{} bytes>", x, y, bytes_per_row, rows, payload_len),
+            EplCommand::Barcode { x, y, rotation, code, narrow, wide, height, hri, data, .. } =>
+                write!(f, "B{},{},{},{},{},{},{},{},{:?}", x, y, rotation, code, narrow, wide, height, if *hri { "B" } else { "N" }, data),
+            EplCommand::Text { x, y, rotation, font, h_mult, v_mult, reverse, data } =>
+                write!(f, "A{},{},{},{},{},{},{},{:?}", x, y, rotation, font, h_mult, v_mult, if *reverse { "R" } else { "N" }, data),
+            EplCommand::Line { x, y, width, height } => write!(f, "LO{},{},{},{}", x, y, width, height),
+            EplCommand::Frame { x, y, thickness, x1, y1 } => write!(f, "X{},{},{},{},{}", x, y, thickness, x1, y1),
+            EplCommand::Print { copies } => write!(f, "P{}", copies),
+            EplCommand::Comment(c) | EplCommand::Other(c) => write!(f, "{}", c),
+        }
+    }
+}
+
+/// Decode a job into its commands, in order. Malformed lines come back as
+/// `Other` rather than failing, so any job can be inspected.
+pub fn parse(job: &[u8]) -> Vec<EplCommand> {
+    command_spans(job).into_iter().map(|(span, line)| {
+        parse_line(&line, span.len()).unwrap_or(EplCommand::Other(line))
+    }).collect()
+}
+
+fn parse_line(line: &str, span_len: usize) -> Option<EplCommand> {
+    let nums = |args: &str| -> Option<Vec<u32>> { args.split(',').map(|a| a.trim().parse().ok()).collect() };
+    Some(match line {
+        "N" => EplCommand::Clear,
+        "JF" => EplCommand::FormBackup(FormBackup::Enabled),
+        "JB" => EplCommand::FormBackup(FormBackup::Disabled),
+        "JC" => EplCommand::FormBackup(FormBackup::DisabledAllModes),
+        _ if line.starts_with(';') => EplCommand::Comment(line.to_string()),
+        _ if line.starts_with('O') => EplCommand::Delivery(Delivery::from_command(line)?),
+        _ if line.starts_with('f') => EplCommand::StopPosition(line[1..].trim().parse().ok()?),
+        _ if line.starts_with("GW") => {
+            let p = nums(&line[2..])?;
+            let [x, y, bytes_per_row, rows] = p[..] else { return None };
+            // Span = header + CRLF + payload + CRLF
+            let payload_len = span_len.saturating_sub(line.len() + 4);
+            EplCommand::Graphic { x, y, bytes_per_row, rows, payload_len }
+        }
+        _ if line.starts_with("LO") => {
+            let [x, y, width, height] = nums(&line[2..])?[..] else { return None };
+            EplCommand::Line { x, y, width, height }
+        }
+        _ if line.starts_with('q') => EplCommand::Width(line[1..].trim().parse().ok()?),
+        _ if line.starts_with('Q') => {
+            let p = nums(&line[1..])?;
+            EplCommand::Length { height: *p.first()?, gap: *p.get(1)? }
+        }
+        _ if line.starts_with('D') => EplCommand::Density(line[1..].trim().parse().ok()?),
+        _ if line.starts_with('S') => EplCommand::Speed(line[1..].trim().parse().ok()?),
+        _ if line.starts_with('P') => EplCommand::Print { copies: line[1..].split(',').next()?.trim().parse().ok()? },
+        _ if line.starts_with('X') => {
+            let [x, y, thickness, x1, y1] = nums(&line[1..])?[..] else { return None };
+            EplCommand::Frame { x, y, thickness, x1, y1 }
+        }
+        _ if line.starts_with('B') => {
+            let p: Vec<&str> = line[1..].splitn(9, ',').collect();
+            let [x, y, rotation, code, narrow, wide, height, hri, data] = p[..] else { return None };
+            EplCommand::Barcode {
+                x: x.trim().parse().ok()?,
+                y: y.trim().parse().ok()?,
+                rotation: rotation.trim().parse().ok()?,
+                code: code.to_string(),
+                symbology: Symbology::from_epl_code(code),
+                narrow: narrow.trim().parse().ok()?,
+                wide: wide.trim().parse().ok()?,
+                height: height.trim().parse().ok()?,
+                hri: hri == "B",
+                data: unquote(data),
+            }
+        }
+        _ if line.starts_with('A') => {
+            let p: Vec<&str> = line[1..].splitn(8, ',').collect();
+            let [x, y, rotation, font, h_mult, v_mult, reverse, data] = p[..] else { return None };
+            EplCommand::Text {
+                x: x.trim().parse().ok()?,
+                y: y.trim().parse().ok()?,
+                rotation: rotation.trim().parse().ok()?,
+                font: font.to_string(),
+                h_mult: h_mult.trim().parse().ok()?,
+                v_mult: v_mult.trim().parse().ok()?,
+                reverse: reverse == "R",
+                data: unquote(data),
+            }
+        }
+        _ => return None,
+    })
+}
+
+// Contents of a quoted data field with `\"` and `\\` resolved; whatever
+// follows the closing quote (e.g. a counter `C0`) is kept as is
+fn unquote(field: &str) -> String {
+    let Some(rest) = field.strip_prefix('"') else { return field.to_string() };
+    let mut out = String::new();
+    let mut chars = rest.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => out.extend(chars.next().map(|(_, e)| e)),
+            '"' => {
+                out.push_str(&rest[i + 1..]);
+                return out;
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
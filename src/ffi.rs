@@ -0,0 +1,190 @@
+//! C ABI for the label builders (feature `ffi`), for hosts such as Delphi or
+//! C++ POS software that load the crate as a cdylib. The matching header is
+//! `include/zebra_epl2_printer.h` (regenerate with `cbindgen --config cbindgen.toml`).
+//!
+//! Functions return `ZEBRA_OK` (0) or a negative error code; the message of the
+//! last failure on the calling thread is available from `zebra_last_error`.
+//! Buffers handed out by the library must be released with `zebra_free_buffer`.
+
+use std::cell::RefCell;
+use std::error::Error;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+use crate::font::FontContext;
+use crate::product::Product;
+use crate::transport::Transport;
+
+pub const ZEBRA_OK: i32 = 0;
+/// A required pointer was null or a string was not UTF-8.
+pub const ZEBRA_ERR_ARGUMENT: i32 = -1;
+/// The font could not be parsed.
+pub const ZEBRA_ERR_FONT: i32 = -2;
+/// The printer could not be reached or refused the job.
+pub const ZEBRA_ERR_SEND: i32 = -3;
+/// Unexpected internal failure (a caught panic).
+pub const ZEBRA_ERR_INTERNAL: i32 = -4;
+
+/// Bytes owned by the library.
+#[repr(C)]
+pub struct ZebraBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+/// One product cell; all strings are NUL-terminated UTF-8.
+#[repr(C)]
+pub struct ZebraProduct {
+    pub name: *const c_char,
+    pub price: *const c_char,
+    pub barcode: *const c_char,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn fail(code: i32, msg: impl ToString) -> i32 {
+    let msg = CString::new(msg.to_string().replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(msg));
+    code
+}
+
+// Run `f` without letting a panic cross the C boundary
+fn guarded(f: impl FnOnce() -> Result<(), (i32, String)>) -> i32 {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => ZEBRA_OK,
+        Ok(Err((code, msg))) => fail(code, msg),
+        Err(_) => fail(ZEBRA_ERR_INTERNAL, "internal error"),
+    }
+}
+
+unsafe fn text<'a>(p: *const c_char, what: &str) -> Result<&'a str, (i32, String)> {
+    if p.is_null() {
+        return Err((ZEBRA_ERR_ARGUMENT, format!("{} is null", what)));
+    }
+    CStr::from_ptr(p).to_str().map_err(|_| (ZEBRA_ERR_ARGUMENT, format!("{} is not UTF-8", what)))
+}
+
+unsafe fn bytes<'a>(p: *const u8, len: usize, what: &str) -> Result<&'a [u8], (i32, String)> {
+    if p.is_null() {
+        return Err((ZEBRA_ERR_ARGUMENT, format!("{} is null", what)));
+    }
+    Ok(std::slice::from_raw_parts(p, len))
+}
+
+unsafe fn products<const N: usize>(p: *const ZebraProduct) -> Result<[Product; N], (i32, String)> {
+    if p.is_null() {
+        return Err((ZEBRA_ERR_ARGUMENT, "products is null".to_string()));
+    }
+    let cells = std::slice::from_raw_parts(p, N);
+    let mut out: [Product; N] = std::array::from_fn(|_| Product::default());
+    for (i, c) in cells.iter().enumerate() {
+        out[i] = Product::new(
+            text(c.name, &format!("products[{}].name", i))?,
+            text(c.price, &format!("products[{}].price", i))?,
+            text(c.barcode, &format!("products[{}].barcode", i))?,
+        );
+    }
+    Ok(out)
+}
+
+fn hand_out(bytes: Vec<u8>, out: &mut ZebraBuffer) {
+    let boxed = bytes.into_boxed_slice();
+    out.len = boxed.len();
+    out.data = Box::into_raw(boxed) as *mut u8;
+}
+
+unsafe fn build<const N: usize>(
+    font: *const u8,
+    font_len: usize,
+    brand: *const c_char,
+    cells: *const ZebraProduct,
+    out: *mut ZebraBuffer,
+    build: fn(&FontContext, &str, &[Product; N]) -> crate::LabelJob,
+) -> i32 {
+    guarded(|| {
+        let out = out.as_mut().ok_or((ZEBRA_ERR_ARGUMENT, "out is null".to_string()))?;
+        *out = ZebraBuffer { data: ptr::null_mut(), len: 0 };
+        let ctx = FontContext::new(bytes(font, font_len, "font")?).map_err(|e| (ZEBRA_ERR_FONT, e))?;
+        let job = build(&ctx, text(brand, "brand")?, &products::<N>(cells)?);
+        hand_out(job.bytes, out);
+        Ok(())
+    })
+}
+
+/// Build the two-product label (`build_two_product_job`) into `out`.
+///
+/// # Safety
+/// `font` must point to `font_len` readable bytes, `brand` to a NUL-terminated
+/// string, `products` to 2 `ZebraProduct`s and `out` to a writable `ZebraBuffer`.
+#[no_mangle]
+pub unsafe extern "C" fn zebra_build_two_product_label(
+    font: *const u8,
+    font_len: usize,
+    brand: *const c_char,
+    products: *const ZebraProduct,
+    out: *mut ZebraBuffer,
+) -> i32 {
+    build::<2>(font, font_len, brand, products, out, crate::build_two_product_job)
+}
+
+/// Build the four-product label (`build_four_product_job`) into `out`.
+///
+/// # Safety
+/// As `zebra_build_two_product_label`, with `products` pointing to 4 entries.
+#[no_mangle]
+pub unsafe extern "C" fn zebra_build_four_product_label(
+    font: *const u8,
+    font_len: usize,
+    brand: *const c_char,
+    products: *const ZebraProduct,
+    out: *mut ZebraBuffer,
+) -> i32 {
+    build::<4>(font, font_len, brand, products, out, crate::build_four_product_job)
+}
+
+/// Send `len` bytes to `printer`: a device path such as `/dev/usb/lp0`, a
+/// network printer `ip` or `host:port` (raw port 9100 by default), or any other
+/// name as a Windows printer (with the `windows-spooler` feature) or host name.
+///
+/// # Safety
+/// `printer` must be a NUL-terminated string and `data` point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn zebra_send_raw(printer: *const c_char, data: *const u8, len: usize) -> i32 {
+    guarded(|| {
+        let printer = text(printer, "printer")?;
+        let data = bytes(data, len, "data")?;
+        send(printer, data).map_err(|e| (ZEBRA_ERR_SEND, e.to_string()))
+    })
+}
+
+fn send(printer: &str, data: &[u8]) -> Result<(), Box<dyn Error>> {
+    if printer.starts_with('/') {
+        return crate::transport::lp_device(printer).send(data);
+    }
+    #[cfg(all(target_os = "windows", feature = "windows-spooler"))]
+    if !printer.contains(':') && printer.parse::<std::net::IpAddr>().is_err() {
+        return crate::printer::send_raw_to_printer(printer, data);
+    }
+    crate::transport::tcp::printer(printer)?.send(data)
+}
+
+/// Release a buffer returned by the library; null buffers are ignored.
+///
+/// # Safety
+/// `buffer` must come from this library and not have been freed before.
+#[no_mangle]
+pub unsafe extern "C" fn zebra_free_buffer(buffer: ZebraBuffer) {
+    if !buffer.data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(buffer.data, buffer.len)));
+    }
+}
+
+/// Message of the last failed call on this thread (NUL-terminated UTF-8), or
+/// null. Valid until the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn zebra_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ptr::null(), |m| m.as_ptr()))
+}
@@ -0,0 +1,458 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use image::{GrayImage, Luma};
+use rusttype::{point, Font, GlyphId, Point, PositionedGlyph, Scale};
+
+use crate::cache::{CacheStats, RenderCache, RenderKey, RenderedText, DEFAULT_CAPACITY};
+use crate::script::{convert_digits, Digits};
+use crate::shaping::{strip_diacritics, Diacritics, ReshaperShaper, ShapedGlyph, Shaper};
+
+/// Synthetic emboldening, applied as a dilation of the glyph coverage bitmap.
+/// The stroke grows right (and, for `Heavy`, down) in proportion to the font size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum BoldStrength {
+    #[default]
+    Off,
+    /// ~1 dot per 40 px horizontally.
+    Light,
+    /// ~1 dot per 20 px horizontally and half that vertically.
+    Heavy,
+}
+
+impl BoldStrength {
+    /// Dilation (dx, dy) in dots at `font_px`.
+    pub fn extent(self, font_px: f32) -> (u32, u32) {
+        match self {
+            BoldStrength::Off => (0, 0),
+            BoldStrength::Light => ((font_px / 40.0).round().max(1.0) as u32, 0),
+            BoldStrength::Heavy => {
+                let dx = (font_px / 20.0).round().max(2.0) as u32;
+                (dx, (dx / 2).max(1))
+            }
+        }
+    }
+}
+
+/// How glyph outlines are turned into 1-bit dots.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextStyle {
+    /// Coverage above which a dot is black (0.0..1.0).
+    pub threshold: f32,
+    pub bold_strength: BoldStrength,
+    /// Shear glyphs to the right above the baseline (~11°).
+    pub synthetic_italic: bool,
+    /// Extra dots between neighbouring Latin glyphs (Arabic joins are never split).
+    pub letter_spacing: f32,
+    /// Print only the glyph outlines, this many dots thick: hollow letters for
+    /// large banner text, readable with a fraction of the heat.
+    pub outline: Option<u32>,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        TextStyle { threshold: 0.5, bold_strength: BoldStrength::Off, synthetic_italic: false, letter_spacing: 0.0, outline: None }
+    }
+}
+
+impl TextStyle {
+    pub fn bold(strength: BoldStrength) -> Self {
+        TextStyle { bold_strength: strength, ..Default::default() }
+    }
+
+    /// Hollow glyphs with a `stroke` dots thick outline (at least 1).
+    pub fn outline(stroke: u32) -> Self {
+        TextStyle { outline: Some(stroke.max(1)), ..Default::default() }
+    }
+}
+
+const ITALIC_SLANT: f32 = 0.2;
+
+const TATWEEL: char = '\u{0640}';
+
+// Arabic letters that only join to the preceding letter (no kashida after them)
+const RIGHT_JOINING: &str = "اأإآٱدذرزوؤةء";
+
+// Erosion of a w x h mask by a (2r+1)-square, done as two 1-D passes; dots
+// outside the mask count as white, so glyphs touching the edge keep a stroke there
+fn erode(mask: &[bool], w: u32, h: u32, r: u32) -> Vec<bool> {
+    let (w, h, r) = (w as usize, h as usize, r as usize);
+    let pass = |src: &[bool], len: usize, at: &dyn Fn(usize, usize) -> usize, lines: usize| {
+        let mut out = vec![false; src.len()];
+        for line in 0..lines {
+            // Length of the black run ending at each position, then look r ahead
+            let mut run = vec![0usize; len];
+            for i in 0..len {
+                run[i] = if src[at(line, i)] { if i > 0 { run[i - 1] + 1 } else { 1 } } else { 0 };
+            }
+            for i in r..len.saturating_sub(r) {
+                out[at(line, i)] = run[i + r] > 2 * r;
+            }
+        }
+        out
+    };
+    let rows = pass(mask, w, &|y, x| y * w + x, h);
+    pass(&rows, h, &|x, y| y * w + x, w)
+}
+
+/// Kinds of text on a product label, each of which can have its own font
+/// (see `FontContext::with_font_for`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FontRole {
+    /// Brand header.
+    Brand,
+    /// Name and price line.
+    Body,
+    /// Small print: date line, PLU line, rendered barcode digits.
+    Detail,
+}
+
+/// Font bytes by name, so settings can say which typeface a role uses
+/// ("brand = Cairo Black") instead of passing bytes around.
+#[derive(Debug, Clone, Default)]
+pub struct FontRegistry<'a> {
+    fonts: HashMap<String, &'a [u8]>,
+}
+
+impl<'a> FontRegistry<'a> {
+    pub fn new() -> Self {
+        FontRegistry::default()
+    }
+
+    /// Add (or replace) `name`; fails if the bytes aren't a usable font.
+    pub fn register(&mut self, name: &str, font_bytes: &'a [u8]) -> Result<(), String> {
+        Font::try_from_bytes(font_bytes).ok_or_else(|| format!("{}: bad font", name))?;
+        self.fonts.insert(name.to_string(), font_bytes);
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&'a [u8]> {
+        self.fonts.get(name).copied()
+    }
+
+    /// Registered names, sorted.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.fonts.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+}
+
+/// Parsed font plus the shaping backend used to turn text into glyphs.
+/// Build one per font and reuse it for every label: rendered lines (brand
+/// header, digits, ...) are kept in an LRU cache, so repeated text in a batch
+/// is shaped and rasterized once.
+pub struct FontContext<'a> {
+    data: &'a [u8],
+    font: Font<'a>,
+    shaper: Arc<dyn Shaper>,
+    diacritics: Diacritics,
+    digits: Option<Digits>,
+    cache: Mutex<RenderCache>,
+    // Contexts of roles with their own font; they share shaper and settings
+    roles: HashMap<FontRole, FontContext<'a>>,
+}
+
+impl<'a> FontContext<'a> {
+    pub fn new(font_bytes: &'a [u8]) -> Result<Self, String> {
+        let font = Font::try_from_bytes(font_bytes).ok_or("bad font")?;
+        Ok(FontContext {
+            data: font_bytes,
+            font,
+            shaper: Arc::new(ReshaperShaper::default()),
+            diacritics: Diacritics::default(),
+            digits: None,
+            cache: Mutex::new(RenderCache::new(DEFAULT_CAPACITY)),
+            roles: HashMap::new(),
+        })
+    }
+
+    /// Render `role` text in another font, e.g. a display typeface for the
+    /// brand. Shaper, diacritics and digit settings stay shared with this context.
+    pub fn with_font_for(mut self, role: FontRole, font_bytes: &'a [u8]) -> Result<Self, String> {
+        let mut ctx = FontContext::new(font_bytes)?;
+        (ctx.shaper, ctx.diacritics, ctx.digits) = (self.shaper.clone(), self.diacritics, self.digits);
+        self.roles.insert(role, ctx);
+        Ok(self)
+    }
+
+    /// `with_font_for` with a font from `registry`.
+    pub fn with_registered_font(self, role: FontRole, registry: &FontRegistry<'a>, name: &str) -> Result<Self, String> {
+        let bytes = registry.get(name).ok_or_else(|| format!("font \"{}\" is not registered", name))?;
+        self.with_font_for(role, bytes)
+    }
+
+    /// Context to render `role` text with: its own font if one was set,
+    /// otherwise this one.
+    pub fn role(&self, role: FontRole) -> &FontContext<'a> {
+        self.roles.get(&role).unwrap_or(self)
+    }
+
+    /// Replace the default `ar_reshaper` backend (clears the render cache).
+    pub fn with_shaper<S: Shaper + 'static>(mut self, shaper: S) -> Self {
+        self.shaper = Arc::new(shaper);
+        for ctx in self.roles.values_mut() {
+            ctx.shaper = self.shaper.clone();
+        }
+        self.clear_cache();
+        self
+    }
+
+    /// Strip (default) or keep harakat in all text (clears the render cache).
+    pub fn with_diacritics(mut self, diacritics: Diacritics) -> Self {
+        self.diacritics = diacritics;
+        for ctx in self.roles.values_mut() {
+            ctx.diacritics = diacritics;
+        }
+        self.clear_cache();
+        self
+    }
+
+    /// Print every digit (prices, dates, ...) as `digits`, e.g. `Digits::Persian`
+    /// for Iranian or Pakistani stock. By default digits are left as written.
+    /// Clears the render cache.
+    pub fn with_digits(mut self, digits: Digits) -> Self {
+        self.digits = Some(digits);
+        for ctx in self.roles.values_mut() {
+            ctx.digits = Some(digits);
+        }
+        self.clear_cache();
+        self
+    }
+
+    /// Keep up to `capacity` rendered lines; 0 turns the cache off.
+    pub fn with_cache_capacity(mut self, capacity: usize) -> Self {
+        *self.cache.lock().unwrap_or_else(|e| e.into_inner()) = RenderCache::new(capacity);
+        self.roles = self.roles.into_iter().map(|(role, ctx)| (role, ctx.with_cache_capacity(capacity))).collect();
+        self
+    }
+
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache.lock().unwrap_or_else(|e| e.into_inner()).stats()
+    }
+
+    pub fn clear_cache(&self) {
+        self.cache.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        for ctx in self.roles.values() {
+            ctx.clear_cache();
+        }
+    }
+
+    // Cached result of `render` under `key`; rendering happens outside the lock
+    pub(crate) fn cached(&self, key: RenderKey, render: impl FnOnce() -> GrayImage) -> Arc<RenderedText> {
+        if let Some(hit) = self.cache.lock().unwrap_or_else(|e| e.into_inner()).get(&key) {
+            return hit;
+        }
+        let value = Arc::new(RenderedText::new(render()));
+        self.cache.lock().unwrap_or_else(|e| e.into_inner()).insert(key, value.clone());
+        value
+    }
+
+    pub fn font(&self) -> &Font<'a> {
+        &self.font
+    }
+
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    pub fn shape(&self, text: &str, font_px: f32) -> Vec<ShapedGlyph> {
+        let text = match self.digits {
+            Some(digits) => convert_digits(text, digits),
+            None => text.to_string(),
+        };
+        match self.diacritics {
+            Diacritics::Strip => self.shaper.shape(&strip_diacritics(&text), self.data, font_px),
+            Diacritics::Keep => self.shaper.shape(&text, self.data, font_px),
+        }
+    }
+
+    /// `shape` plus the style's letter spacing.
+    pub fn shape_styled(&self, text: &str, font_px: f32, style: &TextStyle) -> Vec<ShapedGlyph> {
+        let mut glyphs = self.shape(text, font_px);
+        if style.letter_spacing != 0.0 {
+            self.space_latin(&mut glyphs, style.letter_spacing);
+        }
+        glyphs
+    }
+
+    // Shift glyphs right by `spacing` after every Latin glyph followed by another one.
+    // Glyphs are classified through the cmap, so this works with any shaper.
+    fn space_latin(&self, glyphs: &mut [ShapedGlyph], spacing: f32) {
+        let latin: HashSet<u16> = ('!'..='\u{024F}')
+            .map(|c| self.font.glyph(c).id().0)
+            .filter(|&id| id != 0)
+            .collect();
+        let mut shift = 0.0;
+        for i in 0..glyphs.len() {
+            glyphs[i].x += shift;
+            if latin.contains(&glyphs[i].id) && glyphs.get(i + 1).is_some_and(|n| latin.contains(&n.id)) {
+                shift += spacing;
+            }
+        }
+    }
+
+    /// Stretch Arabic words with tatweel (kashida) so `text` renders close to
+    /// `target_w` dots wide without exceeding it. Kashidas go between joining
+    /// letters, spread evenly from the end of each word; text that is already
+    /// wide enough, has no joins, or a font without U+0640 is returned unchanged.
+    pub fn justify_kashida(&self, text: &str, font_px: f32, target_w: u32) -> String {
+        let (w, _) = self.measure(text, font_px);
+        let tatweel_w = self.font.glyph(TATWEEL).scaled(Scale::uniform(font_px)).h_metrics().advance_width;
+        if w >= target_w || self.font.glyph(TATWEEL).id().0 == 0 || tatweel_w < 1.0 {
+            return text.to_string();
+        }
+
+        let chars: Vec<char> = text.chars().collect();
+        let is_arabic = |c: char| ('\u{0621}'..='\u{064A}').contains(&c) && c != TATWEEL;
+        // Slot after chars[i]: both letters Arabic, the first joins forward, no lam-alef ligature
+        let mut slots: Vec<usize> = (0..chars.len().saturating_sub(1))
+            .filter(|&i| {
+                let (a, b) = (chars[i], chars[i + 1]);
+                is_arabic(a) && is_arabic(b) && !RIGHT_JOINING.contains(a) && a != 'ء'
+                    && !(a == 'ل' && "اأإآ".contains(b))
+            })
+            .collect();
+        if slots.is_empty() {
+            return text.to_string();
+        }
+        slots.reverse();
+
+        let mut counts = vec![0usize; chars.len()];
+        let mut n = ((target_w - w) as f32 / tatweel_w) as usize;
+        // The estimate ignores kerning; back off until the result fits
+        loop {
+            counts.iter_mut().for_each(|c| *c = 0);
+            for k in 0..n {
+                counts[slots[k % slots.len()]] += 1;
+            }
+            let out: String = chars.iter().zip(&counts)
+                .flat_map(|(&c, &k)| std::iter::once(c).chain(std::iter::repeat_n(TATWEEL, k)))
+                .collect();
+            if n == 0 || self.measure(&out, font_px).0 <= target_w {
+                return out;
+            }
+            n -= 1;
+        }
+    }
+
+    /// Position shaped glyphs relative to `origin` (pen start on the baseline).
+    pub fn positioned<'g>(&'g self, glyphs: &'g [ShapedGlyph], scale: Scale, origin: Point<f32>)
+        -> impl Iterator<Item = PositionedGlyph<'a>> + 'g
+    {
+        glyphs.iter().map(move |g| {
+            self.font.glyph(GlyphId(g.id)).scaled(scale)
+                .positioned(point(origin.x + g.x, origin.y + g.y))
+        })
+    }
+
+    /// Render one line as a tight 1-bit image (no bolding): black glyphs on white,
+    /// width = advance width (see `advance_extent`) + `pad` on each side,
+    /// height = ascent - descent.
+    pub fn render_line(&self, text: &str, font_px: f32, pad: u32) -> GrayImage {
+        self.render_line_styled(text, font_px, pad, &TextStyle::default())
+    }
+
+    /// `render_line` with a threshold, bolding and slant; the image grows by the
+    /// bold extent and the italic overhang.
+    pub fn render_line_styled(&self, text: &str, font_px: f32, pad: u32, style: &TextStyle) -> GrayImage {
+        self.render_line_cached(text, font_px, pad, style).image.clone()
+    }
+
+    /// `render_line_styled` through the render cache.
+    pub fn render_line_cached(&self, text: &str, font_px: f32, pad: u32, style: &TextStyle) -> Arc<RenderedText> {
+        self.cached(RenderKey::new("line", text, font_px, pad, style), || self.rasterize_line(text, font_px, pad, style))
+    }
+
+    fn rasterize_line(&self, text: &str, font_px: f32, pad: u32, style: &TextStyle) -> GrayImage {
+        let glyphs = self.shape_styled(text, font_px, style);
+        let scale = Scale::uniform(font_px);
+        let ascent = self.font.v_metrics(scale).ascent.ceil();
+        let (text_w, line_h) = self.extent(&glyphs, font_px);
+        let (overhang, _) = self.advance_extent(&glyphs, font_px);
+        let (bold_x, _) = style.bold_strength.extent(font_px);
+        let slant = if style.synthetic_italic { (ascent * ITALIC_SLANT).ceil() as u32 } else { 0 };
+        let w = (text_w + pad * 2 + bold_x + slant).max(1);
+
+        let mut img = GrayImage::from_pixel(w, line_h, Luma([255]));
+        self.draw(&mut img, &glyphs, font_px, point((pad + overhang) as f32, ascent), style);
+        img
+    }
+
+    /// Rasterize shaped glyphs into `img` (black on white) starting at `origin`
+    /// (pen position on the baseline). Dots outside the image are dropped.
+    pub fn draw(&self, img: &mut GrayImage, glyphs: &[ShapedGlyph], font_px: f32, origin: Point<f32>, style: &TextStyle) {
+        let (w, h) = img.dimensions();
+        let mut ink = vec![false; (w * h) as usize];
+        for g in self.positioned(glyphs, Scale::uniform(font_px), origin) {
+            if let Some(bb) = g.pixel_bounding_box() {
+                g.draw(|x, y, v| {
+                    if v <= style.threshold {
+                        return;
+                    }
+                    let py = y as i32 + bb.min.y;
+                    let mut px = x as i32 + bb.min.x;
+                    if style.synthetic_italic {
+                        px += ((origin.y - py as f32) * ITALIC_SLANT).round() as i32;
+                    }
+                    if px >= 0 && py >= 0 && (px as u32) < w && (py as u32) < h {
+                        ink[(py as u32 * w + px as u32) as usize] = true;
+                    }
+                });
+            }
+        }
+
+        // Dilate right/down by the bold extent
+        let (bx, by) = style.bold_strength.extent(font_px);
+        let mut solid = vec![false; ink.len()];
+        for (i, _) in ink.iter().enumerate().filter(|(_, &on)| on) {
+            let (x, y) = (i as u32 % w, i as u32 / w);
+            for yy in y..(y + by + 1).min(h) {
+                for xx in x..(x + bx + 1).min(w) {
+                    solid[(yy * w + xx) as usize] = true;
+                }
+            }
+        }
+
+        // Outline: the filled glyphs minus their erosion by the stroke width
+        let inner = style.outline.map(|stroke| erode(&solid, w, h, stroke));
+        for (i, _) in solid.iter().enumerate().filter(|(_, &on)| on) {
+            if !inner.as_ref().is_some_and(|inner| inner[i]) {
+                img.put_pixel(i as u32 % w, i as u32 / w, Luma([0]));
+            }
+        }
+    }
+
+    /// Size `render_line(text, font_px, 0)` would have, without rasterizing.
+    pub fn measure(&self, text: &str, font_px: f32) -> (u32, u32) {
+        let (w, h) = self.extent(&self.shape(text, font_px), font_px);
+        (w.max(1), h)
+    }
+
+    /// Horizontal extent of shaped glyphs as (overhang, width) in dots.
+    ///
+    /// The width runs from the pen start to the pen end after the last advance
+    /// (glyph positions already carry the shaper's kerning), so side bearings,
+    /// trailing spaces and marks count; ink reaching past either end widens it.
+    /// `overhang` is how far ink reaches left of the pen start: draw at
+    /// `x + overhang` to keep it on the image.
+    pub fn advance_extent(&self, glyphs: &[ShapedGlyph], font_px: f32) -> (u32, u32) {
+        let scale = Scale::uniform(font_px);
+        let (mut left, mut right) = (0.0f32, 0.0f32);
+        for (g, pg) in glyphs.iter().zip(self.positioned(glyphs, scale, point(0.0, 0.0))) {
+            right = right.max(g.x + pg.unpositioned().h_metrics().advance_width);
+            if let Some(bb) = pg.pixel_bounding_box() {
+                (left, right) = (left.min(bb.min.x as f32), right.max(bb.max.x as f32));
+            }
+        }
+        let overhang = (-left).ceil() as u32;
+        (overhang, right.ceil() as u32 + overhang)
+    }
+
+    // Advance width and line height (ascent - descent) of shaped glyphs
+    fn extent(&self, glyphs: &[ShapedGlyph], font_px: f32) -> (u32, u32) {
+        let vm = self.font.v_metrics(Scale::uniform(font_px));
+        let line_h = (vm.ascent.ceil() - vm.descent.floor()).ceil().max(1.0) as u32;
+        (self.advance_extent(glyphs, font_px).1, line_h)
+    }
+}
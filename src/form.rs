@@ -0,0 +1,162 @@
+//! Stored forms (`FS`/`FE`) with printer counters (`C`).
+//!
+//! A form is stored once, then every recall (`FR`) only sends the counters'
+//! start values and a `P` count; the printer steps the counters from label to
+//! label itself, so a run of numbered tickets costs a few bytes instead of one
+//! bitmap job per ticket.
+//!
+//! ```text
+//! FK"TICKET"      delete any old copy
+//! FS"TICKET"      start storing
+//! C0,6,R,+1,"Serial"
+//! q440            media setup, then the label's elements
+//! A40,40,0,4,1,1,N,"No. "C0
+//! FE              end of form
+//! FR"TICKET"      recall
+//! ?
+//! 000001          start value of C0
+//! P100
+//! ```
+
+use crate::epl::epl_line;
+use crate::label::Label;
+
+/// Placement of the counter value inside its `digits` wide field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Justify {
+    Left,
+    #[default]
+    Right,
+    Center,
+    /// No padding, the digits as they are.
+    None,
+}
+
+impl Justify {
+    fn code(self) -> char {
+        match self {
+            Justify::Left => 'L',
+            Justify::Right => 'R',
+            Justify::Center => 'C',
+            Justify::None => 'N',
+        }
+    }
+}
+
+/// Printer counter `C<number>`, referenced from text with `TextCmd::with_counter`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Counter {
+    /// 0..=9.
+    pub number: u8,
+    /// Maximum digits, 1..=9.
+    pub digits: u8,
+    pub justify: Justify,
+    /// Added after every label (negative counts down).
+    pub step: i32,
+    /// Shown by printers with a keyboard; ignored over a host connection.
+    pub prompt: String,
+}
+
+impl Counter {
+    pub fn new(number: u8, digits: u8, step: i32) -> Result<Self, String> {
+        if number > 9 {
+            return Err(format!("counter number {} out of range 0..=9", number));
+        }
+        if !(1..=9).contains(&digits) {
+            return Err(format!("counter width {} out of range 1..=9", digits));
+        }
+        Ok(Counter { number, digits, justify: Justify::default(), step, prompt: String::new() })
+    }
+
+    pub fn justify(mut self, justify: Justify) -> Self {
+        self.justify = justify;
+        self
+    }
+
+    pub fn to_command(&self) -> String {
+        format!("C{},{},{},{:+},\"{}\"", self.number, self.digits, self.justify.code(), self.step, self.prompt.replace('"', "'"))
+    }
+}
+
+/// A label stored in printer memory under `name`, with its counters.
+#[derive(Debug, Clone)]
+pub struct StoredForm {
+    /// Form name (up to 8 characters on EPL2 firmware).
+    pub name: String,
+    pub label: Label,
+    pub counters: Vec<Counter>,
+}
+
+impl StoredForm {
+    pub fn new(name: &str, label: Label) -> Result<Self, String> {
+        if name.is_empty() || name.len() > 8 || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(format!("invalid form name {:?} (1-8 letters, digits or '_')", name));
+        }
+        Ok(StoredForm { name: name.to_string(), label, counters: Vec::new() })
+    }
+
+    pub fn counter(mut self, counter: Counter) -> Self {
+        self.counters.retain(|c| c.number != counter.number);
+        self.counters.push(counter);
+        self
+    }
+
+    /// Job that (re)stores the form, media setup included. Send it once, e.g.
+    /// at startup; the form survives power cycles.
+    pub fn store(&self) -> Vec<u8> {
+        let l = &self.label;
+        let mut buf = Vec::new();
+        if let Some(mode) = l.form_backup {
+            epl_line(&mut buf, mode.command());
+        }
+        buf.extend_from_slice(&self.delete());
+        epl_line(&mut buf, &format!("FS\"{}\"", self.name));
+        for c in self.sorted_counters() {
+            epl_line(&mut buf, &c.to_command());
+        }
+        // Media setup is part of the form, so a recall restores it
+        epl_line(&mut buf, &format!("q{}", l.width));
+        epl_line(&mut buf, &format!("Q{},{}", l.height, l.gap));
+        l.write_print_settings(&mut buf);
+        l.write_elements(&mut buf);
+        epl_line(&mut buf, "FE");
+        buf
+    }
+
+    /// Print `count` labels, counters starting at `start` (one value per
+    /// counter, in counter number order). A start value's leading zeros are
+    /// kept, so `"000001"` prints as 000001, 000002, ...
+    pub fn recall(&self, start: &[&str], count: u32) -> Result<Vec<u8>, String> {
+        let counters = self.sorted_counters();
+        if start.len() != counters.len() {
+            return Err(format!("form {} has {} counters, got {} start values", self.name, counters.len(), start.len()));
+        }
+        for (c, v) in counters.iter().zip(start) {
+            if v.len() > c.digits as usize || !v.chars().all(|ch| ch.is_ascii_digit()) {
+                return Err(format!("start value {:?} of C{} is not a number of up to {} digits", v, c.number, c.digits));
+            }
+        }
+        let mut buf = Vec::new();
+        epl_line(&mut buf, &format!("FR\"{}\"", self.name));
+        epl_line(&mut buf, "?");
+        for v in start {
+            epl_line(&mut buf, v);
+        }
+        epl_line(&mut buf, &format!("P{}", count));
+        Ok(buf)
+    }
+
+    /// Remove the form from printer memory (`FK`).
+    pub fn delete(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        epl_line(&mut buf, &format!("FK\"{}\"", self.name));
+        buf
+    }
+
+    // The printer prompts for (and reads) counter values in number order
+    fn sorted_counters(&self) -> Vec<&Counter> {
+        let mut c: Vec<&Counter> = self.counters.iter().collect();
+        c.sort_by_key(|c| c.number);
+        c
+    }
+}
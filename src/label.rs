@@ -0,0 +1,727 @@
+use image::GrayImage;
+
+use crate::barcode::{Barcode, Symbology};
+use crate::barcode_bitmap::{
+    render_barcode, BarcodeRenderer, BitmapBarcodeOptions, Code128Renderer, Code39Renderer, Code93Renderer, Ean13Renderer,
+    Ean8Renderer, Itf14Renderer, UpcARenderer,
+};
+use crate::consts::{DARKNESS, HRI_GAP, HRI_PX, LABEL_GAP, MAX_DOT_DENSITY_PERCENT, MIN_MATRIX_MODULE, MIRROR, SPEED};
+use crate::font::{FontContext, FontRole};
+use crate::epl::{epl_line, gw_bytes, image_to_row_bytes, Delivery, FormBackup, STOP_POSITION};
+use crate::meta::JobMeta;
+use crate::report::BuildReport;
+use crate::warning::{Side, Warning};
+
+/// Human readable digits under a barcode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Hri {
+    None,
+    /// Printer firmware font (`B` flag of the `B` command).
+    #[default]
+    Printer,
+    /// Drawn in the label font and sent as a bitmap under the bars
+    /// (see `Label::barcode_with_hri`).
+    Rendered,
+}
+
+/// Printer-rendered barcode (`B` command). Coordinates are label dots.
+#[derive(Debug, Clone)]
+pub struct BarcodeCmd {
+    pub x: u32,
+    pub y: u32,
+    pub symbology: Symbology,
+    pub narrow: u32,
+    pub wide: u32,
+    pub height: u32,
+    pub hri: Hri,
+    pub data: String,
+}
+
+impl BarcodeCmd {
+    pub fn ean13(x: u32, y: u32, narrow: u32, height: u32, data: &str) -> Self {
+        BarcodeCmd {
+            x, y,
+            symbology: Symbology::Ean13,
+            narrow,
+            wide: 3,
+            height,
+            hri: Hri::Printer,
+            data: data.to_string(),
+        }
+    }
+
+    /// `barcode` as chosen by `Barcode::auto` (or built by hand).
+    pub fn from_barcode(x: u32, y: u32, narrow: u32, height: u32, barcode: &Barcode) -> Self {
+        let symbology = barcode.symbology;
+        BarcodeCmd { x, y, symbology, narrow, wide: symbology.default_wide(narrow), height, hri: Hri::Printer, data: barcode.data.clone() }
+    }
+
+    /// Code 39 at 3:1; `check` makes the printer add the mod-43 character.
+    pub fn code39(x: u32, y: u32, narrow: u32, height: u32, data: &str, check: bool) -> Result<Self, String> {
+        let symbology = Symbology::Code39 { check };
+        let data = crate::barcode::normalize_code39(data, false)?;
+        Ok(BarcodeCmd { x, y, symbology, narrow, wide: symbology.default_wide(narrow), height, hri: Hri::Printer, data })
+    }
+
+    pub fn code93(x: u32, y: u32, narrow: u32, height: u32, data: &str) -> Result<Self, String> {
+        let data = crate::barcode::normalize_code93(data)?;
+        Ok(BarcodeCmd { x, y, symbology: Symbology::Code93, narrow, wide: 3, height, hri: Hri::Printer, data })
+    }
+
+    /// Width of the bars in dots.
+    pub fn width(&self) -> u32 {
+        self.symbology.width_with(&self.data, self.narrow, self.wide)
+    }
+
+    /// Digits as printed under the bars (EAN-13 includes the check digit).
+    pub fn hri_text(&self) -> String {
+        match self.symbology {
+            Symbology::Ean13 | Symbology::Ean8 | Symbology::UpcA => self.symbology.with_check_digit(&self.data),
+            _ => self.data.clone(),
+        }
+    }
+
+    /// Estimated height of `Hri::Printer` digits under the bars: a font 3 line
+    /// plus its gap (the firmware doesn't report it).
+    pub fn printer_hri_height() -> u32 {
+        4 + TextCmd::cell(3).1
+    }
+
+    /// Bitmap of the digits for `Hri::Rendered` (`None` for the other modes).
+    pub fn render_hri(&self, ctx: &FontContext) -> Option<GrayImage> {
+        (self.hri == Hri::Rendered).then(|| ctx.role(FontRole::Detail).render_line(&self.hri_text(), HRI_PX, 0))
+    }
+
+    /// The bars as a bitmap, `width` dots wide (`None` if the data doesn't
+    /// encode). No HRI: use `Hri::Rendered` to get digits that follow the bitmap.
+    pub fn render_bars(&self) -> Option<GrayImage> {
+        let opts = BitmapBarcodeOptions { module: self.narrow, height: self.height, hri: false, ..Default::default() };
+        // Symbologies with wide elements are drawn at 1 dot per module
+        let dots = BitmapBarcodeOptions { module: 1, ..opts.clone() };
+        let (narrow, wide) = (self.narrow, self.wide);
+        match self.symbology {
+            Symbology::Ean13 => render_barcode(&Ean13Renderer, &self.data, &opts, None),
+            Symbology::Ean8 => render_barcode(&Ean8Renderer, &self.data, &opts, None),
+            Symbology::UpcA => render_barcode(&UpcARenderer, &self.data, &opts, None),
+            Symbology::Code93 => render_barcode(&Code93Renderer, &self.data, &opts, None),
+            Symbology::Code128 => render_barcode(&Code128Renderer, &self.data, &opts, None),
+            Symbology::Code39 { check } => render_barcode(&Code39Renderer { check, narrow, wide }, &self.data, &dots, None),
+            Symbology::Itf14 => render_barcode(&Itf14Renderer { narrow, wide }, &self.data, &dots, None),
+        }.ok()
+    }
+
+    pub fn to_command(&self) -> String {
+        // Code 128 data may hold quotes and backslashes
+        let data = self.data.replace('\\', "\\\\").replace('"', "\\\"");
+        format!("B{},{},0,{},{},{},{},{},\"{}\"",
+            self.x, self.y, self.symbology.epl_code(), self.narrow, self.wide, self.height,
+            if self.hri == Hri::Printer { "B" } else { "N" }, data)
+    }
+}
+
+/// Text in one of the printer's resident fonts (`A` command).
+#[derive(Debug, Clone)]
+pub struct TextCmd {
+    pub x: u32,
+    pub y: u32,
+    /// Resident font 1..5, or a soft font `b'a'..=b'z'` (see `softfont`).
+    pub font: u8,
+    pub h_mult: u32,
+    pub v_mult: u32,
+    pub reverse: bool,
+    pub data: String,
+    /// Printer counter `C<n>` appended after `data` (stored forms only, see `form::StoredForm`).
+    pub counter: Option<u8>,
+    /// Character cell (pitch, height) when it isn't a resident font's, e.g. the
+    /// widest glyph of a soft font.
+    pub cell: Option<(u32, u32)>,
+}
+
+impl TextCmd {
+    pub fn new(x: u32, y: u32, font: u8, data: &str) -> Self {
+        TextCmd { x, y, font, h_mult: 1, v_mult: 1, reverse: false, data: data.to_string(), counter: None, cell: None }
+    }
+
+    /// `data` followed by the value of printer counter `n`, e.g. `"No. "C0`.
+    pub fn with_counter(mut self, n: u8) -> Self {
+        self.counter = Some(n);
+        self
+    }
+
+    /// Character cell (pitch, height) of the resident fonts at 203 dpi.
+    pub fn cell(font: u8) -> (u32, u32) {
+        match font {
+            1 => (10, 12),
+            2 => (12, 16),
+            3 => (14, 20),
+            4 => (16, 24),
+            _ => (36, 48),
+        }
+    }
+
+    /// Size of the fixed text; a counter's digits are not included.
+    pub fn size(&self) -> (u32, u32) {
+        let (pitch, h) = self.cell.unwrap_or_else(|| Self::cell(self.font));
+        (self.data.chars().count() as u32 * pitch * self.h_mult, h * self.v_mult)
+    }
+
+    pub fn to_command(&self) -> String {
+        // Quotes and backslashes must be escaped inside EPL2 data fields
+        let data = self.data.replace('\\', "\\\\").replace('"', "\\\"");
+        let counter = self.counter.map_or(String::new(), |n| format!("C{}", n));
+        // Soft fonts go by their letter, resident ones by number
+        let font = if self.font.is_ascii_lowercase() { (self.font as char).to_string() } else { self.font.to_string() };
+        format!("A{},{},0,{},{},{},{},\"{}\"{}",
+            self.x, self.y, font, self.h_mult, self.v_mult,
+            if self.reverse { "R" } else { "N" }, data, counter)
+    }
+}
+
+/// One printable object on a label.
+#[derive(Debug, Clone)]
+pub enum Element {
+    /// 1-bit bitmap (luma < 128 is black) sent with `GW`.
+    Graphic { x: u32, y: u32, image: GrayImage },
+    Barcode(BarcodeCmd),
+    Text(TextCmd),
+    /// Rectangle outline (`X` command); `(x1, y1)` is the bottom-right corner.
+    Frame { x: u32, y: u32, x1: u32, y1: u32, thickness: u32 },
+    /// Solid black rectangle (`LO` command), e.g. a rule or separator.
+    Line { x: u32, y: u32, width: u32, height: u32 },
+    /// Region filled in at send time (see `stamp::Stamper`); serialized as a
+    /// `; stamp` comment line, which the printer ignores if left unresolved.
+    /// `mirrored` stamps render their value flipped.
+    Stamp { x: u32, y: u32, width: u32, height: u32, font_px: f32, key: String, mirrored: bool },
+}
+
+impl Element {
+    /// Bounding box `(x, y, w, h)` in dots (barcodes exclude HRI).
+    pub fn bounds(&self) -> (u32, u32, u32, u32) {
+        match self {
+            Element::Graphic { x, y, image } => (*x, *y, image.width(), image.height()),
+            Element::Barcode(b) => (b.x, b.y, b.width(), b.height),
+            Element::Text(t) => {
+                let (w, h) = t.size();
+                (t.x, t.y, w, h)
+            }
+            Element::Frame { x, y, x1, y1, .. } => (*x, *y, x1.saturating_sub(*x), y1.saturating_sub(*y)),
+            Element::Line { x, y, width, height } | Element::Stamp { x, y, width, height, .. } => (*x, *y, *width, *height),
+        }
+    }
+
+    /// Short name for messages.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Element::Graphic { .. } => "bitmap",
+            Element::Barcode(_) => "barcode",
+            Element::Text(_) => "text",
+            Element::Frame { .. } => "frame",
+            Element::Line { .. } => "line",
+            Element::Stamp { .. } => "stamp",
+        }
+    }
+
+    /// What `mirrored` can't flip: `None` if the element mirrors fully.
+    pub fn mirror_note(&self) -> Option<&'static str> {
+        match self {
+            Element::Barcode(b) if b.render_bars().is_none() => Some("barcode data has no bitmap encoding; moved but not flipped"),
+            Element::Barcode(b) if b.hri == Hri::Printer => Some("barcode flipped without its printer HRI digits (use Hri::Rendered)"),
+            Element::Text(_) => Some("resident-font text moved but not flipped (see Label::render_text)"),
+            _ => None,
+        }
+    }
+
+    /// Horizontal mirror image on a label `label_w` dots wide.
+    ///
+    /// Bitmaps are flipped, barcodes become flipped bitmaps (printer HRI is
+    /// dropped, `Hri::Rendered` digits are a bitmap and mirror along) and
+    /// stamps are flipped when resolved. Resident-font text and barcodes whose
+    /// data doesn't encode can't be flipped by the printer and are only moved
+    /// to their mirrored position; `mirror_note` says which, and
+    /// `Label::check_mirror` rejects them.
+    pub fn mirrored(&self, label_w: u32) -> Element {
+        let (x, _, w, _) = self.bounds();
+        let mx = label_w.saturating_sub(x + w);
+        match self {
+            Element::Graphic { y, image, .. } =>
+                Element::Graphic { x: mx, y: *y, image: image::imageops::flip_horizontal(image) },
+            Element::Barcode(b) => match b.render_bars() {
+                Some(bars) => Element::Graphic { x: mx, y: b.y, image: image::imageops::flip_horizontal(&bars) },
+                None => Element::Barcode(BarcodeCmd { x: mx, ..b.clone() }),
+            },
+            Element::Text(t) => Element::Text(TextCmd { x: mx, ..t.clone() }),
+            Element::Frame { y, y1, thickness, .. } =>
+                Element::Frame { x: mx, y: *y, x1: mx + w, y1: *y1, thickness: *thickness },
+            Element::Line { y, width, height, .. } => Element::Line { x: mx, y: *y, width: *width, height: *height },
+            Element::Stamp { y, width, height, font_px, key, mirrored, .. } =>
+                Element::Stamp { x: mx, y: *y, width: *width, height: *height, font_px: *font_px, key: key.clone(), mirrored: !mirrored },
+        }
+    }
+}
+
+/// Layout annotation kept with a `Label` for `preview::render_preview_debug`;
+/// never part of the job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Guide {
+    /// Layout cell (half, quadrant, ...).
+    Cell { x: u32, y: u32, width: u32, height: u32 },
+    /// Text baseline, `width` dots from `x`.
+    Baseline { x: u32, y: u32, width: u32 },
+}
+
+/// A single label form: media setup plus positioned elements, serialized with `to_epl`.
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub width: u32,
+    pub height: u32,
+    pub gap: u32,
+    /// `D` value; `None` sends no `D` and keeps the printer's own setting.
+    pub darkness: Option<u8>,
+    /// `S` value; `None` keeps the printer's setting.
+    pub speed: Option<u8>,
+    pub copies: u32,
+    /// Print a mirror image (labels applied to the inside of glass), see `Element::mirrored`.
+    pub mirror: bool,
+    /// `JF`/`JB`/`JC` before the form; `None` leaves the printer setting alone.
+    pub form_backup: Option<FormBackup>,
+    /// `O` before the form: tear-off, dispenser or cutter; `None` leaves the
+    /// printer setting alone.
+    pub delivery: Option<Delivery>,
+    /// `f` stop position (`epl::STOP_POSITION`, clamped); `None` leaves it alone.
+    pub stop_position: Option<u8>,
+    /// Written as comment lines at the top of the job (see `set_meta`).
+    pub meta: Option<JobMeta>,
+    pub elements: Vec<Element>,
+    /// Elements moved onto the label because their layout put them past the
+    /// left or top edge, as `(element, side, dots)` (see `clamped_from`).
+    pub clamped: Vec<(usize, Side, u32)>,
+    /// Layout cells and baselines for debug previews; not sent.
+    pub guides: Vec<Guide>,
+}
+
+impl Label {
+    pub fn new(width: u32, height: u32) -> Self {
+        Label {
+            width,
+            height,
+            gap: LABEL_GAP,
+            darkness: Some(DARKNESS),
+            speed: Some(SPEED),
+            copies: 1,
+            mirror: MIRROR,
+            form_backup: None,
+            delivery: None,
+            stop_position: None,
+            meta: None,
+            elements: Vec::new(),
+            clamped: Vec::new(),
+            guides: Vec::new(),
+        }
+    }
+
+    pub fn graphic(&mut self, x: u32, y: u32, image: GrayImage) {
+        self.elements.push(Element::Graphic { x, y, image });
+    }
+
+    /// `graphic` at a layout position that may lie left of or above the label
+    /// (`Cell::offsets`): the bitmap is moved onto the label and the move recorded.
+    pub fn graphic_at(&mut self, x: i32, y: i32, image: GrayImage) {
+        self.graphic(x.max(0) as u32, y.max(0) as u32, image);
+        self.clamped_from(x, y);
+    }
+
+    /// Record that the last element added was moved onto the label from the
+    /// signed position `(x, y)`; `overflows` reports it past the left / top edge.
+    pub fn clamped_from(&mut self, x: i32, y: i32) {
+        let Some(i) = self.elements.len().checked_sub(1) else { return };
+        self.clamped.extend([(Side::Left, x), (Side::Top, y)].into_iter()
+            .filter(|&(_, v)| v < 0)
+            .map(|(side, v)| (i, side, v.unsigned_abs())));
+    }
+
+    pub fn barcode(&mut self, cmd: BarcodeCmd) {
+        self.elements.push(Element::Barcode(cmd));
+    }
+
+    pub fn line(&mut self, x: u32, y: u32, width: u32, height: u32) {
+        self.elements.push(Element::Line { x, y, width, height });
+    }
+
+    /// Reserve a `width` x `height` region for the value of `key`, rendered in
+    /// the label font at `font_px` when the job is sent.
+    pub fn stamp(&mut self, x: u32, y: u32, width: u32, height: u32, font_px: f32, key: &str) {
+        self.elements.push(Element::Stamp { x, y, width, height, font_px, key: key.to_string(), mirrored: false });
+    }
+
+    /// Tag the job with `meta`; with `meta.print_code` its short code is also
+    /// printed in resident font 1, in the first free corner (bottom left,
+    /// bottom right, top left, top right; bottom left if none is free).
+    pub fn set_meta(&mut self, meta: JobMeta) {
+        if meta.print_code {
+            let mut code = TextCmd::new(0, 0, 1, &meta.short_code());
+            let (w, h) = code.size();
+            let (right, bottom) = (self.width.saturating_sub(w + 4), self.height.saturating_sub(h + 2));
+            let corners = [(4, bottom), (right, bottom), (4, 2), (right, 2)];
+            (code.x, code.y) = corners.into_iter().find(|&(x, y)| self.is_free(x, y, w, h)).unwrap_or(corners[0]);
+            self.elements.push(Element::Text(code));
+        }
+        self.meta = Some(meta);
+    }
+
+    // Nothing printed within the box. Bitmaps count by their black dots (they
+    // carry white margins); printer HRI, a font 3 line that can start left of
+    // the bars, counts as part of its barcode.
+    fn is_free(&self, x: u32, y: u32, w: u32, h: u32) -> bool {
+        self.elements.iter().all(|e| {
+            let (mut ex, ey, mut ew, mut eh) = e.bounds();
+            match e {
+                Element::Graphic { x: gx, y: gy, image } => {
+                    return !image.enumerate_pixels().any(|(px, py, p)| {
+                        let (px, py) = (gx + px, gy + py);
+                        p.0[0] < 128 && px >= x && px < x + w && py >= y && py < y + h
+                    });
+                }
+                Element::Barcode(b) if b.hri == Hri::Printer => {
+                    let (pitch, _) = TextCmd::cell(3);
+                    (ex, ew, eh) = (ex.saturating_sub(pitch), ew + 2 * pitch, eh + BarcodeCmd::printer_hri_height());
+                }
+                _ => {}
+            }
+            x + w <= ex || ex + ew <= x || y + h <= ey || ey + eh <= y
+        })
+    }
+
+    /// Add a barcode plus, for `Hri::Rendered`, its digits centered `HRI_GAP`
+    /// dots under the bars. Returns the height of bars + rendered digits.
+    pub fn barcode_with_hri(&mut self, cmd: BarcodeCmd, ctx: &FontContext) -> u32 {
+        let mut h = cmd.height;
+        if let Some(img) = cmd.render_hri(ctx) {
+            let x = (cmd.x + cmd.width() / 2).saturating_sub(img.width() / 2);
+            let y = cmd.y + cmd.height + HRI_GAP;
+            h += HRI_GAP + img.height();
+            self.graphic(x, y, img);
+        }
+        self.barcode(cmd);
+        h
+    }
+
+    /// Serialize as one EPL2 job (`N` ... `P<copies>`).
+    pub fn to_epl(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        for line in self.meta.iter().flat_map(JobMeta::comment_lines) {
+            epl_line(&mut buf, &line);
+        }
+        if let Some(mode) = self.form_backup {
+            epl_line(&mut buf, mode.command());
+        }
+        if let Some(delivery) = self.delivery {
+            epl_line(&mut buf, &delivery.command());
+        }
+        if let Some(pos) = self.stop_position {
+            epl_line(&mut buf, &format!("f{}", pos.clamp(*STOP_POSITION.start(), *STOP_POSITION.end())));
+        }
+        epl_line(&mut buf, "N");
+        epl_line(&mut buf, &format!("q{}", self.width));
+        epl_line(&mut buf, &format!("Q{},{}", self.height, self.gap));
+        self.write_print_settings(&mut buf);
+        self.write_elements(&mut buf);
+        epl_line(&mut buf, &format!("P{}", self.copies));
+        buf
+    }
+
+    // `D` / `S` for the settings that are set
+    pub(crate) fn write_print_settings(&self, buf: &mut Vec<u8>) {
+        if let Some(d) = self.darkness {
+            epl_line(buf, &format!("D{}", d));
+        }
+        if let Some(s) = self.speed {
+            epl_line(buf, &format!("S{}", s));
+        }
+    }
+
+    /// The element commands alone (no setup, no `P`), e.g. for a stored form.
+    pub(crate) fn write_elements(&self, buf: &mut Vec<u8>) {
+        for e in &self.elements {
+            let flipped;
+            let e = if self.mirror {
+                flipped = e.mirrored(self.width);
+                &flipped
+            } else {
+                e
+            };
+            match e {
+                Element::Graphic { x, y, image } => {
+                    let (w, h, rows) = image_to_row_bytes(image);
+                    gw_bytes(buf, *x, *y, w, h, &rows);
+                }
+                Element::Barcode(b) => epl_line(buf, &b.to_command()),
+                Element::Text(t) => epl_line(buf, &t.to_command()),
+                Element::Frame { x, y, x1, y1, thickness } =>
+                    epl_line(buf, &format!("X{},{},{},{},{}", x, y, thickness, x1, y1)),
+                Element::Line { x, y, width, height } =>
+                    epl_line(buf, &format!("LO{},{},{},{}", x, y, width, height)),
+                Element::Stamp { x, y, width, height, font_px, key, mirrored } =>
+                    epl_line(buf, &crate::stamp::placeholder_line(*x, *y, *width, *height, *font_px, key, *mirrored)),
+            }
+        }
+    }
+
+    /// `to_epl` in canonical form (see `snapshot::canonicalize`), for stable
+    /// fingerprints and snapshots.
+    pub fn to_epl_canonical(&self) -> Vec<u8> {
+        crate::snapshot::canonicalize(&self.to_epl())
+    }
+
+    /// Elements reaching past the label edges, as `(element, side, dots)`:
+    /// past the left or top edge as recorded in `clamped`, past the right or
+    /// bottom edge by their bounds. Barcodes are checked without their HRI line.
+    pub fn overflows(&self) -> Vec<(usize, Side, u32)> {
+        let mut out = self.clamped.clone();
+        for (i, e) in self.elements.iter().enumerate() {
+            let (x, y, w, h) = e.bounds();
+            let right = (x + w).saturating_sub(self.width);
+            let bottom = (y + h).saturating_sub(self.height);
+            out.extend([(Side::Right, right), (Side::Bottom, bottom)].into_iter()
+                .filter(|&(_, dots)| dots > 0)
+                .map(|(side, dots)| (i, side, dots)));
+        }
+        out.sort_by_key(|&(i, _, _)| i);
+        out
+    }
+
+    /// On a mirrored label, `Err` naming every element `Element::mirrored`
+    /// can't flip (see `Element::mirror_note`).
+    pub fn check_mirror(&self) -> Result<(), String> {
+        if !self.mirror {
+            return Ok(());
+        }
+        let msgs: Vec<String> = self.elements.iter().enumerate()
+            .filter_map(|(i, e)| e.mirror_note().map(|note| format!("element {} ({}): {}", i, e.kind(), note)))
+            .collect();
+        if msgs.is_empty() { Ok(()) } else { Err(format!("mirrored label can't flip: {}", msgs.join("; "))) }
+    }
+
+    /// Redraw resident-font text as bitmaps in the label font (`FontRole::Detail`,
+    /// at the height of the font cell), e.g. so a mirrored label can flip it.
+    /// Text with a printer counter keeps its `A` command.
+    pub fn render_text(&mut self, ctx: &FontContext) {
+        for e in &mut self.elements {
+            let Element::Text(t) = e else { continue };
+            if t.counter.is_some() {
+                continue;
+            }
+            let (_, cell_h) = t.cell.unwrap_or_else(|| TextCmd::cell(t.font));
+            let mut image = ctx.role(FontRole::Detail).render_line(&t.data, (cell_h * t.v_mult) as f32, 0);
+            if t.reverse {
+                image::imageops::invert(&mut image);
+            }
+            *e = Element::Graphic { x: t.x, y: t.y, image };
+        }
+    }
+
+    /// `Err` describing every element that doesn't fit on the label (see `overflows`).
+    pub fn check_bounds(&self) -> Result<(), String> {
+        let overflows = self.overflows();
+        if overflows.is_empty() {
+            return Ok(());
+        }
+        let msgs: Vec<String> = overflows.iter().map(|&(i, side, dots)| {
+            let (x, y, w, h) = self.elements[i].bounds();
+            format!("element {} ({} at {},{} size {}x{}) is {} dots past the {} edge",
+                i, self.elements[i].kind(), x, y, w, h, dots, format!("{:?}", side).to_lowercase())
+        }).collect();
+        Err(format!("content overflows the {}x{} label: {}", self.width, self.height, msgs.join("; ")))
+    }
+
+    /// Layout checks that don't prevent printing: quiet zones, dot density,
+    /// elements past the label edges and, on mirrored labels, elements the
+    /// printer can't flip.
+    pub fn warnings(&self) -> Vec<Warning> {
+        let mut out = Vec::new();
+        for (i, e) in self.elements.iter().enumerate() {
+            match e {
+                Element::Barcode(b) => self.check_quiet_zone(i, b, &mut out),
+                Element::Text(_) | Element::Frame { .. } | Element::Line { .. } | Element::Stamp { .. } => {}
+                Element::Graphic { image, .. } => {
+                    let total = (image.width() * image.height()).max(1);
+                    let black = image.pixels().filter(|p| p.0[0] < 128).count() as u32;
+                    let ratio_percent = black * 100 / total;
+                    if ratio_percent > MAX_DOT_DENSITY_PERCENT {
+                        out.push(Warning::HighDotDensity { element: i, ratio_percent });
+                    }
+                }
+            }
+        }
+        out.extend(self.overflows().into_iter().map(|(element, side, overflow)| Warning::OutOfBounds { element, side, overflow }));
+        if self.mirror {
+            out.extend(self.elements.iter().enumerate()
+                .filter_map(|(element, e)| e.mirror_note().map(|note| Warning::NotMirrored { element, note })));
+        }
+        out
+    }
+
+    fn check_quiet_zone(&self, index: usize, b: &BarcodeCmd, out: &mut Vec<Warning>) {
+        let (left_mod, right_mod) = b.symbology.quiet_zone();
+        let (bx, by, bw, bh) = (b.x, b.y, b.width(), b.height);
+
+        // Free space up to the label edge or the nearest element sharing the barcode's rows
+        let mut left = bx;
+        let mut right = self.width.saturating_sub(bx + bw);
+        for (j, e) in self.elements.iter().enumerate() {
+            let (ex, ey, ew, eh) = e.bounds();
+            if j == index || ey >= by + bh || ey + eh <= by {
+                continue;
+            }
+            if ex + ew <= bx {
+                left = left.min(bx - (ex + ew));
+            } else if ex >= bx + bw {
+                right = right.min(ex - (bx + bw));
+            }
+        }
+
+        for (side, available, modules) in [(Side::Left, left, left_mod), (Side::Right, right, right_mod)] {
+            let required = modules * b.narrow;
+            if available < required {
+                out.push(Warning::TightQuietZone { element: index, side, available, required });
+            }
+        }
+    }
+}
+
+/// ITF-14 carton code framed by bearer bars, placed as one label element.
+///
+/// `(x, y)` is the top-left corner of the bearer frame. The bars sit inside the
+/// frame behind a 10-module quiet zone; the HRI (if enabled) is printed below
+/// the frame in resident font 3, since the printer's own HRI would collide with
+/// the bottom bearer.
+#[derive(Debug, Clone)]
+pub struct Itf14 {
+    pub x: u32,
+    pub y: u32,
+    pub narrow: u32,
+    pub wide: u32,
+    pub height: u32,
+    /// Bearer bar thickness in dots.
+    pub bearer: u32,
+    pub hri: bool,
+    pub data: String,
+}
+
+impl Itf14 {
+    /// 13 or 14 digits; the check digit is added or verified.
+    pub fn new(x: u32, y: u32, data: &str) -> Result<Self, String> {
+        let data = crate::barcode::normalize_itf14(data.to_string())?;
+        let narrow = 2;
+        Ok(Itf14 { x, y, narrow, wide: Symbology::Itf14.default_wide(narrow), height: 80, bearer: 4 * narrow, hri: true, data })
+    }
+
+    fn quiet(&self) -> u32 {
+        Symbology::Itf14.quiet_zone().0 * self.narrow
+    }
+
+    /// Outer size (w, h) including frame and HRI line.
+    pub fn size(&self) -> (u32, u32) {
+        let bars = Symbology::Itf14.width_with(&self.data, self.narrow, self.wide);
+        let w = bars + 2 * (self.quiet() + self.bearer);
+        let hri_h = if self.hri { 4 + TextCmd::cell(3).1 } else { 0 };
+        (w, self.height + 2 * self.bearer + hri_h)
+    }
+
+    pub fn add_to(&self, label: &mut Label) {
+        let (w, _) = self.size();
+        let frame_h = self.height + 2 * self.bearer;
+        label.elements.push(Element::Frame {
+            x: self.x, y: self.y,
+            x1: self.x + w, y1: self.y + frame_h,
+            thickness: self.bearer,
+        });
+        label.barcode(BarcodeCmd {
+            x: self.x + self.bearer + self.quiet(),
+            y: self.y + self.bearer,
+            symbology: Symbology::Itf14,
+            narrow: self.narrow,
+            wide: self.wide,
+            height: self.height,
+            hri: Hri::None,
+            data: self.data.clone(),
+        });
+        if self.hri {
+            let text = TextCmd::new(0, self.y + frame_h + 4, 3, &self.data);
+            let tx = self.x + w.saturating_sub(text.size().0) / 2;
+            label.elements.push(Element::Text(TextCmd { x: tx, ..text }));
+        }
+    }
+}
+
+/// 2D symbol drawn in Rust and sent as a `GW` bitmap instead of relying on the
+/// LP-2824's unreliable firmware 2D support. DataMatrix and QR have built-in
+/// renderers (features `datamatrix`, `qr`); PDF417 needs a user-supplied
+/// `BarcodeRenderer`, as no PDF417 encoder crate builds on stable Rust.
+///
+/// `(x, y)` is the top-left corner of the symbol itself; `quiet` modules of
+/// clear space must fit around it inside the label.
+#[derive(Debug, Clone)]
+pub struct MatrixCode {
+    pub x: u32,
+    pub y: u32,
+    /// Dots per module.
+    pub module: u32,
+    /// Largest allowed outer size (w, h) in dots, including HRI.
+    pub max_size: Option<(u32, u32)>,
+    /// Quiet zone in modules (1 for DataMatrix, 2 for PDF417).
+    pub quiet: u32,
+    pub hri: bool,
+}
+
+impl MatrixCode {
+    pub fn new(x: u32, y: u32) -> Self {
+        MatrixCode { x, y, module: MIN_MATRIX_MODULE, max_size: None, quiet: 1, hri: false }
+    }
+
+    /// Encode `data`, check module size and space, then add the bitmap.
+    /// Returns the placed size (w, h).
+    pub fn add_to(
+        &self,
+        label: &mut Label,
+        renderer: &dyn BarcodeRenderer,
+        data: &str,
+        ctx: Option<&FontContext>,
+    ) -> Result<(u32, u32), String> {
+        if self.module < MIN_MATRIX_MODULE {
+            return Err(format!("{}: module {} dots is below the {}-dot minimum", renderer.name(), self.module, MIN_MATRIX_MODULE));
+        }
+        let opts = BitmapBarcodeOptions { module: self.module, hri: self.hri, ..Default::default() };
+        let image = render_barcode(renderer, data, &opts, ctx)?;
+        let (w, h) = image.dimensions();
+
+        if let Some((max_w, max_h)) = self.max_size {
+            if w > max_w || h > max_h {
+                return Err(format!("{}: symbol is {}x{} dots, limit is {}x{}", renderer.name(), w, h, max_w, max_h));
+            }
+        }
+        let q = self.quiet * self.module;
+        if self.x < q || self.y < q || self.x + w + q > label.width || self.y + h + q > label.height {
+            return Err(format!("{}: {}x{} dots plus {}-dot quiet zone does not fit at ({}, {}) on a {}x{} label",
+                renderer.name(), w, h, q, self.x, self.y, label.width, label.height));
+        }
+
+        label.graphic(self.x, self.y, image);
+        Ok((w, h))
+    }
+}
+
+/// A built job plus the non-fatal issues found while building it.
+#[derive(Debug, Clone)]
+pub struct LabelJob {
+    pub bytes: Vec<u8>,
+    pub warnings: Vec<Warning>,
+    pub report: BuildReport,
+}
+
+impl LabelJob {
+    /// Job with its `BuildReport` read back from `bytes`.
+    pub fn new(bytes: Vec<u8>, warnings: Vec<Warning>) -> LabelJob {
+        let report = BuildReport::from_job(&bytes);
+        LabelJob { bytes, warnings, report }
+    }
+}
@@ -0,0 +1,329 @@
+use crate::consts::{BRAND_LETTER_SPACING, DARKNESS, JUSTIFY_NAMES, MIRROR, SPEED};
+use crate::date::DateStyle;
+use crate::epl::{Delivery, FormBackup};
+use crate::meta::JobMeta;
+
+/// Vertical placement of a stack of blocks inside a cell's padded area.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AlignV {
+    #[default]
+    Top,
+    Middle,
+    Bottom,
+    /// Put the first block's baseline this many dots below the padded top.
+    Baseline(u32),
+}
+
+/// Horizontal placement of each block inside the padded area.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AlignH {
+    Left,
+    #[default]
+    Center,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Padding {
+    pub top: u32,
+    pub right: u32,
+    pub bottom: u32,
+    pub left: u32,
+}
+
+impl Padding {
+    pub fn all(v: u32) -> Self {
+        Padding { top: v, right: v, bottom: v, left: v }
+    }
+
+    pub fn top(v: u32) -> Self {
+        Padding { top: v, ..Default::default() }
+    }
+}
+
+/// Something to place: a rendered line, a barcode, ...
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Block {
+    pub width: u32,
+    pub height: u32,
+    /// Baseline offset from the block's top (text lines), used by `AlignV::Baseline`.
+    pub baseline: Option<u32>,
+    /// Space before this block; negative values overlap the previous one.
+    pub gap_before: i32,
+}
+
+impl Block {
+    pub fn new(width: u32, height: u32) -> Self {
+        Block { width, height, baseline: None, gap_before: 0 }
+    }
+
+    pub fn with_baseline(mut self, baseline: u32) -> Self {
+        self.baseline = Some(baseline);
+        self
+    }
+
+    pub fn gap(mut self, gap_before: i32) -> Self {
+        self.gap_before = gap_before;
+        self
+    }
+}
+
+/// Rectangular region of a label that stacks blocks top to bottom.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub padding: Padding,
+    pub align_h: AlignH,
+    pub align_v: AlignV,
+}
+
+impl Cell {
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Cell { x, y, width, height, padding: Padding::default(), align_h: AlignH::Center, align_v: AlignV::Top }
+    }
+
+    pub fn padding(mut self, padding: Padding) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn align(mut self, h: AlignH, v: AlignV) -> Self {
+        self.align_h = h;
+        self.align_v = v;
+        self
+    }
+
+    /// Padded content box (x, y, w, h).
+    pub fn inner(&self) -> (u32, u32, u32, u32) {
+        let p = &self.padding;
+        (
+            self.x + p.left,
+            self.y + p.top,
+            self.width.saturating_sub(p.left + p.right),
+            self.height.saturating_sub(p.top + p.bottom),
+        )
+    }
+
+    /// Equal `cols` x `rows` sub-cells in row-major order, inheriting alignment and padding.
+    pub fn grid(&self, cols: u32, rows: u32) -> Vec<Cell> {
+        let (cw, rh) = (self.width / cols.max(1), self.height / rows.max(1));
+        (0..rows)
+            .flat_map(|r| (0..cols).map(move |c| (c, r)))
+            .map(|(c, r)| Cell { x: self.x + c * cw, y: self.y + r * rh, width: cw, height: rh, ..*self })
+            .collect()
+    }
+
+    /// Top-left corner of every block, stacked in order. Positions are clamped
+    /// to 0 but not to the cell, so oversized content overflows visibly; see
+    /// `offsets` for where blocks clamped at 0 wanted to go.
+    pub fn place(&self, blocks: &[Block]) -> Vec<(u32, u32)> {
+        self.offsets(blocks).into_iter().map(|(x, y)| (x.max(0) as u32, y.max(0) as u32)).collect()
+    }
+
+    /// `place` before clamping: negative where a block starts left of or above
+    /// the label. Blocks still stack under the clamped position of the one above.
+    pub fn offsets(&self, blocks: &[Block]) -> Vec<(i32, i32)> {
+        let (ix, iy, iw, ih) = self.inner();
+        let (ix, iy, iw, ih) = (ix as i32, iy as i32, iw as i32, ih as i32);
+        let stack_h: i32 = blocks.iter().map(|b| b.gap_before + b.height as i32).sum();
+
+        let mut y = match self.align_v {
+            AlignV::Top => iy,
+            AlignV::Middle => iy + (ih - stack_h) / 2,
+            AlignV::Bottom => iy + ih - stack_h,
+            AlignV::Baseline(b) => {
+                let first = blocks.first().map_or(0, |f| f.baseline.unwrap_or(f.height) as i32);
+                iy + b as i32 - first
+            }
+        };
+
+        blocks.iter().map(|b| {
+            let bx = match self.align_h {
+                AlignH::Left => ix,
+                AlignH::Center => ix + (iw - b.width as i32) / 2,
+                AlignH::Right => ix + iw - b.width as i32,
+            };
+            let by = y + b.gap_before;
+            y = by.max(0) + b.height as i32;
+            (bx, by)
+        }).collect()
+    }
+}
+
+/// Blocks flowed down a cell: each is pushed with the space above it and the
+/// positions are worked out when placing, so a bigger font moves everything
+/// under it instead of overlapping it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Flow {
+    pub cell: Cell,
+    pub blocks: Vec<Block>,
+    /// Lowest dot the flow may reach (default: the bottom of the cell).
+    pub limit: u32,
+}
+
+impl Flow {
+    pub fn new(cell: Cell) -> Self {
+        Flow { cell, blocks: Vec::new(), limit: cell.y + cell.height }
+    }
+
+    /// Keep the flow above `limit` instead of the cell bottom, e.g. the label
+    /// edge or the next row.
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Add `block` `gap` dots under the previous one (negative values overlap
+    /// it); returns its index in `place`.
+    pub fn push(&mut self, block: Block, gap: i32) -> usize {
+        self.blocks.push(block.gap(gap));
+        self.blocks.len() - 1
+    }
+
+    /// `push` for chaining.
+    pub fn then(mut self, block: Block, gap: i32) -> Self {
+        self.push(block, gap);
+        self
+    }
+
+    /// Top-left corner of every block (see `Cell::place`), whether or not it fits.
+    pub fn positions(&self) -> Vec<(u32, u32)> {
+        self.cell.place(&self.blocks)
+    }
+
+    /// `positions` before clamping at 0 (see `Cell::offsets`).
+    pub fn offsets(&self) -> Vec<(i32, i32)> {
+        self.cell.offsets(&self.blocks)
+    }
+
+    /// Dots the lowest block reaches past `limit` (0 = fits).
+    pub fn overflow(&self) -> u32 {
+        let bottom = self.positions().iter().zip(&self.blocks).map(|(&(_, y), b)| y + b.height).max().unwrap_or(0);
+        bottom.saturating_sub(self.limit)
+    }
+
+    /// `positions`, or an error if the blocks don't fit above `limit`.
+    pub fn place(&self) -> Result<Vec<(u32, u32)>, String> {
+        match self.overflow() {
+            0 => Ok(self.positions()),
+            over => Err(format!("{} blocks run {} dots past y = {}", self.blocks.len(), over, self.limit)),
+        }
+    }
+}
+
+/// Spacing of the 2x2 product grid (see `build_four_product_job_with_grid`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GridStyle {
+    /// Dots between the two columns; negative values let them overlap.
+    pub gap_x: i32,
+    /// Dots between the two rows; negative values let them overlap.
+    pub gap_y: i32,
+    /// Border kept clear on every side, for stock with a smaller printable area.
+    pub outer_margin: u32,
+    /// Draw `LO` rules through the middle of the gaps.
+    pub draw_separators: bool,
+}
+
+impl Default for GridStyle {
+    fn default() -> Self {
+        GridStyle { gap_x: -2, gap_y: -2, outer_margin: 0, draw_separators: false }
+    }
+}
+
+impl GridStyle {
+    /// Split `area` at its midlines into four cells (row-major), leaving the
+    /// gaps centered on the midlines. Cells inherit `area`'s padding and alignment.
+    pub fn quadrants(&self, area: Cell) -> [Cell; 4] {
+        let split = |start: u32, len: u32, gap: i32| {
+            let mid = (start + len / 2) as i32;
+            let first_end = (mid - gap / 2).max(start as i32) as u32;
+            let second_start = (mid + gap - gap / 2).max(0) as u32;
+            ((start, first_end - start), (second_start, (start + len).saturating_sub(second_start)))
+        };
+        let (left, right) = split(area.x, area.width, self.gap_x);
+        let (top, bottom) = split(area.y, area.height, self.gap_y);
+        [(left, top), (right, top), (left, bottom), (right, bottom)]
+            .map(|((x, width), (y, height))| Cell { x, y, width, height, ..area })
+    }
+}
+
+/// Reading direction of a label layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Direction {
+    /// Name right, price left; products fill the 2x2 grid left to right.
+    #[default]
+    Ltr,
+    /// Price right, name left; products fill the grid right to left, top to bottom.
+    Rtl,
+}
+
+impl Direction {
+    /// Grid column (0 = left) of product `i` in a two-column layout.
+    pub fn column(self, i: usize) -> usize {
+        match self {
+            Direction::Ltr => i % 2,
+            Direction::Rtl => 1 - i % 2,
+        }
+    }
+}
+
+/// Options of the product label builders (`build_*_product_job_with`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct BuildOptions {
+    /// 2x2 grid spacing (four-product label only).
+    pub grid: GridStyle,
+    /// Format of the production / expiry line of products that have dates.
+    pub dates: DateStyle,
+    /// `D` for this stock (default `DARKNESS`); `None` leaves a shared
+    /// printer's configured darkness alone.
+    pub darkness: Option<u8>,
+    /// `S` (default `SPEED`); `None` leaves the printer's setting alone.
+    pub speed: Option<u8>,
+    /// Side of name and price, and the order products fill the grid in.
+    pub direction: Direction,
+    /// `JF`/`JB`/`JC` before the form (`None`: printer setting).
+    pub form_backup: Option<FormBackup>,
+    /// Tear-off, dispenser or cutter (`None`: printer setting).
+    pub delivery: Option<Delivery>,
+    /// `f` stop position, see `epl::STOP_POSITION` (`None`: printer setting).
+    pub stop_position: Option<u8>,
+    /// Print each product's barcode in the symbology its data calls for
+    /// (`Barcode::auto`: EAN-8, UPC-A, EAN-13, Code 128) instead of forcing
+    /// EAN-13; substitutions come back as `Warning::BarcodeFallback`. Long
+    /// Code 128 data can be wider than a cell: watch for `OutOfBounds`.
+    pub auto_barcodes: bool,
+    /// Job id / operator comments and optional printed code (see `Label::set_meta`).
+    pub meta: Option<JobMeta>,
+    /// Print a mirror image for the inside of glass (default `MIRROR`). Text
+    /// and barcode digits are drawn as bitmaps so they flip; the `try_` builders
+    /// reject anything that still can't (see `Label::check_mirror`).
+    pub mirror: bool,
+    /// Extra dots between Latin letters of the brand (default `BRAND_LETTER_SPACING`).
+    pub brand_letter_spacing: f32,
+    /// Stretch short Arabic names with kashida to fill their side of the
+    /// name/price line (default `JUSTIFY_NAMES`).
+    pub justify_names: bool,
+}
+
+impl Default for BuildOptions {
+    fn default() -> Self {
+        BuildOptions {
+            grid: GridStyle::default(),
+            dates: DateStyle::default(),
+            darkness: Some(DARKNESS),
+            speed: Some(SPEED),
+            direction: Direction::default(),
+            form_backup: None,
+            delivery: None,
+            stop_position: None,
+            auto_barcodes: false,
+            meta: None,
+            mirror: MIRROR,
+            brand_letter_spacing: BRAND_LETTER_SPACING,
+            justify_names: JUSTIFY_NAMES,
+        }
+    }
+}
@@ -4,309 +4,375 @@
 //! - Optional bit inversion for GW polarity
 //! - Compensates driver Landscape by rotating in code
 //! - Centers EAN-13 barcodes and keeps HRI visible
+//!
+//! The original `build_*_label_with_brand` functions live on in `v1`, which
+//! also describes how to move to `FontContext` + `Product` + `LabelJob`.
 
-use image::{ImageBuffer, Luma};
-use rusttype::{Font, Scale, point};
-use ar_reshaper::{ArabicReshaper, ReshaperConfig};
-use unicode_bidi::BidiInfo;
+use image::{GrayImage, ImageBuffer, Luma};
+use rusttype::{Scale, point};
+use std::ops::Range;
+
+use barcode::ensure_valid_ean13;
 
 // ======== Config (edit if needed) ========
 
-const LABEL_W: u32 = 440;          // dots (≈55 mm)
-const LABEL_H: u32 = 320;          // dots (≈40 mm)
+pub mod consts;
+use consts::*;
 
-const FONT_PX: f32 = 36.0;         // larger for better readability in 4-product layout
-const BOLD_STROKE: bool = true;    // draw twice w/ 1px offset
+// ======== Public API ========
 
-const DARKNESS: u8 = 8;            // D0..D15 (darker for better contrast like reference)
-const SPEED: u8 = 2;               // S1..S6 (slower for better quality)
+pub mod v1;
+pub use v1::{build_four_product_label_with_brand, build_two_product_label_with_brand};
 
-const NARROW: u32 = 2;             // EAN13 module width (back to 2 like reference)
-const HEIGHT: u32 = 35;            // barcode bar height (smaller for 4-product layout)
+pub mod shelf;
+pub use shelf::{build_shelf_label, PackSize, PackUnit};
 
-const INVERT_BITS: bool = true;      // Invert GW bits for black-on-white
+pub mod receipt;
+pub use receipt::{Receipt, ReceiptLine};
 
-// ======== Public API ========
+/// Same as `build_two_product_label_with_brand`, but also returns layout warnings
+/// (truncated names, tight quiet zones, dense bitmaps).
+pub fn build_two_product_job(ctx: &FontContext, brand: &str, products: &[Product; 2]) -> LabelJob {
+    build_two_product_job_with(ctx, brand, products, &BuildOptions::default())
+}
 
-/// Build a single EPL2 print job for two products (original working implementation).
-/// - `font_bytes`: embedded Arabic font bytes 
-/// - `name1/price1/barcode1` + `name2/price2/barcode2`
-/// Returns raw bytes ready to send to the printer (USB raw write).
-pub fn build_two_product_label_with_brand(
-    font_bytes: &[u8],
-    brand: &str,
-    name1: &str, price1: &str, barcode1: &str,
-    name2: &str, price2: &str, barcode2: &str,
-) -> Vec<u8> {
-    // Ensure barcodes are valid EAN-13 format
-    let bc1 = ensure_valid_ean13(barcode1);
-    let bc2 = ensure_valid_ean13(barcode2);
-
-    // Render brand (large, extra bold)
-    let brand_img = {
-        let font = rusttype::Font::try_from_bytes(font_bytes).expect("bad font");
-        let reshaper = ar_reshaper::ArabicReshaper::new(ar_reshaper::ReshaperConfig::default());
-        let visual = bidi_then_shape(brand, &reshaper);
-        let scale = rusttype::Scale { x: 40.0, y: 40.0 };
-        let vm = font.v_metrics(scale);
-        let ascent = vm.ascent.ceil();
-        let descent = vm.descent.floor();
-        let line_h = (ascent - descent).ceil().max(30.0) as u32;
-        let glyphs: Vec<_> = font.layout(&visual, scale, rusttype::point(0.0, ascent)).collect();
-        let text_w = glyphs.iter().rev()
-            .find_map(|g| g.pixel_bounding_box().map(|bb| bb.max.x as f32))
-            .unwrap_or(0.0).ceil() as u32;
-        let w = (text_w + 4).max(2);
-        let mut img = image::ImageBuffer::from_pixel(w, line_h, Luma([255]));
-        let passes: &[(i32,i32)] = &[(0,0),(1,0),(2,0),(0,1)]; // quad-draw for extra boldness
-        for &(_dx, _dy) in passes {
-            for g in font.layout(&visual, scale, rusttype::point(2.0 + _dx as f32, ascent + _dy as f32)) {
-                if let Some(bb) = g.pixel_bounding_box() {
-                    g.draw(|x, y, v| {
-                        if v > 0.5 { // Lower threshold for crisper rendering (was 0.65)
-                            let px = x + bb.min.x as u32;
-                            let py = y + bb.min.y as u32;
-                            if px < w && py < line_h { img.put_pixel(px, py, Luma([0])); }
-                        }
-                    });
-                }
-            }
-        }
-        img
-    };
-    let (brand_w, brand_h, brand_r) = image_to_row_bytes(&brand_img);
+/// `build_two_product_job` with custom options (date line format, ...).
+pub fn build_two_product_job_with(ctx: &FontContext, brand: &str, products: &[Product; 2], opts: &BuildOptions) -> LabelJob {
+    two_up_label(ctx, [Some(brand); 2], [Some(&products[0]), Some(&products[1])], opts).job()
+}
+
+/// `build_two_product_job_with`, but content reaching past the label edges
+/// (see `Label::check_bounds`) or out of its cell (`Flow::place`) is an error
+/// instead of a job the printer clips.
+pub fn try_build_two_product_job(ctx: &FontContext, brand: &str, products: &[Product; 2], opts: &BuildOptions) -> Result<LabelJob, String> {
+    two_up_label(ctx, [Some(brand); 2], [Some(&products[0]), Some(&products[1])], opts).try_job()
+}
+
+/// `build_two_product_job_with` with a brand per product, for items of several
+/// brands co-packed on one label. A `None` brand leaves the header out and
+/// prints that product's name and price bigger (`BRANDLESS_NAME_SCALE`).
+pub fn build_two_product_job_with_brands(ctx: &FontContext, brands: [Option<&str>; 2], products: &[Product; 2], opts: &BuildOptions) -> LabelJob {
+    two_up_label(ctx, brands, [Some(&products[0]), Some(&products[1])], opts).job()
+}
+
+/// The unserialized label behind `build_two_product_job_with`, with its layout
+/// guides (see `render_preview_debug`).
+pub fn two_product_label(ctx: &FontContext, brand: &str, products: &[Product; 2], opts: &BuildOptions) -> (Label, Vec<Warning>) {
+    let built = two_up_label(ctx, [Some(brand); 2], [Some(&products[0]), Some(&products[1])], opts);
+    (built.label, built.warnings)
+}
+
+/// Same as `build_four_product_label_with_brand`, but also returns layout warnings.
+pub fn build_four_product_job(ctx: &FontContext, brand: &str, products: &[Product; 4]) -> LabelJob {
+    build_four_product_job_with_grid(ctx, brand, products, &GridStyle::default())
+}
+
+/// `build_four_product_job` with custom quadrant gaps, margins and separators.
+pub fn build_four_product_job_with_grid(ctx: &FontContext, brand: &str, products: &[Product; 4], grid: &GridStyle) -> LabelJob {
+    build_four_product_job_with(ctx, brand, products, &BuildOptions { grid: *grid, ..BuildOptions::default() })
+}
+
+/// `build_four_product_job` with custom options (grid, date line format, ...).
+pub fn build_four_product_job_with(ctx: &FontContext, brand: &str, products: &[Product; 4], opts: &BuildOptions) -> LabelJob {
+    let cells = [Some(&products[0]), Some(&products[1]), Some(&products[2]), Some(&products[3])];
+    four_up_label(ctx, [Some(brand); 4], cells, opts).job()
+}
+
+/// `build_four_product_job_with` with a brand per quadrant, as
+/// `build_two_product_job_with_brands`.
+pub fn build_four_product_job_with_brands(ctx: &FontContext, brands: [Option<&str>; 4], products: &[Product; 4], opts: &BuildOptions) -> LabelJob {
+    let cells = [Some(&products[0]), Some(&products[1]), Some(&products[2]), Some(&products[3])];
+    four_up_label(ctx, brands, cells, opts).job()
+}
+
+/// The unserialized label behind `build_four_product_job_with`, as `two_product_label`.
+pub fn four_product_label(ctx: &FontContext, brand: &str, products: &[Product; 4], opts: &BuildOptions) -> (Label, Vec<Warning>) {
+    let built = four_up_label(ctx, [Some(brand); 4], [Some(&products[0]), Some(&products[1]), Some(&products[2]), Some(&products[3])], opts);
+    (built.label, built.warnings)
+}
+
+/// `build_four_product_job_with` with bounds checking, as `try_build_two_product_job`.
+pub fn try_build_four_product_job(ctx: &FontContext, brand: &str, products: &[Product; 4], opts: &BuildOptions) -> Result<LabelJob, String> {
+    let cells = [Some(&products[0]), Some(&products[1]), Some(&products[2]), Some(&products[3])];
+    four_up_label(ctx, [Some(brand); 4], cells, opts).try_job()
+}
+
+// ======== Measurement ========
+
+/// Size in dots of `text` rendered as one line at `font_px` (shaped, no bolding).
+pub fn measure_text(text: &str, font_px: f32, ctx: &FontContext) -> (u32, u32) {
+    ctx.measure(text, font_px)
+}
+
+/// Size of a builder's name/price line squeezed into `max_width`, plus how many
+/// dots of the name would be cut off (0 = fits).
+pub fn measure_name_price(name: &str, price: &str, font_px: f32, max_width: u32, ctx: &FontContext) -> ((u32, u32), u32) {
+    let (img, clipped) = render_name_price_space_between(name, price, ctx, font_px, max_width, BOLD_STROKE, Direction::Ltr, JUSTIFY_NAMES);
+    (img.dimensions(), clipped)
+}
+
+/// Width in dots of the bars a `B` command draws (quiet zones and HRI excluded).
+pub fn barcode_width(symbology: Symbology, data: &str, narrow: u32) -> u32 {
+    symbology.width(data, narrow)
+}
+
+/// Two products stacked vertically; `None` cells are left blank.
+fn two_up_label(ctx: &FontContext, brands: [Option<&str>; 2], cells: [Option<&Product>; 2], opts: &BuildOptions) -> ProductLabel {
+    let mut warnings = Vec::new();
+    let blank = Product::default();
+    let products = cells.map(|c| c.unwrap_or(&blank));
+
+    // Ensure barcodes are valid EAN-13 format (or the symbology their data calls for)
+    let bcs = product_barcodes(&cells, opts, &mut warnings);
+
+    // Render brands (large, extra bold)
+    let brand_imgs = brands.map(|b| b.map(|b| render_brand(b, ctx, opts.brand_letter_spacing)));
 
     // Render product lines with space-between layout (name right, price left)
     let max_product_width = LABEL_W - 20; // Leave some padding
-    let (w1, h1, r1) = render_name_price_space_between(name1, price1, font_bytes, 52.0, max_product_width, BOLD_STROKE);
-    let (w2, h2, r2) = render_name_price_space_between(name2, price2, font_bytes, 52.0, max_product_width, BOLD_STROKE);
+    let (mut lines, mut clipped_dots, mut name_px) = (Vec::new(), Vec::new(), Vec::new());
+    for (i, p) in products.iter().enumerate() {
+        let (img, clipped, px) = product_line(p, ctx, 52.0, brands[i].is_some(), max_product_width, opts);
+        if clipped > 0 {
+            warnings.push(Warning::TruncatedName { product: i, name: p.name.clone(), clipped_dots: clipped });
+        }
+        lines.push(img);
+        clipped_dots.push(clipped);
+        name_px.push(px);
+    }
+    let (dates, date_px): (Vec<Option<GrayImage>>, Vec<Option<f32>>) = products.iter()
+        .map(|p| render_dates(p, &opts.dates, ctx, max_product_width).map_or((None, None), |(img, px)| (Some(img), Some(px))))
+        .unzip();
+    let plus: Vec<Option<GrayImage>> = products.iter().map(|p| render_plu(p, ctx)).collect();
+    let report = product_reports(&cells, &name_px, &clipped_dots, &date_px, &bcs);
 
-    // Layout: two vertical halves
+    // Layout: two vertical halves, each stacking brand / name+price / dates / barcode (centered)
     let half_h = LABEL_H / 2;  // 160 dots per half
+    let halves = Cell::new(0, 0, LABEL_W, LABEL_H).padding(Padding::top(TWO_UP_TOP_PAD)).grid(1, 2);
+
+    let mut errors = Vec::new();
+    let flow = |i: usize, text_gap: i32| product_flow(halves[i], brand_imgs[i].as_ref(), &lines[i], dates[i].as_ref(), plus[i].as_ref(), text_gap, TWO_UP_BARCODE_GAP);
+    let top = place_flow(&flow(0, TWO_UP_TEXT_GAP), cells[0].map(|_| 0), &mut warnings, &mut errors);
+    let bottom = place_flow(&flow(1, TWO_UP_TEXT_GAP + TWO_UP_ROW_GAP), cells[1].map(|_| 1), &mut warnings, &mut errors);
+    let bx_center = top.last().unwrap().0.max(0) as u32;
+
+    let mut label = Label::new(LABEL_W, LABEL_H);
+    (label.darkness, label.speed, label.mirror) = (opts.darkness, opts.speed, opts.mirror);
+    (label.form_backup, label.delivery, label.stop_position) = (opts.form_backup, opts.delivery, opts.stop_position);
+    for (i, ((line, date), (placed, half_bottom))) in lines.into_iter().zip(dates)
+        .zip([(top, half_h), (bottom, LABEL_H)])
+        .enumerate()
+    {
+        label.guides.push(cell_guide(&halves[i]));
+        if cells[i].is_some() {
+            label.guides.extend(baseline_guides(ctx, &placed, brand_imgs[i].as_ref(), &line, name_px[i]));
+            if let Some(brand_img) = &brand_imgs[i] {
+                label.graphic_at(placed[0].0, placed[0].1, brand_img.clone());
+            }
+            place_product(&mut label, ctx, products[i], &bcs[i], line, date, &placed, bx_center, half_bottom);
+        }
+    }
 
-    // Center brand horizontally in each half
-    let brand_x = (LABEL_W - brand_w) / 2;
-    let brand_y1 = 8;  // shifted up by 2px (was 10)
-    let brand_y2 = half_h + 8;  // shifted up by 2px (was half_h + 10)
-
-    // Center product text horizontally
-    let x1 = (LABEL_W - w1) / 2;
-    let x2 = (LABEL_W - w2) / 2;
-
-    // Move content down to make space for brand, but reduce gap
-    let brand_to_text_gap: i32 = -6; // further tighten: negative gap pulls product info closer to brand
-    let row_gap: i32 = 4; // 4px between the two rows
-    let text1_y = (brand_y1 as i32 + brand_h as i32 + brand_to_text_gap).max(0) as u32;
-    let bc1_y = (text1_y as i32 + h1 as i32 + 4).max(0) as u32;  // reduced gap by 4px (was 8)
-    let text2_y = (brand_y2 as i32 + brand_h as i32 + brand_to_text_gap + row_gap).max(0) as u32;
-    let bc2_y = (text2_y as i32 + h2 as i32 + 4).max(0) as u32;  // reduced gap by 4px (was 8)
-
-    let bx_center = center_x_for_ean13_single(LABEL_W, NARROW);
-
-    let mut buf = Vec::new();
-    epl_line(&mut buf, "N");
-    epl_line(&mut buf, &format!("q{}", LABEL_W));
-    epl_line(&mut buf, &format!("Q{},{}", LABEL_H, 24));
-    epl_line(&mut buf, &format!("D{}", DARKNESS));
-    epl_line(&mut buf, &format!("S{}", SPEED));
-
-    // Top half
-    gw_bytes(&mut buf, brand_x, brand_y1, brand_w, brand_h, &brand_r);
-    gw_bytes(&mut buf, x1, text1_y, w1, h1, &r1);
-    epl_line(&mut buf, &format!("B{},{},0,E30,{},{},{},B,\"{}\"",
-        bx_center, bc1_y, NARROW, 3, HEIGHT, bc1));
-
-    // Bottom half
-    gw_bytes(&mut buf, brand_x, brand_y2, brand_w, brand_h, &brand_r);
-    gw_bytes(&mut buf, x2, text2_y, w2, h2, &r2);
-    epl_line(&mut buf, &format!("B{},{},0,E30,{},{},{},B,\"{}\"",
-        bx_center, bc2_y, NARROW, 3, HEIGHT, bc2));
-
-    epl_line(&mut buf, "P1");
-    buf
-}
-
-/// Build a single EPL2 print job for four products in 2x2 grid.
-/// - `font_bytes`: embedded Arabic font bytes 
-/// - Four sets of `name/price/barcode` for each quadrant
-/// Returns raw bytes ready to send to the printer (USB raw write).
-pub fn build_four_product_label_with_brand(
-    font_bytes: &[u8],
-    brand: &str,
-    name1: &str, price1: &str, barcode1: &str,
-    name2: &str, price2: &str, barcode2: &str,
-    name3: &str, price3: &str, barcode3: &str,
-    name4: &str, price4: &str, barcode4: &str,
-) -> Vec<u8> {
-    // Ensure barcodes are valid EAN-13 format
-    let bc1 = ensure_valid_ean13(barcode1);
-    let bc2 = ensure_valid_ean13(barcode2);
-    let bc3 = ensure_valid_ean13(barcode3);
-    let bc4 = ensure_valid_ean13(barcode4);
-
-    // Render brand (extra bold, large size) with quad-draw for extra boldness
-    let brand_img = {
-        let font = rusttype::Font::try_from_bytes(font_bytes).expect("bad font");
-        let reshaper = ar_reshaper::ArabicReshaper::new(ar_reshaper::ReshaperConfig::default());
-        let visual = bidi_then_shape(brand, &reshaper);
-        let scale = rusttype::Scale { x: 40.0, y: 40.0 };
-        let vm = font.v_metrics(scale);
-        let ascent = vm.ascent.ceil();
-        let descent = vm.descent.floor();
-        let line_h = (ascent - descent).ceil().max(30.0) as u32;
-        let glyphs: Vec<_> = font.layout(&visual, scale, rusttype::point(0.0, ascent)).collect();
-        let text_w = glyphs.iter().rev()
-            .find_map(|g| g.pixel_bounding_box().map(|bb| bb.max.x as f32))
-            .unwrap_or(0.0).ceil() as u32;
-        let w = (text_w + 4).max(2);
-        let mut img = image::ImageBuffer::from_pixel(w, line_h, Luma([255]));
-        let passes: &[(i32,i32)] = &[(0,0),(1,0),(2,0),(0,1)]; // quad-draw for extra boldness
-        for &(_dx, _dy) in passes {
-            for g in font.layout(&visual, scale, rusttype::point(2.0 + _dx as f32, ascent + _dy as f32)) {
-                if let Some(bb) = g.pixel_bounding_box() {
-                    g.draw(|x, y, v| {
-                        if v > 0.5 { // Lower threshold for crisper rendering (was 0.65)
-                            let px = x + bb.min.x as u32;
-                            let py = y + bb.min.y as u32;
-                            if px < w && py < line_h { img.put_pixel(px, py, Luma([0])); }
-                        }
-                    });
-                }
+    if let Some(meta) = &opts.meta {
+        label.set_meta(meta.clone());
+    }
+    if label.mirror {
+        label.render_text(ctx);
+    }
+    warnings.extend(label.warnings());
+    ProductLabel { label, warnings, products: report, errors }
+}
+
+/// Four products in a 2x2 grid; `None` cells are left blank.
+fn four_up_label(ctx: &FontContext, brands: [Option<&str>; 4], cells: [Option<&Product>; 4], opts: &BuildOptions) -> ProductLabel {
+    let grid = &opts.grid;
+    let mut warnings = Vec::new();
+    let blank = Product::default();
+    let products = cells.map(|c| c.unwrap_or(&blank));
+
+    // Ensure barcodes are valid EAN-13 format (or the symbology their data calls for)
+    let bcs = product_barcodes(&cells, opts, &mut warnings);
+
+    // Render brands (extra bold, large size) with heavy synthetic bold
+    let brand_imgs = brands.map(|b| b.map(|b| render_brand(b, ctx, opts.brand_letter_spacing)));
+
+    // Grid spans the label (minus the margin), moved down to clear the top edge
+    let m = grid.outer_margin;
+    let area = Cell::new(m, FOUR_UP_GRID_Y + m, LABEL_W.saturating_sub(2 * m), LABEL_H.saturating_sub(FOUR_UP_GRID_Y + 2 * m)).padding(Padding::top(FOUR_UP_TOP_PAD));
+    let quads = grid.quadrants(area);
+    // Quadrant of each product, in reading order
+    let cells_at: Vec<Cell> = (0..4).map(|i| quads[i / 2 * 2 + opts.direction.column(i)]).collect();
+
+    // Render product lines with space-between layout (name and price on opposite sides)
+    let max_product_width = quads[0].width.saturating_sub(10); // Quadrant width minus padding
+    let (mut lines, mut clipped_dots, mut name_px) = (Vec::new(), Vec::new(), Vec::new());
+    for (i, p) in products.iter().enumerate() {
+        let (img, clipped, px) = product_line(p, ctx, FONT_PX, brands[i].is_some(), max_product_width, opts);
+        if clipped > 0 {
+            warnings.push(Warning::TruncatedName { product: i, name: p.name.clone(), clipped_dots: clipped });
+        }
+        lines.push(img);
+        clipped_dots.push(clipped);
+        name_px.push(px);
+    }
+    let (dates, date_px): (Vec<Option<GrayImage>>, Vec<Option<f32>>) = products.iter()
+        .map(|p| render_dates(p, &opts.dates, ctx, max_product_width).map_or((None, None), |(img, px)| (Some(img), Some(px))))
+        .unzip();
+    let plus: Vec<Option<GrayImage>> = products.iter().map(|p| render_plu(p, ctx)).collect();
+    let report = product_reports(&cells, &name_px, &clipped_dots, &date_px, &bcs);
+
+    // Each quadrant stacks brand / name+price / dates / barcode, centered horizontally
+    // Bars + HRI of the top row must stay above the bottom row's brand
+    let top_row_bottom = quads[2].y;
+    let label_bottom = LABEL_H.saturating_sub(m);
+    let mut errors = Vec::new();
+    let placed: Vec<Vec<(i32, i32)>> = cells_at.iter().enumerate()
+        .map(|(i, cell)| {
+            let flow = product_flow(*cell, brand_imgs[i].as_ref(), &lines[i], dates[i].as_ref(), plus[i].as_ref(), FOUR_UP_TEXT_GAP, FOUR_UP_BARCODE_GAP)
+                .limit(if i < 2 { top_row_bottom } else { label_bottom });
+            place_flow(&flow, cells[i].map(|_| i), &mut warnings, &mut errors)
+        })
+        .collect();
+
+    // A barcode wider than its column runs into the next one
+    for (i, cell) in cells_at.iter().enumerate().filter(|&(i, _)| cells[i].is_some()) {
+        let bars = bcs[i].symbology.width(&bcs[i].data, NARROW);
+        if bars > cell.width {
+            warnings.push(Warning::CellOverflow { product: i, overflow: bars - cell.width });
+            errors.push(format!("product {}: {}-dot barcode is wider than its {}-dot column", i + 1, bars, cell.width));
+        }
+    }
+
+    // Left column sits right of center, and at least a quiet zone in from the edge
+    let bc_left_x = (quads[0].x + Symbology::Ean13.center_x("", NARROW, quads[0].width) + FOUR_UP_LEFT_BARCODE_SHIFT)
+        .max(Symbology::Ean13.quiet_zone().0 * NARROW);
+    let bc_right_x = quads[1].x + Symbology::Ean13.center_x("", NARROW, quads[1].width);
+
+    let mut label = Label::new(LABEL_W, LABEL_H);
+    (label.darkness, label.speed, label.mirror) = (opts.darkness, opts.speed, opts.mirror);
+    (label.form_backup, label.delivery, label.stop_position) = (opts.form_backup, opts.delivery, opts.stop_position);
+    let mut lines = lines.into_iter();
+    let mut dates = dates.into_iter();
+
+    // Per row: both brands, then product info and barcode in reading order
+    for (row, row_bottom) in [(0, top_row_bottom), (1, label_bottom)] {
+        let row_cells = [2 * row, 2 * row + 1];
+        for i in row_cells {
+            if let (Some(_), Some(brand_img)) = (cells[i], &brand_imgs[i]) {
+                label.graphic_at(placed[i][0].0, placed[i][0].1, brand_img.clone());
             }
         }
-        img
-    };
-    let (brand_w, brand_h, brand_r) = image_to_row_bytes(&brand_img);
+        for i in row_cells {
+            let (line, date) = (lines.next().unwrap(), dates.next().unwrap());
+            label.guides.push(cell_guide(&cells_at[i]));
+            if cells[i].is_none() {
+                continue;
+            }
+            label.guides.extend(baseline_guides(ctx, &placed[i], brand_imgs[i].as_ref(), &line, name_px[i]));
+            let bc_x = if opts.direction.column(i) == 0 { bc_left_x } else { bc_right_x };
+            place_product(&mut label, ctx, products[i], &bcs[i], line, date, &placed[i], bc_x, row_bottom);
+        }
+    }
 
-    // Equal quadrants: 440÷2=220 width, 320÷2=160 height per quadrant
-    let quad_w = LABEL_W / 2;  // 220 dots per column
-    let quad_h = LABEL_H / 2;  // 160 dots per row
-    let gap: i32 = -2;         // Horizontal gap between quadrants (negative to overlap slightly, reducing space by 6px from original 4)
-    let grid_offset_y = 18;    // Move entire grid down (shifted up by 2px from 20)
-    
-    // Render product lines with space-between layout (name right, price left)
-    let max_product_width = ((quad_w as i32 - gap/2 - 10).max(0)) as u32; // Quadrant width minus padding
-    let (w1, h1, r1) = render_name_price_space_between(name1, price1, font_bytes, FONT_PX, max_product_width, BOLD_STROKE);
-    let (w2, h2, r2) = render_name_price_space_between(name2, price2, font_bytes, FONT_PX, max_product_width, BOLD_STROKE);
-    let (w3, h3, r3) = render_name_price_space_between(name3, price3, font_bytes, FONT_PX, max_product_width, BOLD_STROKE);
-    let (w4, h4, r4) = render_name_price_space_between(name4, price4, font_bytes, FONT_PX, max_product_width, BOLD_STROKE);
-    
-    // Quadrant boundaries with gap:
-    // Left column: 0 to (220-gap/2), Right column: (220+gap/2) to 440
-    // Top row: grid_offset_y to (160-gap/2+offset), Bottom row: (160+gap/2+offset) to 320
-    
-    // Center brand horizontally in each quadrant
-    let brand_x_left = ((quad_w as i32 - gap/2 - brand_w as i32) / 2).max(0) as u32;
-    let brand_x_right = (quad_w as i32 + gap/2 + (quad_w as i32 - brand_w as i32) / 2).max(0) as u32;
-    let brand_y_top = grid_offset_y + 4;
-    let brand_y_bottom = (grid_offset_y as i32 + quad_h as i32 + gap/2 + 4).max(0) as u32;
-
-    // Center product text horizontally within each quadrant
-    let x1 = ((quad_w as i32 - gap/2 - w1 as i32) / 2).max(0) as u32;
-    let x2 = (quad_w as i32 + gap/2 + (quad_w as i32 - w2 as i32) / 2).max(0) as u32;
-    let x3 = ((quad_w as i32 - gap/2 - w3 as i32) / 2).max(0) as u32;
-    let x4 = (quad_w as i32 + gap/2 + (quad_w as i32 - w4 as i32) / 2).max(0) as u32;
-
-    // Content vertical positions: brand at top, then product, then barcode
-    // Shift content up by 10px for better balance
-    let shift_up = 10;
-    let text1_y = brand_y_top + brand_h + 6 - shift_up;
-    let bc1_y = text1_y + h1 + 3;
-
-    let text2_y = brand_y_top + brand_h + 6 - shift_up;
-    let bc2_y = text2_y + h2 + 3;
-
-    let text3_y = brand_y_bottom + brand_h + 6 - shift_up;
-    let bc3_y = text3_y + h3 + 3;
-
-    let text4_y = brand_y_bottom + brand_h + 6 - shift_up;
-    let bc4_y = text4_y + h4 + 3;
-
-    let bc_left_x = (center_x_for_ean13_column(((quad_w as i32 - gap/2).max(0)) as u32, NARROW) as i32 + 4).max(0) as u32;
-    let bc_right_x = (quad_w as i32 + gap/2 + center_x_for_ean13_column(((quad_w as i32 - gap/2).max(0)) as u32, NARROW) as i32).max(0) as u32;
-
-    let mut buf = Vec::<u8>::new();
-    epl_line(&mut buf, "N");
-    epl_line(&mut buf, &format!("q{}", LABEL_W));
-    epl_line(&mut buf, &format!("Q{},{}", LABEL_H, 24));
-    epl_line(&mut buf, &format!("D{}", DARKNESS));
-    epl_line(&mut buf, &format!("S{}", SPEED));
-
-    // Top row: Brand, Product 1 (left) and Product 2 (right)
-    gw_bytes(&mut buf, brand_x_left, brand_y_top, brand_w, brand_h, &brand_r);
-    gw_bytes(&mut buf, brand_x_right, brand_y_top, brand_w, brand_h, &brand_r);
-    gw_bytes(&mut buf, x1, text1_y, w1, h1, &r1);
-    epl_line(&mut buf, &format!("B{},{},0,E30,{},{},{},B,\"{}\"",
-        bc_left_x, bc1_y, NARROW, 3, HEIGHT, bc1));
-    gw_bytes(&mut buf, x2, text2_y, w2, h2, &r2);
-    epl_line(&mut buf, &format!("B{},{},0,E30,{},{},{},B,\"{}\"",
-        bc_right_x, bc2_y, NARROW, 3, HEIGHT, bc2));
-
-    // Bottom row: Brand, Product 3 (left) and Product 4 (right)
-    gw_bytes(&mut buf, brand_x_left, brand_y_bottom, brand_w, brand_h, &brand_r);
-    gw_bytes(&mut buf, brand_x_right, brand_y_bottom, brand_w, brand_h, &brand_r);
-    gw_bytes(&mut buf, x3, text3_y, w3, h3, &r3);
-    epl_line(&mut buf, &format!("B{},{},0,E30,{},{},{},B,\"{}\"",
-        bc_left_x, bc3_y, NARROW, 3, HEIGHT, bc3));
-    gw_bytes(&mut buf, x4, text4_y, w4, h4, &r4);
-    epl_line(&mut buf, &format!("B{},{},0,E30,{},{},{},B,\"{}\"",
-        bc_right_x, bc4_y, NARROW, 3, HEIGHT, bc4));
-
-    epl_line(&mut buf, "P1");  // Print exactly ONE label
-    buf
+    if grid.draw_separators {
+        // Rules through the middle of the gaps, clipped to the margins
+        let half = SEPARATOR_DOTS / 2;
+        let mid_x = ((quads[0].x + quads[0].width + quads[1].x) / 2).saturating_sub(half);
+        let mid_y = ((quads[0].y + quads[0].height + quads[2].y) / 2).saturating_sub(half);
+        label.line(mid_x, area.y, SEPARATOR_DOTS, label_bottom.saturating_sub(area.y));
+        label.line(m, mid_y, area.width, SEPARATOR_DOTS);
+    }
+
+    if let Some(meta) = &opts.meta {
+        label.set_meta(meta.clone());
+    }
+    if label.mirror {
+        label.render_text(ctx);
+    }
+    warnings.extend(label.warnings());
+    ProductLabel { label, warnings, products: report, errors }
 }
 
 // ======== Arabic rendering ========
 
-/// Visual-order string: BiDi runs; reshape only RTL runs.
-fn bidi_then_shape(text: &str, reshaper: &ArabicReshaper) -> String {
-    let info = BidiInfo::new(text, None);
-    let para = &info.paragraphs[0];
-    let (levels, ranges) = info.visual_runs(para, para.range.clone());
-
-    let mut out = String::new();
-    // Visual order runs; reshape RTL runs only, preserve LTR (digits) order
-    for (level, range) in levels.into_iter().zip(ranges.into_iter()) {
-        let slice = &text[range];
-        if level.is_rtl() {
-            // Only reverse if it's actually Arabic text (not digits/punctuation)
-            let shaped = reshaper.reshape(slice);
-            // Check if the slice contains Arabic letters vs just digits/symbols
-            if slice.chars().any(|c| c >= '\u{0600}' && c <= '\u{06FF}') {
-                // Contains Arabic - reverse after shaping
-                let reversed: String = shaped.chars().rev().collect();
-                out.push_str(&reversed);
-            } else {
-                // Just numbers/punctuation - don't reverse
-                out.push_str(&shaped);
-            }
-        } else {
-            out.push_str(slice);
+/// Production / expiry line of `p` (`None` without dates), scaled down to fit
+/// `max_w` (4-up cells) and cut if it is still too wide, with the size it ended up at.
+fn render_dates(p: &Product, style: &DateStyle, ctx: &FontContext, max_w: u32) -> Option<(GrayImage, f32)> {
+    let text = style.line(p.produced, p.expires)?;
+    let ctx = ctx.role(FontRole::Detail);
+    let mut font_px = style.font_px;
+    let mut img = ctx.render_line(&text, font_px, 0);
+    if img.width() > max_w {
+        font_px = style.font_px * max_w as f32 / img.width() as f32;
+        img = ctx.render_line(&text, font_px, 0);
+    }
+    let w = img.width().min(max_w);
+    // Keep the right end: the line is right-to-left with the Arabic prefixes
+    Some((image::imageops::crop_imm(&img, img.width() - w, 0, w, img.height()).to_image(), font_px))
+}
+
+/// Name+price line at `font_px`, or for a product without a brand at
+/// `BRANDLESS_NAME_SCALE` times that, unless the bigger line cuts off more of
+/// the name. Returns the image, the dots cut off and the size used.
+fn product_line(p: &Product, ctx: &FontContext, font_px: f32, branded: bool, max_w: u32, opts: &BuildOptions) -> (GrayImage, u32, f32) {
+    let line = |px: f32| render_name_price_space_between(&p.name, &p.price, ctx, px, max_w, BOLD_STROKE, opts.direction, opts.justify_names);
+    let (img, clipped) = line(font_px);
+    if !branded {
+        let big_px = font_px * BRANDLESS_NAME_SCALE;
+        let (big, big_clipped) = line(big_px);
+        if big_clipped <= clipped {
+            return (big, big_clipped, big_px);
         }
     }
-    out
+    (img, clipped, font_px)
+}
+
+/// Brand header: large, extra bold (heavy dilation), tight width.
+fn render_brand(brand: &str, ctx: &FontContext, letter_spacing: f32) -> GrayImage {
+    let ctx = ctx.role(FontRole::Brand);
+    let style = TextStyle { letter_spacing, ..TextStyle::bold(BoldStrength::Heavy) };
+    let key = cache::RenderKey::new("brand", brand, 40.0, 0, &style);
+    ctx.cached(key, || rasterize_brand(brand, ctx, &style)).image.clone()
+}
+
+fn rasterize_brand(brand: &str, ctx: &FontContext, style: &TextStyle) -> GrayImage {
+    let font = ctx.font();
+    let visual = ctx.shape_styled(brand, 40.0, style);
+    let scale = Scale { x: 40.0, y: 40.0 };
+    let vm = font.v_metrics(scale);
+    let ascent = vm.ascent.ceil();
+    let descent = vm.descent.floor();
+    let line_h = (ascent - descent).ceil().max(30.0) as u32;
+    let (overhang, text_w) = ctx.advance_extent(&visual, 40.0);
+    let w = (text_w + 4).max(2);
+    let mut img = ImageBuffer::from_pixel(w, line_h, Luma([255]));
+    ctx.draw(&mut img, &visual, 40.0, point((2 + overhang) as f32, ascent), style);
+    img
 }
 
 /// Render name (right-aligned) and price (left-aligned) in a space-between layout.
-/// Returns (width, height, row_bytes) for the combined image.
+/// Returns the combined image and how many dots of the name were cut off (0 = fits).
 /// Price gets priority - if name is too long, it will be truncated.
+#[allow(clippy::too_many_arguments)]
 fn render_name_price_space_between(
     name: &str,
     price: &str,
-    font_bytes: &[u8],
+    ctx: &FontContext,
     font_px: f32,
     max_width: u32,
     bold: bool,
-) -> (u32, u32, Vec<u8>) {
-    let font = Font::try_from_bytes(font_bytes).expect("bad font");
-    let reshaper = ArabicReshaper::new(ReshaperConfig::default());
+    direction: Direction,
+    justify: bool,
+) -> (GrayImage, u32) {
+    let ctx = ctx.role(FontRole::Body);
+    let font = ctx.font();
     
     // Render price with currency (left side in final output, but right in Arabic)
     let price_text = format!("{} {}", price, "ج.م");
-    let price_visual = bidi_then_shape(&price_text, &reshaper);
+    let price_visual = ctx.shape(&price_text, font_px);
     
     // Render name (right side in final output, but left in Arabic)
-    let name_visual = bidi_then_shape(name, &reshaper);
+    let name_visual = ctx.shape(name, font_px);
     
     let scale = Scale { x: font_px, y: font_px };
     let vm = font.v_metrics(scale);
@@ -315,122 +381,309 @@ fn render_name_price_space_between(
     let line_h = (ascent - descent).ceil().max(30.0) as u32;
     
     // Measure price width (always full)
-    let price_glyphs: Vec<_> = font.layout(&price_visual, scale, point(0.0, ascent)).collect();
-    let price_w = price_glyphs.iter().rev()
-        .find_map(|g| g.pixel_bounding_box().map(|bb| bb.max.x as f32))
-        .unwrap_or(0.0).ceil() as u32;
+    let (price_overhang, price_w) = ctx.advance_extent(&price_visual, font_px);
     
     // Measure name width
-    let name_glyphs: Vec<_> = font.layout(&name_visual, scale, point(0.0, ascent)).collect();
-    let name_w_full = name_glyphs.iter().rev()
-        .find_map(|g| g.pixel_bounding_box().map(|bb| bb.max.x as f32))
-        .unwrap_or(0.0).ceil() as u32;
+    let mut name_visual = name_visual;
+    let (mut name_overhang, name_w_full) = ctx.advance_extent(&name_visual, font_px);
     
     let min_gap = 10; // Minimum gap between name and price
     let left_padding = 5; // Left padding for price
     let available_for_name = max_width.saturating_sub(price_w + min_gap + left_padding);
+
+    // Optionally stretch a short Arabic name with kashida to fill its side
+    let mut name_w_full = name_w_full;
+    if justify && name_w_full < available_for_name {
+        let stretched = ctx.justify_kashida(name, font_px, available_for_name);
+        name_visual = ctx.shape(&stretched, font_px);
+        (name_overhang, name_w_full) = ctx.advance_extent(&name_visual, font_px);
+    }
     let name_w = name_w_full.min(available_for_name);
     
     let total_w = max_width;
     let mut img = ImageBuffer::from_pixel(total_w, line_h, Luma([255]));
     
-    let passes: &[(i32,i32)] = if bold { &[(0,0),(1,0)] } else { &[(0,0)] };
-    
-    // Draw price on the left with 5px padding (x=5)
-    for (dx, dy) in passes {
-        for g in font.layout(&price_visual, scale, point(left_padding as f32 + *dx as f32, ascent + *dy as f32)) {
-            if let Some(bb) = g.pixel_bounding_box() {
-                g.draw(|x, y, v| {
-                    if v > 0.5 {
-                        let px = x + bb.min.x as u32;
-                        let py = y + bb.min.y as u32;
-                        if px < total_w && py < line_h { img.put_pixel(px, py, Luma([0])); }
-                    }
-                });
-            }
-        }
+    let price_style = TextStyle::bold(if bold { BoldStrength::Light } else { BoldStrength::Off });
+
+    // Price 5px in from its edge; the name fills the other side and has always
+    // been drawn unbolded. Long names lose their visual end in Ltr and their
+    // visual start in Rtl, the part away from the price either way.
+    let (price_x, name_x) = match direction {
+        Direction::Ltr => (left_padding as f32, (total_w - name_w) as f32),
+        // A price wider than the line starts at the left edge like in Ltr
+        Direction::Rtl => (total_w.saturating_sub(left_padding + price_w) as f32, name_w as f32 - name_w_full as f32),
+    };
+    ctx.draw(&mut img, &price_visual, font_px, point(price_x + price_overhang as f32, ascent), &price_style);
+    ctx.draw(&mut img, &name_visual, font_px, point(name_x + name_overhang as f32, ascent), &TextStyle::default());
+
+    (img, name_w_full - name_w)
+}
+// ======== Layout helpers ========
+
+/// Flow of one product cell: brand, name+price, optional date line, barcode.
+/// With dates or a PLU line the bars get shorter by their height (down to
+/// `MIN_BAR_HEIGHT`), so the PLU under the digits takes no extra room.
+fn product_flow(cell: Cell, brand: Option<&GrayImage>, line: &GrayImage, dates: Option<&GrayImage>, plu: Option<&GrayImage>, text_gap: i32, barcode_gap: i32) -> Flow {
+    // Without a brand the first block is empty and the line starts at the top
+    let mut flow = match brand {
+        Some(b) => Flow::new(cell).then(Block::new(b.width(), b.height()), 0).then(Block::new(line.width(), line.height()), text_gap),
+        None => Flow::new(cell).then(Block::new(0, 0), 0).then(Block::new(line.width(), line.height()), 0),
+    };
+    if let Some(d) = dates {
+        flow.push(Block::new(d.width(), d.height()), DATE_GAP as i32);
     }
-    
-    // Draw name on the right (x = total_w - name_w)
-    let name_x = total_w - name_w;
-    for &(_dx, _dy) in passes {
-        for g in font.layout(&name_visual, scale, point(0.0, ascent)) {
-            if let Some(bb) = g.pixel_bounding_box() {
-                g.draw(|x, y, v| {
-                    if v > 0.5 {
-                        let px = x + bb.min.x as u32 + name_x;
-                        let py = y + bb.min.y as u32;
-                        if px < total_w && py < line_h { img.put_pixel(px, py, Luma([0])); }
-                    }
-                });
-            }
-        }
+    flow.then(Block::new(barcode_width(Symbology::Ean13, "", NARROW), bar_height(dates, plu)), barcode_gap)
+}
+
+// Signed positions of a product's flow (`Flow::offsets`); one that doesn't fit
+// (`Flow::place`) is placed anyway, with a warning and the error for `try_build_*`
+fn place_flow(flow: &Flow, product: Option<usize>, warnings: &mut Vec<Warning>, errors: &mut Vec<String>) -> Vec<(i32, i32)> {
+    if let (Err(e), Some(product)) = (flow.place(), product) {
+        warnings.push(Warning::CellOverflow { product, overflow: flow.overflow() });
+        errors.push(format!("product {}: {}", product + 1, e));
     }
-    
-    image_to_row_bytes(&img)
+    flow.offsets()
 }
 
-// ======== EPL2 helpers (binary GW + CRLF, optional invert) ========
+// Bars give up the room the date and PLU lines take, keeping at least MIN_BAR_HEIGHT
+fn bar_height(dates: Option<&GrayImage>, plu: Option<&GrayImage>) -> u32 {
+    let taken = dates.map_or(0, |d| d.height() + DATE_GAP) + plu.map_or(0, |p| p.height() + PLU_GAP);
+    if taken == 0 { HEIGHT } else { HEIGHT.saturating_sub(taken).max(MIN_BAR_HEIGHT) }
+}
 
-fn epl_line(buf: &mut Vec<u8>, s: &str) {
-    buf.extend_from_slice(s.as_bytes());
-    buf.extend_from_slice(b"\r\n");
+// Small PLU line of `p` (`None` without one)
+fn render_plu(p: &Product, ctx: &FontContext) -> Option<GrayImage> {
+    p.plu.as_deref().filter(|plu| !plu.is_empty()).map(|plu| ctx.role(FontRole::Detail).render_line(plu, PLU_PX, 0))
 }
 
-fn image_to_row_bytes(img: &ImageBuffer<Luma<u8>, Vec<u8>>) -> (u32,u32,Vec<u8>) {
-    let (w,h) = (img.width(), img.height());
-    let bpr = ((w + 7)/8) as usize;
-    let mut out = vec![0u8; bpr*h as usize];
+/// Draw a cell's name+price line, date line and barcode at the positions from
+/// `product_flow`; the barcode keeps above `bottom`.
+#[allow(clippy::too_many_arguments)]
+fn place_product(label: &mut Label, ctx: &FontContext, product: &Product, code: &Barcode, line: GrayImage, dates: Option<GrayImage>, placed: &[(i32, i32)], bc_x: u32, bottom: u32) {
+    let (text_x, text_y) = placed[1];
+    let mut above = text_y.max(0) as u32 + line.height();
+    let plu = render_plu(product, ctx);
+    let bar_h = bar_height(dates.as_ref(), plu.as_ref());
+    label.graphic_at(text_x, text_y, line);
+    if let Some(d) = dates {
+        let (x, y) = placed[2];
+        above = y.max(0) as u32 + d.height();
+        label.graphic_at(x, y, d);
+    }
+    place_barcode(label, ctx, product, code, bc_x, placed[placed.len() - 1].1.max(0) as u32, bar_h, plu, above..bottom);
+}
 
-    for y in 0..h {
-        for x in 0..w {
-            if img.get_pixel(x,y).0[0] < 128 {
-                let i = y as usize * bpr + (x as usize / 8);
-                out[i] |= 1 << (7 - (x as usize % 8));
-            }
-        }
+/// Barcode of a product cell, centered where an EAN-13 at (x, y) would be,
+/// with the PLU line centered under its digits.
+/// With rendered HRI or a PLU line the bars move up when everything under
+/// them would cross `space.end`, but never above `space.start` (the text line).
+#[allow(clippy::too_many_arguments)]
+fn place_barcode(label: &mut Label, ctx: &FontContext, product: &Product, code: &Barcode, x: u32, y: u32, bar_h: u32, plu: Option<GrayImage>, space: Range<u32>) {
+    let center = x + barcode_width(Symbology::Ean13, "", NARROW) / 2;
+    // Printer digits can't be mirrored; drawn ones flip with the bars
+    let hri = if label.mirror && product.hri == Hri::Printer { Hri::Rendered } else { product.hri };
+    let mut cmd = BarcodeCmd { hri, ..BarcodeCmd::from_barcode(x, y, NARROW, bar_h, code) };
+    // Bars wider than twice the center start left of the label
+    let left = center as i32 - (cmd.width() / 2) as i32;
+    cmd.x = left.max(0) as u32;
+    let hri_h = match (cmd.hri, cmd.render_hri(ctx)) {
+        (_, Some(img)) => HRI_GAP + img.height(),
+        (Hri::Printer, None) => BarcodeCmd::printer_hri_height(),
+        _ => 0,
+    };
+    let plu_h = plu.as_ref().map_or(0, |p| PLU_GAP + p.height());
+    if cmd.hri == Hri::Rendered || plu.is_some() {
+        let bottom = y + cmd.height + hri_h + plu_h;
+        cmd.y = y.saturating_sub(bottom.saturating_sub(space.end)).max(space.start);
+    }
+    let (plu_y, bar_y) = (cmd.y + cmd.height + hri_h + PLU_GAP, cmd.y as i32);
+    label.barcode_with_hri(cmd, ctx);
+    label.clamped_from(left, bar_y);
+    if let Some(img) = plu {
+        label.graphic_at(center as i32 - (img.width() / 2) as i32, plu_y as i32, img);
     }
-    if INVERT_BITS { for b in &mut out { *b = !*b; } }
-    (w,h,out)
 }
 
-fn gw_bytes(buf:&mut Vec<u8>, x:u32, y:u32, w:u32, h:u32, rows:&[u8]) {
-    let bpr = ((w+7)/8) as usize;
-    epl_line(buf, &format!("GW{},{},{},{}", x,y,bpr,h));
-    buf.extend_from_slice(rows);  // RAW binary
-    buf.extend_from_slice(b"\r\n");
+/// Barcode of each cell: EAN-13 from whatever digits the product has, or with
+/// `opts.auto_barcodes` the symbology `Barcode::auto` picks (EAN-13 if none fits).
+fn product_barcodes(cells: &[Option<&Product>], opts: &BuildOptions, warnings: &mut Vec<Warning>) -> Vec<Barcode> {
+    let ean13 = |code: &str| Barcode { symbology: Symbology::Ean13, data: ensure_valid_ean13(code), note: None };
+    cells.iter().enumerate().map(|(i, cell)| {
+        let code = cell.map_or("", |p| p.barcode.as_str());
+        if !opts.auto_barcodes || cell.is_none() {
+            return ean13(code);
+        }
+        let (barcode, note) = match Barcode::auto(code) {
+            Ok(b) => (b.clone(), b.note),
+            Err(e) => (ean13(code), Some(format!("{}; printed as EAN-13", e))),
+        };
+        if let Some(note) = note {
+            warnings.push(Warning::BarcodeFallback { product: i, note });
+        }
+        barcode
+    }).collect()
 }
 
-fn center_x_for_ean13_single(label_w: u32, narrow: u32) -> u32 {
-    let w = 95 * narrow; // EAN-13 total width (95 modules)
-    (label_w - w) / 2
+// One `ProductReport` per filled cell
+fn product_reports(cells: &[Option<&Product>], name_px: &[f32], clipped: &[u32], date_px: &[Option<f32>], bcs: &[Barcode]) -> Vec<ProductReport> {
+    (0..cells.len()).filter(|&i| cells[i].is_some()).map(|i| ProductReport {
+        product: i,
+        name_font_px: name_px[i],
+        truncated_dots: clipped[i],
+        date_font_px: date_px[i],
+        barcode: bcs[i].clone(),
+    }).collect()
 }
 
-fn center_x_for_ean13_column(column_w: u32, narrow: u32) -> u32 {
-    let w = 95 * narrow; // EAN-13 total width (95 modules)
-    (column_w - w) / 2
+/// Label laid out by a product builder, before serializing.
+pub(crate) struct ProductLabel {
+    pub(crate) label: Label,
+    pub(crate) warnings: Vec<Warning>,
+    pub(crate) products: Vec<ProductReport>,
+    /// Content that doesn't fit its cell; an error for `try_build_*`.
+    pub(crate) errors: Vec<String>,
 }
 
-// Ensure barcode is valid 12-digit EAN-13 (without check digit)
-fn ensure_valid_ean13(barcode: &str) -> String {
-    let digits: String = barcode.chars().filter(|c| c.is_ascii_digit()).collect();
-    
-    if digits.len() >= 12 {
-        // Take first 12 digits (EPL2 will calculate check digit)
-        digits[..12].to_string()
-    } else if digits.len() == 13 {
-        // If 13 digits provided, use first 12 (remove check digit)
-        digits[..12].to_string()
-    } else {
-        // Pad with zeros to make 12 digits
-        format!("{:0<12}", digits)
+impl ProductLabel {
+    /// Job with the per-product part of its report filled in.
+    pub(crate) fn job(self) -> LabelJob {
+        let mut job = LabelJob::new(self.label.to_epl(), self.warnings);
+        job.report.products = self.products;
+        job
     }
+
+    /// `job`, or an error for content out of its cell, past the label edges or
+    /// that a mirrored label can't flip.
+    pub(crate) fn try_job(self) -> Result<LabelJob, String> {
+        if !self.errors.is_empty() {
+            return Err(self.errors.join("; "));
+        }
+        self.label.check_bounds()?;
+        self.label.check_mirror()?;
+        Ok(self.job())
+    }
+}
+
+fn cell_guide(cell: &Cell) -> Guide {
+    Guide::Cell { x: cell.x, y: cell.y, width: cell.width, height: cell.height }
 }
 
-// ======== Windows printer (optional, keep if you need send_raw_to_printer) ========
+// Baselines of the brand (rendered at 40px) and of the name+price line placed by `product_flow`
+fn baseline_guides(ctx: &FontContext, placed: &[(i32, i32)], brand: Option<&GrayImage>, line: &GrayImage, font_px: f32) -> Vec<Guide> {
+    let placed: Vec<(u32, u32)> = placed.iter().map(|&(x, y)| (x.max(0) as u32, y.max(0) as u32)).collect();
+    let ascent = |role: FontRole, px: f32| ctx.role(role).font().v_metrics(Scale::uniform(px)).ascent.ceil() as u32;
+    let brand = brand.map(|b| Guide::Baseline { x: placed[0].0, y: placed[0].1 + ascent(FontRole::Brand, 40.0), width: b.width() });
+    let line = Guide::Baseline { x: placed[1].0, y: placed[1].1 + ascent(FontRole::Body, font_px), width: line.width() };
+    brand.into_iter().chain([line]).collect()
+}
+
+// ======== Windows printer (feature `windows-spooler`) ========
 
-#[cfg(target_os = "windows")]
+#[cfg(all(target_os = "windows", feature = "windows-spooler"))]
 pub mod printer;
 
-#[cfg(target_os = "windows")]
+#[cfg(all(target_os = "windows", feature = "windows-spooler"))]
 pub use printer::send_raw_to_printer;
+
+// ======== C ABI (feature `ffi`) ========
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+// ======== Browser (feature `wasm`) ========
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+// ======== Job delivery (transports + retrying queue) ========
+
+pub mod transport;
+pub mod queue;
+pub mod dedup;
+pub mod discovery;
+pub mod stock;
+pub mod status;
+pub mod monitor;
+pub mod checkpoint;
+pub mod stamp;
+pub mod counter;
+pub mod verify;
+#[cfg(feature = "archive")]
+pub mod archive;
+
+pub use transport::{ascii_only, dedup, lp_device, Chunking, Dedup, Transport};
+#[cfg(unix)]
+pub use transport::cups_raw;
+pub use queue::{Flush, Flusher, JobId, JobQueue, JobStatus, RetryPolicy};
+pub use dedup::RecentJobs;
+pub use stock::{RollTracker, StockCounter};
+pub use status::{PrinterStatus, StatusSource};
+pub use monitor::{Health, HealthWatch, PrinterMonitor};
+pub use checkpoint::{BatchOutcome, BatchRunner};
+pub use stamp::Stamper;
+pub use counter::SerialCounter;
+pub use verify::{ScanSource, Verifier};
+#[cfg(feature = "archive")]
+pub use archive::LabelArchive;
+
+// ======== Label model ========
+
+pub mod epl;
+pub mod barcode;
+pub mod barcode_bitmap;
+pub mod label;
+pub mod element;
+pub mod product;
+pub mod warning;
+#[cfg(feature = "preview")]
+pub mod preview;
+pub mod diagnostics;
+pub mod snapshot;
+pub mod date;
+pub mod form;
+pub mod meta;
+pub mod softfont;
+pub mod report;
+pub mod preflight;
+
+use label::{BarcodeCmd, Guide, Label};
+pub use barcode::{Barcode, Symbology};
+pub use barcode_bitmap::{render_barcode, BarcodeRenderer};
+#[cfg(feature = "datamatrix")]
+pub use barcode_bitmap::DataMatrixRenderer;
+#[cfg(feature = "qr")]
+pub use barcode_bitmap::QrRenderer;
+pub use label::{Hri, LabelJob, MatrixCode};
+pub use element::{RenderCtx, RenderElement, TextLine};
+pub use product::Product;
+pub use date::{Date, DateFormat, DateStyle, MonthNames};
+pub use form::{Counter, Justify, StoredForm};
+pub use meta::JobMeta;
+pub use softfont::SoftFont;
+pub use report::{estimate_print_time, labels_per_hour, BuildReport, PrintTimeConfig, ProductReport};
+pub use preflight::{estimate, JobLimit, JobStats, Oversize};
+pub use warning::Warning;
+#[cfg(feature = "preview")]
+pub use preview::{render_preview, render_preview_debug};
+pub use snapshot::{assert_snapshot, fingerprint};
+
+// ======== Batches ========
+
+pub mod batch;
+
+pub use batch::{print_with_first_article, BatchItem, FirstArticle, Layout};
+
+// ======== Text (fonts + pluggable shaping) ========
+
+pub mod font;
+pub mod shaping;
+pub mod layout;
+pub mod cache;
+pub mod script;
+
+pub use font::{BoldStrength, FontContext, FontRegistry, FontRole, TextStyle};
+pub use cache::{CacheStats, RenderedText};
+pub use shaping::{Diacritics, ShapedGlyph, Shaper};
+pub use script::{convert_digits, Digits, Script};
+pub use layout::{BuildOptions, Direction, GridStyle};
+use layout::{Block, Cell, Flow, Padding};
@@ -0,0 +1,78 @@
+use std::time::SystemTime;
+
+use crate::epl::{parse, EplCommand};
+use crate::stamp::utc_timestamp;
+
+const ID: &str = "; job-id=";
+const PRINTED_AT: &str = "; printed-at=";
+const OPERATOR: &str = "; operator=";
+
+/// Traceability data carried by a job: `; job-id=...` comment lines at the
+/// top (the printer skips them) and, optionally, a short code printed in a
+/// corner of the label so a label on the shelf leads back to its job.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct JobMeta {
+    pub id: String,
+    /// `YYYY-MM-DD HH:MM` UTC, see `stamp::utc_timestamp`.
+    pub printed_at: Option<String>,
+    pub operator: Option<String>,
+    /// Print `short_code` in the bottom left corner (resident font 1).
+    pub print_code: bool,
+}
+
+impl JobMeta {
+    pub fn new(id: &str) -> Self {
+        JobMeta { id: id.to_string(), ..Default::default() }
+    }
+
+    pub fn printed_at(mut self, t: SystemTime) -> Self {
+        self.printed_at = Some(utc_timestamp(t));
+        self
+    }
+
+    pub fn operator(mut self, operator: &str) -> Self {
+        self.operator = Some(operator.to_string());
+        self
+    }
+
+    pub fn print_code(mut self, on: bool) -> Self {
+        self.print_code = on;
+        self
+    }
+
+    /// Last 6 letters or digits of `id`, upper case: the part that differs
+    /// between sequential ids, and enough to find the job in a log.
+    pub fn short_code(&self) -> String {
+        let chars: Vec<char> = self.id.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+        chars[chars.len().saturating_sub(6)..].iter().collect::<String>().to_ascii_uppercase()
+    }
+
+    /// The comment lines, without line endings.
+    pub fn comment_lines(&self) -> Vec<String> {
+        // A line break in a value would end the comment and leave the rest as a command
+        let clean = |v: &str| v.replace(['\r', '\n'], " ");
+        let mut out = vec![format!("{}{}", ID, clean(&self.id))];
+        out.extend(self.printed_at.as_deref().map(|t| format!("{}{}", PRINTED_AT, clean(t))));
+        out.extend(self.operator.as_deref().map(|o| format!("{}{}", OPERATOR, clean(o))));
+        out
+    }
+
+    /// Metadata of a built job, `None` without a `; job-id=` line.
+    /// `print_code` is not recorded in the job and comes back `false`.
+    pub fn from_job(job: &[u8]) -> Option<JobMeta> {
+        let mut meta: Option<JobMeta> = None;
+        for cmd in parse(job) {
+            let EplCommand::Comment(line) = cmd else { continue };
+            if let Some(id) = line.strip_prefix(ID) {
+                meta = Some(JobMeta::new(id));
+            } else if let Some(m) = meta.as_mut() {
+                if let Some(t) = line.strip_prefix(PRINTED_AT) {
+                    m.printed_at = Some(t.to_string());
+                } else if let Some(o) = line.strip_prefix(OPERATOR) {
+                    m.operator = Some(o.to_string());
+                }
+            }
+        }
+        meta
+    }
+}
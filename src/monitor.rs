@@ -0,0 +1,134 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::status::{PrinterStatus, StatusSource};
+use crate::transport::tcp::TcpPrinter;
+
+/// Last known condition of a printer, as published by `PrinterMonitor`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Health {
+    /// Not checked yet.
+    #[default]
+    Unknown,
+    /// Reachable. `status` is `None` when only the connection was checked (`spawn_ping`).
+    Up { status: Option<PrinterStatus> },
+    /// Not reachable, or the status query failed.
+    Down { error: String },
+}
+
+impl Health {
+    /// Worth enabling a print button: reachable and not reporting an error.
+    pub fn can_print(&self) -> bool {
+        matches!(self, Health::Up { status: None | Some(PrinterStatus::Ready) })
+    }
+}
+
+struct Shared {
+    // Health plus a version bumped on every change
+    state: Mutex<(Health, u64)>,
+    changed: Condvar,
+}
+
+impl Shared {
+    fn publish(&self, health: Health) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.0 != health {
+            *state = (health, state.1 + 1);
+            self.changed.notify_all();
+        }
+    }
+}
+
+/// Receiving end of a monitor: the latest `Health`, and a way to wait for the
+/// next change. Clone one per UI component; each tracks what it has seen.
+#[derive(Clone)]
+pub struct HealthWatch {
+    shared: Arc<Shared>,
+    seen: u64,
+}
+
+impl HealthWatch {
+    /// Current health (marks it as seen).
+    pub fn latest(&mut self) -> Health {
+        let state = self.shared.state.lock().unwrap_or_else(|e| e.into_inner());
+        self.seen = state.1;
+        state.0.clone()
+    }
+
+    /// Block until the health differs from the last one seen through this
+    /// watch, up to `timeout`. `None` if nothing changed in time.
+    pub fn changed(&mut self, timeout: Duration) -> Option<Health> {
+        let state = self.shared.state.lock().unwrap_or_else(|e| e.into_inner());
+        let (state, _) = self.shared.changed
+            .wait_timeout_while(state, timeout, |s| s.1 == self.seen)
+            .unwrap_or_else(|e| e.into_inner());
+        (state.1 != self.seen).then(|| {
+            self.seen = state.1;
+            state.0.clone()
+        })
+    }
+}
+
+type Check = Box<dyn FnMut() -> Health + Send>;
+
+/// Background thread that checks a printer every `interval` and publishes the
+/// result through `HealthWatch`es, e.g. to grey out a print button while the
+/// printer is down.
+pub struct PrinterMonitor {
+    shared: Arc<Shared>,
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+impl PrinterMonitor {
+    /// Poll `source` for its `^ee` status; a failed query counts as down.
+    pub fn spawn<S>(mut source: S, interval: Duration) -> Self
+    where
+        S: StatusSource + Send + 'static,
+    {
+        Self::start(Box::new(move || match source.status() {
+            Ok(status) => Health::Up { status: Some(status) },
+            Err(e) => Health::Down { error: e.to_string() },
+        }), interval)
+    }
+
+    /// Only check that `printer` accepts connections, for print servers that
+    /// don't answer status requests.
+    pub fn spawn_ping(printer: TcpPrinter, interval: Duration) -> Self {
+        Self::start(Box::new(move || match printer.ping() {
+            Ok(_) => Health::Up { status: None },
+            Err(e) => Health::Down { error: e.to_string() },
+        }), interval)
+    }
+
+    fn start(mut check: Check, interval: Duration) -> Self {
+        let shared = Arc::new(Shared { state: Mutex::new((Health::Unknown, 0)), changed: Condvar::new() });
+        let stop = Arc::new(AtomicBool::new(false));
+        let (flag, publish) = (stop.clone(), shared.clone());
+        let handle = thread::spawn(move || {
+            while !flag.load(Ordering::Relaxed) {
+                publish.publish(check());
+                thread::park_timeout(interval);
+            }
+        });
+        PrinterMonitor { shared, stop, handle }
+    }
+
+    pub fn watch(&self) -> HealthWatch {
+        HealthWatch { shared: self.shared.clone(), seen: 0 }
+    }
+
+    /// Check again right away (e.g. after a failed send) instead of at the next interval.
+    pub fn check_now(&self) {
+        self.handle.thread().unpark();
+    }
+
+    /// Stop after the current check and wait for the thread.
+    pub fn stop(self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.handle.thread().unpark();
+        let _ = self.handle.join();
+    }
+}
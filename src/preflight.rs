@@ -0,0 +1,122 @@
+use std::ops::Range;
+
+use crate::consts::MAX_JOB_BYTES;
+use crate::epl::{command_spans, parse, EplCommand};
+use crate::label::LabelJob;
+
+/// Size of a job as the printer receives it, see `estimate`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct JobStats {
+    pub bytes: usize,
+    /// `GW` bitmap payload, the part that fills the image buffer.
+    pub gw_bytes: usize,
+    /// Command lines, a `GW` with its payload counting as one.
+    pub commands: usize,
+    /// Forms (label boundaries), one per `P` command.
+    pub forms: usize,
+    /// Bytes of the biggest form: the least a split can get a send down to.
+    pub largest_form: usize,
+}
+
+/// Pre-flight statistics of `job`, without sending anything.
+pub fn estimate(job: &[u8]) -> JobStats {
+    let commands = parse(job);
+    let gw_bytes = commands.iter()
+        .map(|c| match c {
+            EplCommand::Graphic { payload_len, .. } => *payload_len,
+            _ => 0,
+        })
+        .sum();
+    let forms = forms(job);
+    JobStats {
+        bytes: job.len(),
+        gw_bytes,
+        commands: commands.len(),
+        forms: commands.iter().filter(|c| matches!(c, EplCommand::Print { .. })).count(),
+        largest_form: forms.iter().map(|f| f.len()).max().unwrap_or(0),
+    }
+}
+
+/// Byte ranges of the forms of `job`, each ending after its `P` command.
+/// Setup before the first `N` stays with the first form; bytes after the
+/// last `P` go with the last one.
+pub fn forms(job: &[u8]) -> Vec<Range<usize>> {
+    let mut out = Vec::new();
+    let mut start = 0;
+    for (span, line) in command_spans(job) {
+        if line.starts_with('P') {
+            out.push(start..span.end);
+            start = span.end;
+        }
+    }
+    match out.last_mut() {
+        Some(last) => last.end = job.len(),
+        None if !job.is_empty() => out.push(0..job.len()),
+        None => {}
+    }
+    out
+}
+
+/// What `JobLimit` does with a job over the limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Oversize {
+    /// Refuse the job.
+    #[default]
+    Error,
+    /// Send it in parts, cut between forms; a single form over the limit is
+    /// still an error.
+    Split,
+}
+
+/// Largest job to send in one go (default `MAX_JOB_BYTES`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JobLimit {
+    pub max_bytes: usize,
+    pub oversize: Oversize,
+}
+
+impl Default for JobLimit {
+    fn default() -> Self {
+        JobLimit { max_bytes: MAX_JOB_BYTES, oversize: Oversize::Error }
+    }
+}
+
+impl JobLimit {
+    pub fn new(max_bytes: usize) -> Self {
+        JobLimit { max_bytes, ..Default::default() }
+    }
+
+    /// Split oversized jobs at label boundaries instead of refusing them.
+    pub fn split(mut self) -> Self {
+        self.oversize = Oversize::Split;
+        self
+    }
+
+    /// `job` as the sends that keep within the limit: the job itself when it
+    /// fits, otherwise consecutive forms packed up to `max_bytes` each.
+    pub fn check(&self, job: &[u8]) -> Result<Vec<Vec<u8>>, String> {
+        if job.len() <= self.max_bytes {
+            return Ok(vec![job.to_vec()]);
+        }
+        let stats = estimate(job);
+        if self.oversize == Oversize::Error {
+            return Err(format!("job is {} bytes ({} of bitmaps), limit is {}", stats.bytes, stats.gw_bytes, self.max_bytes));
+        }
+        if stats.largest_form > self.max_bytes {
+            return Err(format!("a single form is {} bytes, limit is {}; it can't be split further", stats.largest_form, self.max_bytes));
+        }
+        let mut sends: Vec<Vec<u8>> = Vec::new();
+        for form in forms(job) {
+            match sends.last_mut() {
+                Some(send) if send.len() + form.len() <= self.max_bytes => send.extend_from_slice(&job[form]),
+                _ => sends.push(job[form].to_vec()),
+            }
+        }
+        Ok(sends)
+    }
+
+    /// `check` of a whole batch sent as one stream of forms, in print order.
+    pub fn check_batch(&self, jobs: &[LabelJob]) -> Result<Vec<Vec<u8>>, String> {
+        self.check(&jobs.iter().flat_map(|j| j.bytes.iter().copied()).collect::<Vec<u8>>())
+    }
+}
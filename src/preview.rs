@@ -0,0 +1,171 @@
+use image::{GrayImage, Luma};
+
+use crate::barcode::Symbology;
+use crate::barcode_bitmap::{BarcodeRenderer, Ean13Renderer, Symbol};
+use crate::consts::{INVERT_BITS, LABEL_H, LABEL_W};
+use crate::epl::command_spans;
+use crate::label::{Element, Guide, Label, TextCmd};
+
+const GRAY: Luma<u8> = Luma([160]);
+
+// Debug overlay levels, darkest for the coarsest structure
+const CELL_GRAY: Luma<u8> = Luma([64]);
+const BASELINE_GRAY: Luma<u8> = Luma([96]);
+const BOX_GRAY: Luma<u8> = Luma([128]);
+const QUIET_GRAY: Luma<u8> = Luma([216]);
+
+/// Approximate on-screen rendering of the first label form of a job.
+///
+/// `GW` bitmaps, `X` frames and `LO` lines are exact; EAN-13 bars are drawn from the
+/// encoder, other printer barcodes as a gray block of their estimated width,
+/// and resident-font text (`A`) as the outline of its character cells.
+pub fn render_preview(job: &[u8]) -> GrayImage {
+    let (mut w, mut h) = (LABEL_W, LABEL_H);
+    let mut img: Option<GrayImage> = None;
+
+    for (span, line) in command_spans(job) {
+        let args = |prefix: &str| -> Vec<String> {
+            line[prefix.len()..].split(',').map(|a| a.trim().to_string()).collect()
+        };
+        let num = |a: &[String], i: usize| a.get(i).and_then(|v| v.parse::<u32>().ok()).unwrap_or(0);
+
+        // Setup lines before `q` must not create the canvas at the default size
+        if matches!(line.as_str(), "N" | "JF" | "JB" | "JC") || line.starts_with([';', 'O', 'f']) {
+            continue;
+        }
+        if let Some(v) = line.strip_prefix('q') {
+            w = v.trim().parse().unwrap_or(w);
+            continue;
+        }
+        if line.starts_with('Q') {
+            h = num(&args("Q"), 0).max(1);
+            continue;
+        }
+        if line.starts_with('P') {
+            break;
+        }
+        let canvas = img.get_or_insert_with(|| GrayImage::from_pixel(w, h, Luma([255])));
+
+        if line.starts_with("GW") {
+            let a = args("GW");
+            let (x, y, bpr, rows) = (num(&a, 0), num(&a, 1), num(&a, 2), num(&a, 3));
+            let start = span.start + line.len() + 2;
+            let payload = &job[start.min(job.len())..(start + (bpr * rows) as usize).min(job.len())];
+            for (i, byte) in payload.iter().enumerate() {
+                let (row, col) = (i as u32 / bpr.max(1), i as u32 % bpr.max(1));
+                for bit in 0..8 {
+                    // Rows are packed with 1 = black, inverted when INVERT_BITS is set
+                    if (byte & (0x80 >> bit) != 0) != INVERT_BITS {
+                        put(canvas, x + col * 8 + bit, y + row, Luma([0]));
+                    }
+                }
+            }
+        } else if line.starts_with('X') {
+            let a = args("X");
+            let (x, y, t, x1, y1) = (num(&a, 0), num(&a, 1), num(&a, 2), num(&a, 3), num(&a, 4));
+            for yy in y..y1 {
+                for xx in x..x1 {
+                    if xx < x + t || xx + t >= x1 || yy < y + t || yy + t >= y1 {
+                        put(canvas, xx, yy, Luma([0]));
+                    }
+                }
+            }
+        } else if line.starts_with("LO") {
+            let a = args("LO");
+            fill(canvas, num(&a, 0), num(&a, 1), num(&a, 2), num(&a, 3), Luma([0]));
+        } else if let Some(rest) = line.strip_prefix('B') {
+            let Some((params, data)) = rest.split_once(",\"") else { continue };
+            let a: Vec<String> = params.split(',').map(str::to_string).collect();
+            let data = data.trim_end_matches('"');
+            let (x, y, narrow, wide, bar_h) = (num(&a, 0), num(&a, 1), num(&a, 4), num(&a, 5), num(&a, 6));
+            let symbology = a.get(3).and_then(|c| Symbology::from_epl_code(c));
+            let modules = match symbology {
+                Some(Symbology::Ean13) => Ean13Renderer.encode(data).ok().map(|e| e.symbol),
+                _ => None,
+            };
+            match (modules, symbology) {
+                (Some(Symbol::Linear(bars)), _) => {
+                    for (i, _) in bars.iter().enumerate().filter(|(_, &b)| b) {
+                        fill(canvas, x + i as u32 * narrow, y, narrow, bar_h, Luma([0]));
+                    }
+                }
+                (_, Some(s)) => fill(canvas, x, y, s.width_with(data, narrow, wide), bar_h, GRAY),
+                _ => {}
+            }
+        } else if let Some(rest) = line.strip_prefix('A') {
+            let Some((params, data)) = rest.split_once(",\"") else { continue };
+            let a: Vec<String> = params.split(',').map(str::to_string).collect();
+            let font = a.get(3).and_then(|f| f.parse().ok()).unwrap_or(1);
+            let (pitch, cell_h) = TextCmd::cell(font);
+            let (hm, vm) = (num(&a, 4).max(1), num(&a, 5).max(1));
+            let (x, y) = (num(&a, 0), num(&a, 1));
+            for i in 0..data.trim_end_matches('"').chars().count() as u32 {
+                fill(canvas, x + i * pitch * hm + 1, y + 1, pitch * hm - 2, cell_h * vm - 2, GRAY);
+            }
+        }
+    }
+    img.unwrap_or_else(|| GrayImage::from_pixel(w, h, Luma([255])))
+}
+
+/// `render_preview` of `label` with its layout drawn over the white parts:
+/// layout cells (darkest), text baselines, element bounding boxes and barcode
+/// quiet zones (lightest, shaded). Only the image changes; `label.to_epl()`
+/// stays as printed.
+pub fn render_preview_debug(label: &Label) -> GrayImage {
+    let mut img = render_preview(&label.to_epl());
+    for e in &label.elements {
+        if let Element::Barcode(b) = e {
+            let (left, right) = b.symbology.quiet_zone();
+            let (l, r) = (left * b.narrow, right * b.narrow);
+            overlay(&mut img, b.x.saturating_sub(l), b.y, l.min(b.x), b.height, QUIET_GRAY);
+            overlay(&mut img, b.x + b.width(), b.y, r, b.height, QUIET_GRAY);
+        }
+    }
+    for e in &label.elements {
+        let (x, y, w, h) = e.bounds();
+        outline(&mut img, x, y, w, h, BOX_GRAY);
+    }
+    for g in &label.guides {
+        match *g {
+            Guide::Cell { x, y, width, height } => outline(&mut img, x, y, width, height, CELL_GRAY),
+            Guide::Baseline { x, y, width } => overlay(&mut img, x, y, width, 1, BASELINE_GRAY),
+        }
+    }
+    img
+}
+
+// Rectangle border, 1 dot wide
+fn outline(img: &mut GrayImage, x: u32, y: u32, w: u32, h: u32, v: Luma<u8>) {
+    if w == 0 || h == 0 {
+        return;
+    }
+    overlay(img, x, y, w, 1, v);
+    overlay(img, x, y + h - 1, w, 1, v);
+    overlay(img, x, y, 1, h, v);
+    overlay(img, x + w - 1, y, 1, h, v);
+}
+
+// `fill` that leaves printed (non-white) dots alone
+fn overlay(img: &mut GrayImage, x: u32, y: u32, w: u32, h: u32, v: Luma<u8>) {
+    for yy in y..(y + h).min(img.height()) {
+        for xx in x..(x + w).min(img.width()) {
+            if img.get_pixel(xx, yy).0[0] == 255 {
+                img.put_pixel(xx, yy, v);
+            }
+        }
+    }
+}
+
+fn put(img: &mut GrayImage, x: u32, y: u32, v: Luma<u8>) {
+    if x < img.width() && y < img.height() {
+        img.put_pixel(x, y, v);
+    }
+}
+
+fn fill(img: &mut GrayImage, x: u32, y: u32, w: u32, h: u32, v: Luma<u8>) {
+    for yy in y..y + h {
+        for xx in x..x + w {
+            put(img, xx, yy, v);
+        }
+    }
+}
@@ -1,7 +1,14 @@
 use std::error::Error;
 
+use crate::transport::Chunking;
+
 /// Send raw bytes to the named printer. On non-Windows this function returns an error.
 pub fn send_raw_to_printer(printer_name: &str, data: &[u8]) -> Result<(), Box<dyn Error>> {
+    send_raw_to_printer_chunked(printer_name, data, None)
+}
+
+/// `send_raw_to_printer` with one `WritePrinter` call per chunk.
+pub fn send_raw_to_printer_chunked(printer_name: &str, data: &[u8], chunking: Option<&Chunking>) -> Result<(), Box<dyn Error>> {
     #[cfg(target_os = "windows")]
     {
         use std::ffi::OsStr;
@@ -41,13 +48,23 @@ pub fn send_raw_to_printer(printer_name: &str, data: &[u8]) -> Result<(), Box<dy
                 return Err(Box::<dyn Error>::from("StartPagePrinter failed"));
             }
 
-            let mut written: DWORD = 0;
-            let ok = WritePrinter(
-                handle as *mut _,
-                data.as_ptr() as *mut _,
-                data.len() as DWORD,
-                &mut written as *mut DWORD,
-            );
+            let mut ok = 1;
+            let mut parts = crate::transport::chunks(data, chunking).peekable();
+            while let Some(part) = parts.next() {
+                let mut written: DWORD = 0;
+                ok = WritePrinter(
+                    handle as *mut _,
+                    part.as_ptr() as *mut _,
+                    part.len() as DWORD,
+                    &mut written as *mut DWORD,
+                );
+                if ok == 0 {
+                    break;
+                }
+                if let (Some(c), Some(_)) = (chunking, parts.peek()) {
+                    std::thread::sleep(c.delay);
+                }
+            }
 
             EndPagePrinter(handle as *mut _);
             EndDocPrinter(handle as *mut _);
@@ -62,6 +79,7 @@ pub fn send_raw_to_printer(printer_name: &str, data: &[u8]) -> Result<(), Box<dy
 
     #[cfg(not(target_os = "windows"))]
     {
+        let _ = chunking;
         Err(Box::<dyn Error>::from("send_raw_to_printer is only supported on Windows (uses Win32 spooler)"))
     }
 }
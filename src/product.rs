@@ -0,0 +1,57 @@
+use crate::barcode::{ean13_price_embedded_with, PriceEmbedding};
+use crate::date::Date;
+use crate::label::Hri;
+
+/// One product cell on a label: name, price (without currency) and barcode digits.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Product {
+    pub name: String,
+    pub price: String,
+    pub barcode: String,
+    /// How the barcode digits are printed.
+    pub hri: Hri,
+    /// Production date, printed on the date line (`BuildOptions::dates`).
+    pub produced: Option<Date>,
+    /// Expiry date, printed on the date line.
+    pub expires: Option<Date>,
+    /// Internal PLU / SKU, printed in small type under the barcode digits.
+    pub plu: Option<String>,
+}
+
+impl Product {
+    pub fn new(name: &str, price: &str, barcode: &str) -> Self {
+        Product {
+            name: name.to_string(),
+            price: price.to_string(),
+            barcode: barcode.to_string(),
+            hri: Hri::Printer,
+            produced: None,
+            expires: None,
+            plu: None,
+        }
+    }
+
+    pub fn with_hri(mut self, hri: Hri) -> Self {
+        self.hri = hri;
+        self
+    }
+
+    pub fn with_dates(mut self, produced: Option<Date>, expires: Option<Date>) -> Self {
+        self.produced = produced;
+        self.expires = expires;
+        self
+    }
+
+    pub fn with_plu(mut self, plu: &str) -> Self {
+        self.plu = Some(plu.to_string());
+        self
+    }
+
+    /// Weighed/variable-price item: the price is shown as `units.cents` and embedded
+    /// in a prefix-2 EAN-13 built from `item_code`.
+    pub fn with_embedded_price(name: &str, item_code: &str, price_cents: u32, layout: &PriceEmbedding) -> Result<Self, String> {
+        let barcode = ean13_price_embedded_with(item_code, price_cents, layout)?;
+        let price = format!("{}.{:02}", price_cents / 100, price_cents % 100);
+        Ok(Product { price, barcode, ..Product::new(name, "", "") })
+    }
+}
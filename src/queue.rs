@@ -0,0 +1,439 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+use crate::dedup::RecentJobs;
+use crate::epl::count_labels;
+use crate::status::{PrinterStatus, StatusSource};
+use crate::stock::StockCounter;
+use crate::transport::Transport;
+
+pub type JobId = u64;
+
+/// Lifecycle of a queued job, reported to status listeners.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobStatus {
+    Queued,
+    Sending { attempt: u32 },
+    /// Transport failed; the job will be retried after `delay`.
+    Retrying { attempt: u32, delay: Duration, error: String },
+    Sent,
+    /// Sent, but the printer reported `status` right after instead of `Ready`
+    /// (or couldn't be asked: `Error(0)`), so whether it printed is unknown.
+    /// Not resent, which could print it twice; a spooled job is kept as
+    /// `<id>.unconfirmed` for the operator to check.
+    Unconfirmed { status: PrinterStatus },
+    /// Retries exhausted. A spooled job is kept on disk as `<id>.failed`.
+    Failed { attempts: u32, error: String },
+    /// Older than the queue's `max_age`; dropped unsent (spool file kept as `<id>.expired`).
+    Expired { age: Duration },
+    /// Same bytes as job `of`, still queued or sent within the dedup window;
+    /// not queued (see `JobQueue::dedup_window`).
+    Duplicate { of: JobId },
+}
+
+/// Exponential backoff: `initial_delay * multiplier^(attempt-1)`, capped at `max_delay`.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    pub multiplier: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 5,
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            multiplier: 2,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay to wait after the given (1-based) failed attempt.
+    pub fn delay_for(&self, attempt: u32) -> Duration {
+        let factor = self.multiplier.max(1).saturating_pow(attempt.saturating_sub(1));
+        self.initial_delay.saturating_mul(factor).min(self.max_delay)
+    }
+}
+
+struct Job {
+    id: JobId,
+    data: Vec<u8>,
+    attempts: u32,
+    queued_at: SystemTime,
+}
+
+type StatusListener = Box<dyn FnMut(JobId, &JobStatus) + Send>;
+
+/// FIFO of raw EPL2 jobs with retry/backoff and an optional on-disk spool.
+///
+/// With a spool directory every accepted job is written to `<id>.job` before
+/// `push` returns and removed only once the transport accepted it, so jobs
+/// survive a crash or restart and are picked up again by `with_spool`.
+pub struct JobQueue {
+    jobs: VecDeque<Job>,
+    next_id: JobId,
+    spool_dir: Option<PathBuf>,
+    retry: RetryPolicy,
+    listeners: Vec<StatusListener>,
+    stock: Option<Box<dyn StockCounter>>,
+    max_age: Option<Duration>,
+    dedup: Option<RecentJobs>,
+}
+
+impl Default for JobQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JobQueue {
+    /// In-memory queue (jobs are lost if the process exits).
+    pub fn new() -> Self {
+        JobQueue {
+            jobs: VecDeque::new(),
+            next_id: 1,
+            spool_dir: None,
+            retry: RetryPolicy::default(),
+            listeners: Vec::new(),
+            stock: None,
+            max_age: None,
+            dedup: None,
+        }
+    }
+
+    /// Queue backed by a spool directory; pending `*.job` files are reloaded in id order.
+    /// A reloaded job's age counts from its file's modification time. New ids
+    /// continue after every id in the spool, including `.failed`, `.expired`
+    /// and `.unconfirmed` files, so none of them is reused.
+    pub fn with_spool(dir: impl AsRef<Path>) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+
+        let mut queue = JobQueue::new();
+        let mut found: Vec<(JobId, PathBuf)> = Vec::new();
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            let Some(id) = path.file_stem().and_then(|s| s.to_str()).and_then(|s| s.parse::<JobId>().ok()) else { continue };
+            queue.next_id = queue.next_id.max(id.saturating_add(1));
+            if path.extension().and_then(|e| e.to_str()) == Some("job") {
+                found.push((id, path));
+            }
+        }
+        found.sort_by_key(|(id, _)| *id);
+
+        for (id, path) in found {
+            let data = fs::read(&path)?;
+            let queued_at = fs::metadata(&path)?.modified().unwrap_or_else(|_| SystemTime::now());
+            queue.jobs.push_back(Job { id, data, attempts: 0, queued_at });
+        }
+        queue.spool_dir = Some(dir);
+        Ok(queue)
+    }
+
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Drop jobs instead of sending them once they are older than `age`, so a
+    /// long outage doesn't end with yesterday's prices coming off the printer.
+    pub fn max_age(mut self, age: Duration) -> Self {
+        self.max_age = Some(age);
+        self
+    }
+
+    /// Skip pushed jobs identical to one still queued or sent less than
+    /// `window` ago: they get an id and a `Duplicate` status, but are not
+    /// queued, spooled or sent.
+    pub fn dedup_window(mut self, window: Duration) -> Self {
+        self.dedup = Some(RecentJobs::new(window));
+        self
+    }
+
+    /// Register a status callback (called from whichever thread runs the queue).
+    pub fn on_status<F>(&mut self, f: F)
+    where
+        F: FnMut(JobId, &JobStatus) + Send + 'static,
+    {
+        self.listeners.push(Box::new(f));
+    }
+
+    /// Channel-based alternative to `on_status`.
+    pub fn status_channel(&mut self) -> Receiver<(JobId, JobStatus)> {
+        let (tx, rx) = channel();
+        self.on_status(move |id, status| {
+            let _ = tx.send((id, status.clone()));
+        });
+        rx
+    }
+
+    /// Count labels of every sent job (e.g. a shared `RollTracker`).
+    pub fn track_stock<C: StockCounter + 'static>(&mut self, counter: C) {
+        self.stock = Some(Box::new(counter));
+    }
+
+    /// Accept a job. With a spool the job is persisted before this returns.
+    pub fn push(&mut self, data: Vec<u8>) -> io::Result<JobId> {
+        let id = self.next_id;
+        if let Some(of) = self.duplicate_of(&data) {
+            self.next_id += 1;
+            self.notify(id, &JobStatus::Duplicate { of });
+            return Ok(id);
+        }
+        if let Some(dir) = &self.spool_dir {
+            // Never clobber a spool file, e.g. from another queue on the same directory
+            fs::OpenOptions::new().write(true).create_new(true).open(spool_path(dir, id, "job"))?.write_all(&data)?;
+        }
+        self.next_id += 1;
+        self.jobs.push_back(Job { id, data, attempts: 0, queued_at: SystemTime::now() });
+        self.notify(id, &JobStatus::Queued);
+        Ok(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.jobs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    /// Ids of jobs still waiting, in send order.
+    pub fn pending(&self) -> Vec<JobId> {
+        self.jobs.iter().map(|j| j.id).collect()
+    }
+
+    /// Send the head job, retrying with backoff until it is sent or retries run out.
+    /// Returns the final status, or `None` when the queue is empty.
+    pub fn send_next<T: Transport + ?Sized>(&mut self, transport: &mut T) -> Option<(JobId, JobStatus)> {
+        self.expire();
+        let mut job = self.jobs.pop_front()?;
+        let status = match self.deliver(&mut job, transport) {
+            Ok(()) => {
+                self.complete(&job);
+                JobStatus::Sent
+            }
+            Err(error) => {
+                if let Some(dir) = &self.spool_dir {
+                    let _ = retire(dir, job.id, "failed");
+                }
+                let status = JobStatus::Failed { attempts: job.attempts, error };
+                self.notify(job.id, &status);
+                status
+            }
+        };
+        Some((job.id, status))
+    }
+
+    /// Drain the queue. Returns the number of jobs sent successfully.
+    pub fn run<T: Transport + ?Sized>(&mut self, transport: &mut T) -> usize {
+        let mut sent = 0;
+        while let Some((_, status)) = self.send_next(transport) {
+            if status == JobStatus::Sent {
+                sent += 1;
+            }
+        }
+        sent
+    }
+
+    /// Drain the queue while the printer reports `Ready`.
+    ///
+    /// The status is polled before each job, and the queue stops with it
+    /// unless it is `Ready`. A job the transport couldn't send stays at the
+    /// head, so calling `run_monitored` again after a roll change continues
+    /// exactly there. A sent job is never resent: if the status after it isn't
+    /// `Ready` (`^ee` can't tell a busy printer from one that didn't print)
+    /// it is reported `Unconfirmed` and the queue stops with that status. A
+    /// failed status query is reported as `Error(0)`.
+    pub fn run_monitored<T, S>(&mut self, transport: &mut T, status: &mut S) -> Result<usize, PrinterStatus>
+    where
+        T: Transport + ?Sized,
+        S: StatusSource + ?Sized,
+    {
+        let poll = |s: &mut S| s.status().unwrap_or(PrinterStatus::Error(0));
+        let mut sent = 0;
+        loop {
+            match poll(status) {
+                PrinterStatus::Ready => {}
+                other => return Err(other),
+            }
+            self.expire();
+            let Some(mut job) = self.jobs.pop_front() else { return Ok(sent) };
+
+            if self.deliver(&mut job, transport).is_err() {
+                job.attempts = 0;
+                self.jobs.push_front(job);
+                return Err(poll(status));
+            }
+            match poll(status) {
+                PrinterStatus::Ready => self.complete(&job),
+                after => {
+                    self.settle(&job, JobStatus::Unconfirmed { status: after });
+                    return Err(after);
+                }
+            }
+            sent += 1;
+        }
+    }
+
+    /// Remove jobs older than `max_age`. Returns their ids.
+    pub fn expire(&mut self) -> Vec<JobId> {
+        let Some(max_age) = self.max_age else { return Vec::new() };
+        let now = SystemTime::now();
+        let (stale, keep): (VecDeque<Job>, VecDeque<Job>) = std::mem::take(&mut self.jobs)
+            .into_iter()
+            .partition(|j| now.duration_since(j.queued_at).unwrap_or_default() > max_age);
+        self.jobs = keep;
+
+        let mut ids = Vec::with_capacity(stale.len());
+        for job in stale {
+            if let Some(dir) = &self.spool_dir {
+                let _ = retire(dir, job.id, "expired");
+            }
+            let age = now.duration_since(job.queued_at).unwrap_or_default();
+            self.notify(job.id, &JobStatus::Expired { age });
+            ids.push(job.id);
+        }
+        ids
+    }
+
+    /// Offline-friendly drain: one attempt per job, stopping at the first
+    /// transport error with that job (and everything after it) left queued.
+    /// Stale jobs are expired first.
+    pub fn flush<T: Transport + ?Sized>(&mut self, transport: &mut T) -> Flush {
+        let expired = self.expire().len();
+        let mut sent = 0;
+        while let Some(mut job) = self.jobs.pop_front() {
+            job.attempts += 1;
+            self.notify(job.id, &JobStatus::Sending { attempt: job.attempts });
+            if let Err(e) = transport.send(&job.data) {
+                self.jobs.push_front(job);
+                return Flush { sent, expired, error: Some(e.to_string()) };
+            }
+            self.complete(&job);
+            sent += 1;
+        }
+        Flush { sent, expired, error: None }
+    }
+
+    // Transmit with retry/backoff, reporting Sending/Retrying; no spool bookkeeping
+    fn deliver<T: Transport + ?Sized>(&mut self, job: &mut Job, transport: &mut T) -> Result<(), String> {
+        loop {
+            job.attempts += 1;
+            self.notify(job.id, &JobStatus::Sending { attempt: job.attempts });
+
+            match transport.send(&job.data) {
+                Ok(()) => return Ok(()),
+                Err(e) if job.attempts < self.retry.max_attempts => {
+                    let delay = self.retry.delay_for(job.attempts);
+                    self.notify(job.id, &JobStatus::Retrying { attempt: job.attempts, delay, error: e.to_string() });
+                    thread::sleep(delay);
+                }
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+    }
+
+    // Earlier job with the same bytes, when deduplicating
+    fn duplicate_of(&mut self, data: &[u8]) -> Option<JobId> {
+        let recent = self.dedup.as_mut()?;
+        self.jobs.iter().find(|j| j.data == data).map(|j| j.id)
+            .or_else(|| recent.find(data).map(|(id, _)| id))
+    }
+
+    // Job accepted: drop its spool file, count stock, remember it for dedup, report Sent
+    fn complete(&mut self, job: &Job) {
+        self.settle(job, JobStatus::Sent);
+    }
+
+    // Job handed to the printer, confirmed (`Sent`) or not (spool file kept as `.unconfirmed`)
+    fn settle(&mut self, job: &Job, status: JobStatus) {
+        if let Some(recent) = &mut self.dedup {
+            recent.record(&job.data, job.id);
+        }
+        if let Some(dir) = &self.spool_dir {
+            let _ = match status {
+                JobStatus::Sent => fs::remove_file(spool_path(dir, job.id, "job")),
+                _ => retire(dir, job.id, "unconfirmed"),
+            };
+        }
+        if let Some(stock) = &mut self.stock {
+            stock.labels_printed(count_labels(&job.data));
+        }
+        self.notify(job.id, &status);
+    }
+
+    fn notify(&mut self, id: JobId, status: &JobStatus) {
+        for l in &mut self.listeners {
+            l(id, status);
+        }
+    }
+}
+
+fn spool_path(dir: &Path, id: JobId, ext: &str) -> PathBuf {
+    dir.join(format!("{:010}.{}", id, ext))
+}
+
+// Rename `<id>.job` to `<id>.<ext>`, leaving it in place rather than replacing an existing file
+fn retire(dir: &Path, id: JobId, ext: &str) -> io::Result<()> {
+    let to = spool_path(dir, id, ext);
+    if to.exists() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} already exists", to.display())));
+    }
+    fs::rename(spool_path(dir, id, "job"), to)
+}
+
+/// Result of `JobQueue::flush`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Flush {
+    pub sent: usize,
+    pub expired: usize,
+    /// Set when the printer was unreachable; the remaining jobs stay queued.
+    pub error: Option<String>,
+}
+
+/// Background thread that flushes a shared queue every `interval`, so jobs
+/// built while the printer was offline go out as soon as it is reachable.
+pub struct Flusher {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+impl Flusher {
+    pub fn spawn<T>(queue: Arc<Mutex<JobQueue>>, mut transport: T, interval: Duration) -> Self
+    where
+        T: Transport + Send + 'static,
+    {
+        let stop = Arc::new(AtomicBool::new(false));
+        let flag = stop.clone();
+        let handle = thread::spawn(move || {
+            while !flag.load(Ordering::Relaxed) {
+                if let Ok(mut q) = queue.lock() {
+                    if !q.is_empty() {
+                        q.flush(&mut transport);
+                    }
+                }
+                thread::park_timeout(interval);
+            }
+        });
+        Flusher { stop, handle }
+    }
+
+    /// Stop after the current flush and wait for the thread.
+    pub fn stop(self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.handle.thread().unpark();
+        let _ = self.handle.join();
+    }
+}
@@ -0,0 +1,129 @@
+use image::GrayImage;
+
+use crate::consts::LABEL_W;
+use crate::font::{BoldStrength, FontContext, TextStyle};
+use crate::label::{Label, LabelJob};
+use crate::warning::Warning;
+
+/// One row of a receipt, top to bottom.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReceiptLine {
+    /// Centered, bold, larger type.
+    Title(String),
+    /// Right-aligned line of text.
+    Text(String),
+    /// Name on the right, price (with currency) on the left.
+    Item { name: String, price: String },
+    /// Full-width rule.
+    Rule,
+    /// Empty space in dots.
+    Space(u32),
+}
+
+/// Receipt or pick list on continuous stock: the form is as long as its
+/// content and `Q` is sent with a gap of 0, so the printer doesn't look for
+/// label gaps.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Receipt {
+    pub width: u32,
+    pub font_px: f32,
+    pub lines: Vec<ReceiptLine>,
+    /// Blank dots after the last line, so it clears the tear bar.
+    pub feed: u32,
+}
+
+impl Receipt {
+    pub fn new(width: u32) -> Self {
+        Receipt { width, font_px: 28.0, lines: Vec::new(), feed: 40 }
+    }
+
+    pub fn title(mut self, text: &str) -> Self {
+        self.lines.push(ReceiptLine::Title(text.to_string()));
+        self
+    }
+
+    pub fn text(mut self, text: &str) -> Self {
+        self.lines.push(ReceiptLine::Text(text.to_string()));
+        self
+    }
+
+    pub fn item(mut self, name: &str, price: &str) -> Self {
+        self.lines.push(ReceiptLine::Item { name: name.to_string(), price: price.to_string() });
+        self
+    }
+
+    pub fn rule(mut self) -> Self {
+        self.lines.push(ReceiptLine::Rule);
+        self
+    }
+
+    pub fn space(mut self, dots: u32) -> Self {
+        self.lines.push(ReceiptLine::Space(dots));
+        self
+    }
+
+    /// Lay the lines out; the label height is the content height plus `feed`.
+    pub fn to_label(&self, ctx: &FontContext) -> (Label, Vec<Warning>) {
+        let (margin, line_gap) = (10, 4);
+        let inner_w = self.width.saturating_sub(2 * margin);
+        let mut warnings = Vec::new();
+
+        // (x, y, image) for bitmaps, (y, height) for rules
+        let mut graphics: Vec<(u32, u32, GrayImage)> = Vec::new();
+        let mut rules: Vec<(u32, u32)> = Vec::new();
+        let mut y = margin;
+        for (i, line) in self.lines.iter().enumerate() {
+            let (x, img) = match line {
+                ReceiptLine::Title(t) => {
+                    let img = ctx.render_line_styled(t, self.font_px * 1.4, 0, &TextStyle::bold(BoldStrength::Heavy));
+                    (margin + inner_w.saturating_sub(img.width()) / 2, img)
+                }
+                ReceiptLine::Text(t) => {
+                    let img = ctx.render_line(t, self.font_px, 0);
+                    (margin + inner_w.saturating_sub(img.width()), img)
+                }
+                ReceiptLine::Item { name, price } => {
+                    let (img, clipped) = crate::render_name_price_space_between(name, price, ctx, self.font_px, inner_w, false, crate::Direction::Ltr, crate::consts::JUSTIFY_NAMES);
+                    if clipped > 0 {
+                        warnings.push(Warning::TruncatedName { product: i, name: name.clone(), clipped_dots: clipped });
+                    }
+                    (margin, img)
+                }
+                ReceiptLine::Rule => {
+                    rules.push((y + line_gap, 2));
+                    y += 2 + 3 * line_gap;
+                    continue;
+                }
+                ReceiptLine::Space(dots) => {
+                    y += dots;
+                    continue;
+                }
+            };
+            let h = img.height();
+            graphics.push((x, y, img));
+            y += h + line_gap;
+        }
+
+        let mut label = Label::new(self.width, y + self.feed);
+        label.gap = 0; // continuous stock
+        for (x, gy, img) in graphics {
+            label.graphic(x, gy, img);
+        }
+        for (ry, h) in rules {
+            label.line(margin, ry, inner_w, h);
+        }
+        warnings.extend(label.warnings());
+        (label, warnings)
+    }
+
+    pub fn build(&self, ctx: &FontContext) -> LabelJob {
+        let (label, warnings) = self.to_label(ctx);
+        LabelJob::new(label.to_epl(), warnings)
+    }
+}
+
+impl Default for Receipt {
+    fn default() -> Self {
+        Receipt::new(LABEL_W)
+    }
+}
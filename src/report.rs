@@ -0,0 +1,141 @@
+use std::time::Duration;
+
+use crate::barcode::{Barcode, Symbology};
+use crate::consts::{DARKNESS, SPEED};
+use crate::epl::{parse, EplCommand};
+
+const DPI: f64 = 203.0;
+
+/// What a product builder actually rendered for one cell.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProductReport {
+    /// Index of the product, as in `Warning::TruncatedName`.
+    pub product: usize,
+    /// Size the name/price line was rendered at.
+    pub name_font_px: f32,
+    /// Dots cut off the end of the name (0 = printed in full).
+    pub truncated_dots: u32,
+    /// Size of the date line after shrinking it to the cell, `None` without dates.
+    pub date_font_px: Option<f32>,
+    /// Symbology and data as printed, with the reason for any fallback.
+    pub barcode: Barcode,
+}
+
+impl ProductReport {
+    pub fn truncated(&self) -> bool {
+        self.truncated_dots > 0
+    }
+}
+
+/// Summary of a built job, returned with it in `LabelJob::report`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BuildReport {
+    /// One entry per filled cell (product builders only; empty for other jobs).
+    pub products: Vec<ProductReport>,
+    /// Symbology of every printer barcode (`B`) in the job, in job order.
+    pub symbologies: Vec<Symbology>,
+    /// Size of the job as sent.
+    pub bytes: usize,
+    /// Labels the job feeds, summed over its `P` commands.
+    pub labels: u32,
+    /// `estimate_print_time` with the default (LP-2824) timings.
+    pub estimated_print_time: Duration,
+}
+
+impl BuildReport {
+    /// Everything `products` can't fill in, read back from the job itself.
+    pub fn from_job(job: &[u8]) -> BuildReport {
+        let mut report = BuildReport { bytes: job.len(), ..Default::default() };
+        for cmd in parse(job) {
+            match cmd {
+                EplCommand::Barcode { symbology: Some(s), .. } => report.symbologies.push(s),
+                EplCommand::Print { copies } => report.labels += copies,
+                _ => {}
+            }
+        }
+        report.estimated_print_time = estimate_print_time(job, &PrintTimeConfig::default());
+        report
+    }
+
+    /// Products whose name was cut off, for warning the operator.
+    pub fn truncated_names(&self) -> impl Iterator<Item = &ProductReport> {
+        self.products.iter().filter(|p| p.truncated())
+    }
+}
+
+/// Printer timings behind `estimate_print_time`. The defaults are the
+/// LP-2824's rated speeds plus typical overheads; time a run of your own
+/// stock and adjust `form_overhead` and `label_overhead` to match it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrintTimeConfig {
+    /// Feed speed in inches per second for `S1`, `S2`, ...; higher settings
+    /// print at the last entry.
+    pub inches_per_second: Vec<f64>,
+    /// Darkness from which the head needs longer to heat each dot line.
+    pub slow_darkness: u32,
+    /// Speed lost per darkness step from `slow_darkness` on (0.05 = 5%).
+    pub darkness_slowdown: f64,
+    /// Imaging one form (`N` ... `P`) before its first label feeds.
+    pub form_overhead: Duration,
+    /// Gap sensing and stop/start between labels.
+    pub label_overhead: Duration,
+    /// Host to printer transfer rate; `None` leaves transfer out.
+    pub bytes_per_second: Option<f64>,
+}
+
+impl Default for PrintTimeConfig {
+    fn default() -> Self {
+        PrintTimeConfig {
+            inches_per_second: vec![1.5, 2.0, 2.5, 3.5],
+            slow_darkness: 11,
+            darkness_slowdown: 0.05,
+            form_overhead: Duration::from_millis(250),
+            label_overhead: Duration::from_millis(60),
+            bytes_per_second: Some(100_000.0),
+        }
+    }
+}
+
+impl PrintTimeConfig {
+    fn speed(&self, setting: u32, darkness: u32) -> f64 {
+        let i = (setting.max(1) as usize - 1).min(self.inches_per_second.len().saturating_sub(1));
+        let ips = self.inches_per_second.get(i).copied().unwrap_or(2.0);
+        let steps = (darkness + 1).saturating_sub(self.slow_darkness) as f64;
+        ips * (1.0 - self.darkness_slowdown * steps).max(0.1)
+    }
+}
+
+/// How long `job` takes from the first byte sent to the last label out:
+/// per form its imaging time plus, per label, feeding the label and its gap
+/// at the speed and darkness in effect (`S` / `D`, crate defaults until set),
+/// plus the transfer of the job.
+pub fn estimate_print_time(job: &[u8], config: &PrintTimeConfig) -> Duration {
+    let (mut form_h, mut speed, mut darkness) = (0, SPEED as u32, DARKNESS as u32);
+    let mut secs = config.bytes_per_second.map_or(0.0, |bps| job.len() as f64 / bps.max(1.0));
+    for cmd in parse(job) {
+        match cmd {
+            EplCommand::Length { height, gap } => form_h = height + gap,
+            EplCommand::Speed(s) => speed = s,
+            EplCommand::Density(d) => darkness = d,
+            EplCommand::Print { copies } => {
+                let feed = form_h as f64 / DPI / config.speed(speed, darkness);
+                secs += config.form_overhead.as_secs_f64()
+                    + copies as f64 * (feed + config.label_overhead.as_secs_f64());
+            }
+            _ => {}
+        }
+    }
+    Duration::from_secs_f64(secs)
+}
+
+/// Labels per hour `job` prints at, repeated back to back (`estimate_print_time`).
+pub fn labels_per_hour(job: &[u8], config: &PrintTimeConfig) -> f64 {
+    let labels: u32 = parse(job).iter()
+        .map(|c| match c {
+            EplCommand::Print { copies } => *copies,
+            _ => 0,
+        })
+        .sum();
+    let secs = estimate_print_time(job, config).as_secs_f64();
+    if secs > 0.0 { labels as f64 * 3600.0 / secs } else { 0.0 }
+}
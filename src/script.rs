@@ -0,0 +1,92 @@
+use std::ops::Range;
+
+/// Writing system of a run of text, as far as shaping is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Script {
+    /// Arabic, Persian, Urdu, ...: joined letters, reshaped into presentation forms.
+    Arabic,
+    /// Right-to-left like Arabic, but letters don't join: reordered only.
+    Hebrew,
+    /// Latin, digits, punctuation, spaces and anything else.
+    Common,
+}
+
+impl Script {
+    pub fn of(c: char) -> Script {
+        match c {
+            '\u{0600}'..='\u{06FF}' | '\u{0750}'..='\u{077F}' | '\u{08A0}'..='\u{08FF}' | '\u{FB50}'..='\u{FDFF}' | '\u{FE70}'..='\u{FEFF}' => {
+                // Arabic-Indic digits are numbers first; they take the script of their neighbours
+                if is_digit(c) { Script::Common } else { Script::Arabic }
+            }
+            '\u{0590}'..='\u{05FF}' | '\u{FB1D}'..='\u{FB4F}' => Script::Hebrew,
+            _ => Script::Common,
+        }
+    }
+
+    /// Letters of the script are written right to left.
+    pub fn is_rtl(self) -> bool {
+        matches!(self, Script::Arabic | Script::Hebrew)
+    }
+}
+
+/// Byte ranges of `text` by script, in logical order. `Common` characters
+/// (spaces, digits, punctuation) stay with the run before them, so only a
+/// change between two real scripts starts a new run.
+pub fn script_runs(text: &str) -> Vec<(Script, Range<usize>)> {
+    let mut runs: Vec<(Script, Range<usize>)> = Vec::new();
+    for (i, c) in text.char_indices() {
+        let script = Script::of(c);
+        match runs.last_mut() {
+            Some((last, range)) if script == Script::Common || script == *last => range.end = i + c.len_utf8(),
+            // Leading neutrals join the first real script
+            Some((last, range)) if *last == Script::Common => {
+                *last = script;
+                range.end = i + c.len_utf8();
+            }
+            _ => runs.push((script, i..i + c.len_utf8())),
+        }
+    }
+    runs
+}
+
+/// Digit shapes to print numbers with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Digits {
+    /// 0123456789, left as they are.
+    #[default]
+    Western,
+    /// ٠١٢٣٤٥٦٧٨٩ (Egypt, Gulf, Levant).
+    ArabicIndic,
+    /// ۰۱۲۳۴۵۶۷۸۹ (Iran, Afghanistan, Pakistan: Persian and Urdu).
+    Persian,
+}
+
+impl Digits {
+    fn zero(self) -> u32 {
+        match self {
+            Digits::Western => '0' as u32,
+            Digits::ArabicIndic => 0x0660,
+            Digits::Persian => 0x06F0,
+        }
+    }
+}
+
+/// ASCII, Arabic-Indic or Persian digit.
+pub fn is_digit(c: char) -> bool {
+    c.is_ascii_digit() || ('\u{0660}'..='\u{0669}').contains(&c) || ('\u{06F0}'..='\u{06F9}').contains(&c)
+}
+
+/// `text` with every digit, whatever its script, written as `digits`.
+pub fn convert_digits(text: &str, digits: Digits) -> String {
+    text.chars()
+        .map(|c| {
+            let value = match c {
+                '0'..='9' => c as u32 - '0' as u32,
+                '\u{0660}'..='\u{0669}' => c as u32 - 0x0660,
+                '\u{06F0}'..='\u{06F9}' => c as u32 - 0x06F0,
+                _ => return c,
+            };
+            char::from_u32(digits.zero() + value).unwrap_or(c)
+        })
+        .collect()
+}
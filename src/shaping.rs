@@ -0,0 +1,168 @@
+use ar_reshaper::{ArabicReshaper, ReshaperConfig};
+use rusttype::{point, Font, Scale};
+use unicode_bidi::BidiInfo;
+
+use crate::script::{is_digit, script_runs, Script};
+
+/// One glyph placed by a shaper. `x`/`y` are pixels from the pen origin on the baseline
+/// (y grows downward), already in visual left→right order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShapedGlyph {
+    pub id: u16,
+    pub x: f32,
+    pub y: f32,
+}
+
+/// Text shaping backend: logical-order text in, visually ordered positioned glyphs out.
+///
+/// Implement this to plug in another engine (e.g. ICU/HarfBuzz via FFI). Glyph ids
+/// must refer to `font_data`, which is the same font the crate rasterizes with.
+pub trait Shaper: Send + Sync {
+    fn shape(&self, text: &str, font_data: &[u8], font_px: f32) -> Vec<ShapedGlyph>;
+}
+
+/// Default backend: BiDi reordering + `ar_reshaper` presentation forms for
+/// Arabic-script runs (Arabic, Persian, Urdu), laid out by rusttype. Hebrew is
+/// reordered only.
+pub struct ReshaperShaper {
+    reshaper: ArabicReshaper,
+}
+
+impl Default for ReshaperShaper {
+    fn default() -> Self {
+        // Harakat are kept; `FontContext` strips them beforehand unless asked not to
+        let mut config = ReshaperConfig::default();
+        config.delete_harakat = false;
+        config.shift_harakat_position = false;
+        ReshaperShaper { reshaper: ArabicReshaper::new(config) }
+    }
+}
+
+/// What happens to Arabic diacritics (harakat / tashkeel) in text to render.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Diacritics {
+    /// Remove them before shaping; names stay clean with any backend.
+    #[default]
+    Strip,
+    /// Shape them with their letters. Marks are stacked by the shaper: exact
+    /// with `RustybuzzShaper`, approximate with the default backend.
+    Keep,
+}
+
+/// Arabic combining marks (harakat, Quranic annotation signs, superscript alef).
+pub fn is_diacritic(c: char) -> bool {
+    matches!(c, '\u{0610}'..='\u{061A}' | '\u{064B}'..='\u{065F}' | '\u{0670}' | '\u{06D6}'..='\u{06DC}' | '\u{06DF}'..='\u{06E4}' | '\u{06E7}' | '\u{06E8}' | '\u{06EA}'..='\u{06ED}' | '\u{08D3}'..='\u{08FF}')
+}
+
+/// `text` without Arabic diacritics.
+pub fn strip_diacritics(text: &str) -> String {
+    text.chars().filter(|&c| !is_diacritic(c)).collect()
+}
+
+impl Shaper for ReshaperShaper {
+    fn shape(&self, text: &str, font_data: &[u8], font_px: f32) -> Vec<ShapedGlyph> {
+        let Some(font) = Font::try_from_bytes(font_data) else { return Vec::new() };
+        let visual = bidi_then_shape(text, &self.reshaper);
+        font.layout(&visual, Scale::uniform(font_px), point(0.0, 0.0))
+            .map(|g| ShapedGlyph { id: g.id().0, x: g.position().x, y: g.position().y })
+            .collect()
+    }
+}
+
+/// Visual-order string: BiDi runs; reshape the Arabic-script parts of RTL runs.
+pub fn bidi_then_shape(text: &str, reshaper: &ArabicReshaper) -> String {
+    let info = BidiInfo::new(text, None);
+    // Empty text has no paragraph
+    let Some(para) = info.paragraphs.first() else { return String::new() };
+    let (levels, ranges) = info.visual_runs(para, para.range.clone());
+
+    let mut out = String::new();
+    // Visual order runs; reshape RTL runs only, preserve LTR (digits) order
+    // `levels` is per byte of the line, so look the level up at each run's start
+    for range in ranges {
+        let level = levels[range.start];
+        let slice = &text[range];
+        if level.is_rtl() {
+            // Only reverse if it's actually RTL text (not digits/punctuation)
+            let shaped = reshape_arabic(slice, reshaper);
+            // Check if the slice contains Arabic/Hebrew letters vs just digits/symbols
+            if slice.chars().any(|c| ('\u{0600}'..='\u{06FF}').contains(&c) || Script::of(c).is_rtl()) {
+                // Contains RTL letters - reverse after shaping, keeping digit groups
+                // (Arabic-Indic digits land in this run) left-to-right
+                out.push_str(&reverse_keeping_numbers(&shaped));
+            } else {
+                // Just numbers/punctuation - don't reverse
+                out.push_str(&shaped);
+            }
+        } else {
+            out.push_str(slice);
+        }
+    }
+    out
+}
+
+// Reshaper per script run: Hebrew letters don't join and pass through as is
+fn reshape_arabic(text: &str, reshaper: &ArabicReshaper) -> String {
+    script_runs(text)
+        .into_iter()
+        .map(|(script, range)| match script {
+            Script::Hebrew => text[range].to_string(),
+            Script::Arabic | Script::Common => reshaper.reshape(&text[range]),
+        })
+        .collect()
+}
+
+fn reverse_keeping_numbers(s: &str) -> String {
+    let mut out: Vec<char> = s.chars().rev().collect();
+    let mut i = 0;
+    while i < out.len() {
+        let len = out[i..].iter().take_while(|c| is_digit(**c)).count();
+        out[i..i + len].reverse();
+        i += len.max(1);
+    }
+    out.into_iter().collect()
+}
+
+/// OpenType shaping with rustybuzz (GSUB/GPOS, marks, ligatures), run by BiDi run.
+#[cfg(feature = "rustybuzz")]
+#[derive(Default)]
+pub struct RustybuzzShaper;
+
+#[cfg(feature = "rustybuzz")]
+impl Shaper for RustybuzzShaper {
+    fn shape(&self, text: &str, font_data: &[u8], font_px: f32) -> Vec<ShapedGlyph> {
+        let Some(face) = rustybuzz::Face::from_slice(font_data, 0) else { return Vec::new() };
+        // rusttype's `Scale` maps ascent-descent (not the em square) to `font_px`
+        let scale = font_px / (face.ascender() - face.descender()) as f32;
+
+        let info = BidiInfo::new(text, None);
+        let mut out = Vec::new();
+        let mut pen = 0.0f32;
+        for para in &info.paragraphs {
+            // `levels` is per byte of the line, so look the level up at each run's start
+            let (levels, ranges) = info.visual_runs(para, para.range.clone());
+            for range in ranges {
+                let level = levels[range.start];
+                let mut buffer = rustybuzz::UnicodeBuffer::new();
+                buffer.push_str(&text[range]);
+                buffer.guess_segment_properties();
+                buffer.set_direction(if level.is_rtl() {
+                    rustybuzz::Direction::RightToLeft
+                } else {
+                    rustybuzz::Direction::LeftToRight
+                });
+                // RTL runs come back already in visual (left→right) order
+                let glyphs = rustybuzz::shape(&face, &[], buffer);
+                for (gi, gp) in glyphs.glyph_infos().iter().zip(glyphs.glyph_positions()) {
+                    out.push(ShapedGlyph {
+                        id: gi.glyph_id as u16,
+                        x: pen + gp.x_offset as f32 * scale,
+                        y: -(gp.y_offset as f32) * scale,
+                    });
+                    pen += gp.x_advance as f32 * scale;
+                }
+            }
+        }
+        out
+    }
+}
@@ -0,0 +1,120 @@
+use image::GrayImage;
+
+use crate::barcode::ensure_valid_ean13;
+use crate::consts::{NARROW, SHELF_H, SHELF_W};
+use crate::font::{BoldStrength, FontContext, TextStyle};
+use crate::label::{BarcodeCmd, Label, LabelJob};
+use crate::layout::{AlignH, AlignV, Block, Cell, Padding};
+use crate::product::Product;
+use crate::warning::Warning;
+
+/// Unit a pack size is given in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackUnit {
+    Gram,
+    Kilogram,
+    Millilitre,
+    Litre,
+}
+
+/// Net contents of a pack, e.g. `PackSize::new(500, PackUnit::Gram)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PackSize {
+    pub quantity: u32,
+    pub unit: PackUnit,
+}
+
+impl PackSize {
+    pub fn new(quantity: u32, unit: PackUnit) -> Self {
+        PackSize { quantity, unit }
+    }
+
+    // Contents in g or ml, and the caption of the unit price ("price per kilo/liter")
+    fn base(self) -> (u64, &'static str) {
+        match self.unit {
+            PackUnit::Gram => (self.quantity as u64, "سعر الكيلو"),
+            PackUnit::Kilogram => (self.quantity as u64 * 1000, "سعر الكيلو"),
+            PackUnit::Millilitre => (self.quantity as u64, "سعر اللتر"),
+            PackUnit::Litre => (self.quantity as u64 * 1000, "سعر اللتر"),
+        }
+    }
+
+    /// Price per kg / liter for a pack costing `price_cents`, rounded to the cent.
+    pub fn unit_price_cents(self, price_cents: u32) -> Result<u64, String> {
+        let (base, _) = self.base();
+        if base == 0 {
+            return Err("pack size must not be zero".to_string());
+        }
+        Ok((price_cents as u64 * 1000 + base / 2) / base)
+    }
+}
+
+/// `"12"`, `"12.5"` or `"12.50"` as cents.
+pub fn parse_price_cents(price: &str) -> Result<u32, String> {
+    let err = || format!("price {:?} is not a number with up to 2 decimals", price);
+    let (units, frac) = price.trim().split_once('.').unwrap_or((price.trim(), ""));
+    if units.is_empty() || frac.len() > 2 || !(units.chars().chain(frac.chars()).all(|c| c.is_ascii_digit())) {
+        return Err(err());
+    }
+    let units: u32 = units.parse().map_err(|_| err())?;
+    let cents: u32 = format!("{:0<2}", frac).parse().map_err(|_| err())?;
+    units.checked_mul(100).and_then(|u| u.checked_add(cents)).ok_or_else(err)
+}
+
+fn money(cents: u64) -> String {
+    format!("{}.{:02}", cents / 100, cents % 100)
+}
+
+/// 60x30 mm shelf-edge label: name across the top, barcode bottom left, price
+/// bottom right with the unit price (per kg or liter, from `pack`) in smaller
+/// type under it. `product.price` must be a plain decimal such as `"45.50"`;
+/// content that doesn't fit on the label is an error.
+pub fn build_shelf_label(ctx: &FontContext, product: &Product, pack: PackSize) -> Result<LabelJob, String> {
+    let price_cents = parse_price_cents(&product.price)?;
+    let unit_price = pack.unit_price_cents(price_cents)?;
+    let (_, caption) = pack.base();
+    let mut warnings = Vec::new();
+
+    // Name: one line, right-aligned; long names lose their (left) end
+    let max_name_w = SHELF_W - 20;
+    let name = ctx.render_line(&product.name, 36.0, 0);
+    let clipped = name.width().saturating_sub(max_name_w);
+    if clipped > 0 {
+        warnings.push(Warning::TruncatedName { product: 0, name: product.name.clone(), clipped_dots: clipped });
+    }
+    let name = crop_left(&name, max_name_w);
+
+    let price = ctx.render_line_styled(&format!("{} {}", product.price, "ج.م"), 52.0, 0, &TextStyle::bold(BoldStrength::Light));
+    // Reads "price per kilo: 182.00 EGP"
+    let unit = ctx.render_line(&format!("{} {} ج.م", caption, money(unit_price)), 22.0, 0);
+
+    let mut label = Label::new(SHELF_W, SHELF_H);
+    let top = Cell::new(0, 0, SHELF_W, SHELF_H).padding(Padding { top: 8, right: 10, ..Padding::default() }).align(AlignH::Right, AlignV::Top);
+    let (nx, ny) = top.place(&[Block::new(name.width(), name.height())])[0];
+    let body_y = ny + name.height() + 6;
+
+    // Barcode: left half, bars plus printer HRI (~20 dots) below
+    let code = ensure_valid_ean13(&product.barcode);
+    let bar_h = SHELF_H.saturating_sub(body_y + 8 + 24);
+    let bars = Cell::new(0, body_y, SHELF_W / 2, SHELF_H - body_y).padding(Padding { left: 24, ..Padding::default() }).align(AlignH::Left, AlignV::Top);
+    let (bx, by) = bars.place(&[Block::new(crate::barcode_width(crate::Symbology::Ean13, "", NARROW), bar_h)])[0];
+
+    // Price block: right half, centered vertically in the space under the name
+    let prices = Cell::new(SHELF_W / 2, body_y, SHELF_W / 2, SHELF_H - body_y).padding(Padding { right: 10, bottom: 8, ..Padding::default() }).align(AlignH::Right, AlignV::Middle);
+    let placed = prices.offsets(&[Block::new(price.width(), price.height()), Block::new(unit.width(), unit.height()).gap(-2)]);
+
+    label.graphic(nx, ny, name);
+    label.graphic_at(placed[0].0, placed[0].1, price);
+    label.graphic_at(placed[1].0, placed[1].1, unit);
+    label.barcode_with_hri(BarcodeCmd { hri: product.hri, ..BarcodeCmd::ean13(bx, by, NARROW, bar_h, &code) }, ctx);
+
+    label.check_bounds()?;
+    warnings.extend(label.warnings());
+    Ok(LabelJob::new(label.to_epl(), warnings))
+}
+
+// Right-most `w` columns of `img`
+fn crop_left(img: &GrayImage, w: u32) -> GrayImage {
+    let w = img.width().min(w);
+    image::imageops::crop_imm(img, img.width() - w, 0, w, img.height()).to_image()
+}
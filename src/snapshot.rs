@@ -0,0 +1,102 @@
+use std::fs;
+use std::path::Path;
+
+use crate::epl::command_spans;
+use crate::stamp::canonical_placeholder;
+
+/// Set to regenerate snapshot files instead of comparing against them.
+pub const UPDATE_ENV: &str = "UPDATE_SNAPSHOTS";
+
+// Commands that only add black to the current form; their order doesn't change
+// the print. White (`LW`) and XOR (`LE`) lines and reverse text (`A...,R,`) act
+// on what's drawn before them, so they stay where they are.
+fn is_drawing(line: &str) -> bool {
+    let reverse_text = line.starts_with('A') && line.split(',').nth(6) == Some("R");
+    !reverse_text && ["GW", "B", "A", "X", "LO", "LS", ";"].iter().any(|p| line.starts_with(p))
+}
+
+/// Job with each run of order-independent drawing commands sorted, floats in stamp
+/// placeholders fixed to two decimals and every command CRLF terminated.
+/// Two jobs that print the same labels the same way canonicalize to the same bytes.
+pub fn canonicalize(job: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(job.len());
+    let mut drawing: Vec<Vec<u8>> = Vec::new();
+    let flush = |out: &mut Vec<u8>, drawing: &mut Vec<Vec<u8>>| {
+        drawing.sort();
+        drawing.drain(..).for_each(|c| out.extend_from_slice(&c));
+    };
+
+    for (span, line) in command_spans(job) {
+        let mut cmd = match canonical_placeholder(&line) {
+            Some(p) => format!("{}\r\n", p).into_bytes(),
+            None => job[span].to_vec(),
+        };
+        if !cmd.ends_with(b"\r\n") {
+            cmd.extend_from_slice(b"\r\n");
+        }
+        if is_drawing(&line) {
+            drawing.push(cmd);
+        } else {
+            flush(&mut out, &mut drawing);
+            out.extend_from_slice(&cmd);
+        }
+    }
+    flush(&mut out, &mut drawing);
+    out
+}
+
+/// Stable 64-bit hash (FNV-1a) of the canonical form of `job`; the same on
+/// every platform and crate version, so it can be stored and compared later.
+pub fn fingerprint(job: &[u8]) -> u64 {
+    fnv1a(&canonicalize(job))
+}
+
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |h, &b| (h ^ b as u64).wrapping_mul(0x0000_0100_0000_01b3))
+}
+
+/// Readable form of the canonical job for snapshot files: one command per
+/// line, `GW` payloads replaced by their size and hash.
+pub fn snapshot_text(job: &[u8]) -> String {
+    let canonical = canonicalize(job);
+    command_spans(&canonical).into_iter()
+        .map(|(span, line)| {
+            if line.starts_with("GW") {
+                let payload = &canonical[span.start + line.len() + 2..span.end.saturating_sub(2)];
+                format!("{} # {} bytes {:016x}\n", line, payload.len(), fnv1a(payload))
+            } else {
+                format!("{}\n", line)
+            }
+        })
+        .collect()
+}
+
+/// Compare `job` with the snapshot stored at `path`, for tests that guard
+/// against layout drift. A missing file is written; with `UPDATE_SNAPSHOTS`
+/// set in the environment the file is rewritten instead of compared.
+///
+/// # Panics
+/// If the job differs from the snapshot, listing the first differing commands.
+pub fn assert_snapshot(path: impl AsRef<Path>, job: &[u8]) {
+    let path = path.as_ref();
+    let actual = snapshot_text(job);
+    if std::env::var_os(UPDATE_ENV).is_some() || !path.exists() {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).unwrap_or_else(|e| panic!("{}: {}", dir.display(), e));
+        }
+        fs::write(path, &actual).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        return;
+    }
+
+    let expected = fs::read_to_string(path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+    if expected == actual {
+        return;
+    }
+    let (exp, act): (Vec<&str>, Vec<&str>) = (expected.lines().collect(), actual.lines().collect());
+    let diff: Vec<String> = (0..exp.len().max(act.len()))
+        .filter(|&i| exp.get(i) != act.get(i))
+        .take(10)
+        .map(|i| format!("  {}: - {}\n  {}: + {}", i + 1, exp.get(i).unwrap_or(&""), i + 1, act.get(i).unwrap_or(&"")))
+        .collect();
+    panic!("label job differs from snapshot {} (set {}=1 to accept):\n{}", path.display(), UPDATE_ENV, diff.join("\n"));
+}
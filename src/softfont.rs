@@ -0,0 +1,176 @@
+use std::error::Error;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+
+use image::GrayImage;
+
+use crate::epl::epl_line;
+use crate::font::{FontContext, TextStyle};
+use crate::label::TextCmd;
+use crate::transport::tcp::TcpPrinter;
+use crate::transport::Transport;
+
+/// Soft font directory request; the printer answers with the stored names.
+pub const LIST_FONTS: &[u8] = b"EI\r\n";
+
+/// One character of a soft font.
+#[derive(Debug, Clone)]
+pub struct SoftGlyph {
+    /// Character code the glyph is stored under (Latin-1).
+    pub code: u8,
+    /// Dots from this character's start to the next one's.
+    pub spacing: u8,
+    /// 1-bit bitmap (luma < 128 is black), the font's height tall.
+    pub image: GrayImage,
+}
+
+/// Bitmap font stored in the printer under a letter (`a`..`z`) and used by
+/// `A` commands like a resident font: plain Latin text without a `GW`
+/// bitmap per line.
+#[derive(Debug, Clone)]
+pub struct SoftFont {
+    pub name: char,
+    /// Cell height in dots, the same for every glyph.
+    pub height: u32,
+    pub glyphs: Vec<SoftGlyph>,
+}
+
+impl SoftFont {
+    /// Subset of `ctx`'s font: the distinct characters of `chars`, rasterized
+    /// at `font_px` with `style`. Characters must be printable Latin-1.
+    pub fn from_font(ctx: &FontContext, name: char, font_px: f32, chars: &str, style: &TextStyle) -> Result<SoftFont, String> {
+        if !name.is_ascii_lowercase() {
+            return Err(format!("soft font name must be a..z, got {:?}", name));
+        }
+        let mut codes: Vec<char> = chars.chars().collect();
+        codes.sort_unstable();
+        codes.dedup();
+        if let Some(c) = codes.iter().find(|&&c| c > '\u{FF}' || c.is_control()) {
+            return Err(format!("{:?} can't be stored in a soft font (printable Latin-1 only)", c));
+        }
+        if codes.is_empty() || codes.len() > 255 {
+            return Err(format!("a soft font holds 1..255 characters, got {}", codes.len()));
+        }
+
+        let mut glyphs = Vec::with_capacity(codes.len());
+        let mut height = 0;
+        for c in codes {
+            let image = ctx.render_line_styled(&c.to_string(), font_px, 0, style);
+            if image.width() > 255 || image.height() > 255 {
+                return Err(format!("{:?} is {}x{} dots at {}px, soft font glyphs are at most 255x255", c, image.width(), image.height(), font_px));
+            }
+            height = height.max(image.height());
+            glyphs.push(SoftGlyph { code: c as u8, spacing: image.width() as u8, image });
+        }
+        Ok(SoftFont { name, height, glyphs })
+    }
+
+    /// `ES` command storing the font. Layout: name, glyph count and height,
+    /// then per glyph its code, spacing and width in bytes followed by
+    /// `height` rows of that many bytes (MSB first, 1 = black).
+    pub fn download_command(&self) -> Vec<u8> {
+        let mut buf = format!("ES{}", self.name).into_bytes();
+        buf.extend([self.glyphs.len() as u8, self.height as u8]);
+        for g in &self.glyphs {
+            let bpr = g.image.width().div_ceil(8);
+            buf.extend([g.code, g.spacing, bpr as u8]);
+            for y in 0..self.height {
+                let mut row = vec![0u8; bpr as usize];
+                for x in 0..g.image.width() {
+                    if y < g.image.height() && g.image.get_pixel(x, y).0[0] < 128 {
+                        row[(x / 8) as usize] |= 0x80 >> (x % 8);
+                    }
+                }
+                buf.extend(row);
+            }
+        }
+        buf.extend_from_slice(b"\r\n");
+        buf
+    }
+
+    /// Width in dots of `text` printed in this font; characters it doesn't
+    /// hold count as nothing (the printer skips them).
+    pub fn text_width(&self, text: &str) -> u32 {
+        text.chars()
+            .filter_map(|c| self.glyphs.iter().find(|g| g.code as u32 == c as u32))
+            .map(|g| g.spacing as u32)
+            .sum()
+    }
+
+    /// `A` command printing `text` at (x, y) in this font; its size is
+    /// estimated from the widest glyph.
+    pub fn text(&self, x: u32, y: u32, text: &str) -> TextCmd {
+        let pitch = self.glyphs.iter().map(|g| g.spacing as u32).max().unwrap_or(0);
+        TextCmd { cell: Some((pitch, self.height)), ..TextCmd::new(x, y, self.name as u8, text) }
+    }
+}
+
+// Length of the `ES` command at the start of `bytes` (trailing CRLF included),
+// `None` if it doesn't start with a complete one
+pub(crate) fn command_len(bytes: &[u8]) -> Option<usize> {
+    let [b'E', b'S', name, count, height, ..] = *bytes else { return None };
+    if !name.is_ascii_lowercase() {
+        return None;
+    }
+    let mut pos = 5;
+    for _ in 0..count {
+        let bpr = *bytes.get(pos + 2)? as usize;
+        pos += 3 + bpr * height as usize;
+    }
+    if bytes.get(pos..pos + 2) == Some(b"\r\n") {
+        pos += 2;
+    }
+    (pos <= bytes.len()).then_some(pos)
+}
+
+/// `EK` command deleting soft font `name`, or every soft font with `None`.
+pub fn delete_command(name: Option<char>) -> Vec<u8> {
+    let mut buf = Vec::new();
+    epl_line(&mut buf, &format!("EK\"{}\"", name.unwrap_or('*')));
+    buf
+}
+
+/// Store `font`, replacing a stored font of the same name.
+pub fn download<T: Transport + ?Sized>(transport: &mut T, font: &SoftFont) -> Result<(), Box<dyn Error>> {
+    let mut job = delete_command(Some(font.name));
+    job.extend(font.download_command());
+    transport.send(&job)
+}
+
+/// Delete soft font `name`, or every soft font with `None`.
+pub fn delete<T: Transport + ?Sized>(transport: &mut T, name: Option<char>) -> Result<(), Box<dyn Error>> {
+    transport.send(&delete_command(name))
+}
+
+/// Soft font names in an `EI` reply: every token that is a single letter a..z.
+pub fn parse_font_list(reply: &[u8]) -> Vec<char> {
+    let mut names: Vec<char> = String::from_utf8_lossy(reply)
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '"')
+        .filter_map(|t| match t.as_bytes() {
+            [b] if b.is_ascii_lowercase() => Some(*b as char),
+            _ => None,
+        })
+        .collect();
+    names.sort_unstable();
+    names.dedup();
+    names
+}
+
+/// Send `EI` and read the reply from a bidirectional stream.
+pub fn list<S: Read + Write>(stream: &mut S) -> Result<Vec<char>, Box<dyn Error>> {
+    stream.write_all(LIST_FONTS)?;
+    stream.flush()?;
+    let mut buf = [0u8; 256];
+    let n = stream.read(&mut buf)?;
+    Ok(parse_font_list(&buf[..n]))
+}
+
+impl TcpPrinter {
+    /// Names of the soft fonts stored in the printer (`EI`).
+    pub fn soft_fonts(&self) -> Result<Vec<char>, Box<dyn Error>> {
+        let mut stream = TcpStream::connect_timeout(&self.addr, self.timeout)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+        list(&mut stream)
+    }
+}
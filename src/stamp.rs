@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use image::{GrayImage, Luma};
+
+use crate::epl::{command_spans, gw_bytes, image_to_row_bytes};
+use crate::font::FontContext;
+use crate::transport::Transport;
+
+const PREFIX: &str = "; stamp ";
+// Placeholder of a mirrored label: the value is flipped when rendered
+const MIRRORED_PREFIX: &str = "; stamp-mirrored ";
+
+/// Built-in key: UTC time the job is handed to the transport (`YYYY-MM-DD HH:MM`).
+pub const PRINT_TIME: &str = "print_time";
+
+// `; stamp x,y,w,h,px,key` (key last so it may contain commas)
+pub(crate) fn placeholder_line(x: u32, y: u32, w: u32, h: u32, font_px: f32, key: &str, mirrored: bool) -> String {
+    let prefix = if mirrored { MIRRORED_PREFIX } else { PREFIX };
+    format!("{}{},{},{},{},{},{}", prefix, x, y, w, h, font_px, key.replace(['\r', '\n'], " "))
+}
+
+// Placeholder line with the font size fixed to two decimals (`snapshot::canonicalize`)
+pub(crate) fn canonical_placeholder(line: &str) -> Option<String> {
+    let p = parse_placeholder(line)?;
+    let prefix = if p.mirrored { MIRRORED_PREFIX } else { PREFIX };
+    Some(format!("{}{},{},{},{},{:.2},{}", prefix, p.x, p.y, p.w, p.h, p.font_px, p.key))
+}
+
+struct Placeholder<'k> {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+    font_px: f32,
+    key: &'k str,
+    mirrored: bool,
+}
+
+fn parse_placeholder(line: &str) -> Option<Placeholder<'_>> {
+    let (args, mirrored) = match line.strip_prefix(MIRRORED_PREFIX) {
+        Some(args) => (args, true),
+        None => (line.strip_prefix(PREFIX)?, false),
+    };
+    let mut p = args.splitn(6, ',');
+    Some(Placeholder {
+        x: p.next()?.parse().ok()?,
+        y: p.next()?.parse().ok()?,
+        w: p.next()?.parse().ok()?,
+        h: p.next()?.parse().ok()?,
+        font_px: p.next()?.parse().ok()?,
+        key: p.next()?,
+        mirrored,
+    })
+}
+
+type Field<'a> = Box<dyn FnMut() -> String + Send + 'a>;
+
+/// Resolves `Label::stamp` placeholders of built jobs just before they are sent.
+///
+/// Each placeholder is replaced by a `GW` bitmap of its value, centered in the
+/// reserved region and clipped to it (and flipped on mirrored labels). Keys without a value are left as
+/// comments, so the region simply prints blank.
+pub struct Stamper<'a> {
+    ctx: &'a FontContext<'a>,
+    fields: HashMap<String, Field<'a>>,
+}
+
+impl<'a> Stamper<'a> {
+    /// Stamper with `PRINT_TIME` predefined.
+    pub fn new(ctx: &'a FontContext<'a>) -> Self {
+        let mut s = Stamper { ctx, fields: HashMap::new() };
+        s.field(PRINT_TIME, || utc_timestamp(SystemTime::now()));
+        s
+    }
+
+    /// Fixed value (e.g. the operator ID of the current shift).
+    pub fn value(&mut self, key: &str, value: &str) -> &mut Self {
+        let value = value.to_string();
+        self.field(key, move || value.clone())
+    }
+
+    /// Value computed for every job.
+    pub fn field<F>(&mut self, key: &str, f: F) -> &mut Self
+    where
+        F: FnMut() -> String + Send + 'a,
+    {
+        self.fields.insert(key.to_string(), Box::new(f));
+        self
+    }
+
+    /// Copy of `job` with every known placeholder rendered.
+    pub fn apply(&mut self, job: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(job.len());
+        for (span, line) in command_spans(job) {
+            let resolved = parse_placeholder(&line)
+                .and_then(|p| Some(((self.fields.get_mut(p.key)?)(), p)));
+            match resolved {
+                Some((value, p)) => {
+                    let img = self.render(&value, &p);
+                    let (w, h, rows) = image_to_row_bytes(&img);
+                    gw_bytes(&mut out, p.x, p.y, w, h, &rows);
+                }
+                None => out.extend_from_slice(&job[span]),
+            }
+        }
+        out
+    }
+
+    fn render(&self, value: &str, p: &Placeholder) -> GrayImage {
+        let text = self.ctx.render_line(value, p.font_px, 0);
+        let mut img = GrayImage::from_pixel(p.w.max(1), p.h.max(1), Luma([255]));
+        let dx = (p.w as i64 - text.width() as i64) / 2;
+        let dy = (p.h as i64 - text.height() as i64) / 2;
+        image::imageops::replace(&mut img, &text, dx, dy);
+        if p.mirrored {
+            image::imageops::flip_horizontal_in_place(&mut img);
+        }
+        img
+    }
+
+    /// Wrap a transport so every job is stamped on its way out.
+    pub fn transport<T: Transport>(self, inner: T) -> StampingTransport<'a, T> {
+        StampingTransport { stamper: self, inner }
+    }
+}
+
+/// Transport adapter returned by `Stamper::transport`.
+pub struct StampingTransport<'a, T> {
+    pub stamper: Stamper<'a>,
+    pub inner: T,
+}
+
+impl<T: Transport> Transport for StampingTransport<'_, T> {
+    fn send(&mut self, data: &[u8]) -> Result<(), Box<dyn Error>> {
+        let job = self.stamper.apply(data);
+        self.inner.send(&job)
+    }
+}
+
+/// `YYYY-MM-DD HH:MM` in UTC.
+pub fn utc_timestamp(t: SystemTime) -> String {
+    let secs = t.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+
+    let date = crate::date::Date::from_unix_days(days as i64);
+    format!("{} {:02}:{:02}", date, rem / 3600, rem % 3600 / 60)
+}
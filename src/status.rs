@@ -0,0 +1,86 @@
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use crate::transport::tcp::TcpPrinter;
+use crate::transport::LpDevice;
+
+/// EPL2 immediate error report request; the printer answers with a 2-digit code.
+pub const ERROR_REPORT: &[u8] = b"^ee\r\n";
+
+/// Printer condition decoded from the `^ee` error report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrinterStatus {
+    Ready,
+    /// Code 07: print head up (open).
+    HeadOpen,
+    /// Code 08: paper or ribbon empty.
+    PaperOut,
+    /// Any other non-zero error code.
+    Error(u8),
+}
+
+impl PrinterStatus {
+    pub fn is_ready(self) -> bool {
+        self == PrinterStatus::Ready
+    }
+}
+
+/// Parse a `^ee` reply (e.g. `"08\r\n"`).
+pub fn parse_error_report(reply: &[u8]) -> Option<PrinterStatus> {
+    let text = std::str::from_utf8(reply).ok()?;
+    let code: u8 = text.split_whitespace().next()?.parse().ok()?;
+    Some(match code {
+        0 => PrinterStatus::Ready,
+        7 => PrinterStatus::HeadOpen,
+        8 => PrinterStatus::PaperOut,
+        other => PrinterStatus::Error(other),
+    })
+}
+
+/// Anything that can report the printer's current condition.
+pub trait StatusSource {
+    fn status(&mut self) -> Result<PrinterStatus, Box<dyn Error>>;
+}
+
+impl<F> StatusSource for F
+where
+    F: FnMut() -> Result<PrinterStatus, Box<dyn Error>>,
+{
+    fn status(&mut self) -> Result<PrinterStatus, Box<dyn Error>> {
+        self()
+    }
+}
+
+// Send `^ee` and read the reply from a bidirectional stream
+fn query<S: Read + Write>(stream: &mut S) -> Result<PrinterStatus, Box<dyn Error>> {
+    stream.write_all(ERROR_REPORT)?;
+    stream.flush()?;
+    let mut buf = [0u8; 32];
+    let n = stream.read(&mut buf)?;
+    parse_error_report(&buf[..n]).ok_or_else(|| "unrecognized ^ee reply".into())
+}
+
+impl StatusSource for TcpPrinter {
+    fn status(&mut self) -> Result<PrinterStatus, Box<dyn Error>> {
+        let mut stream = TcpStream::connect_timeout(&self.addr, self.timeout)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+        query(&mut stream)
+    }
+}
+
+/// Works with bidirectional USB printer-class devices (`usblp` supports reads).
+impl StatusSource for LpDevice {
+    fn status(&mut self) -> Result<PrinterStatus, Box<dyn Error>> {
+        let mut dev = OpenOptions::new().read(true).write(true).open(&self.path)?;
+        // usblp returns what the printer already answered; give it a moment
+        dev.write_all(ERROR_REPORT)?;
+        std::thread::sleep(Duration::from_millis(200));
+        let mut buf = [0u8; 32];
+        let n = dev.read(&mut buf)?;
+        parse_error_report(&buf[..n]).ok_or_else(|| "unrecognized ^ee reply".into())
+    }
+}
@@ -0,0 +1,75 @@
+use std::sync::{Arc, Mutex};
+
+/// Told how many physical labels each successfully sent job consumed.
+pub trait StockCounter: Send {
+    fn labels_printed(&mut self, count: u32);
+}
+
+/// Shared counters let the application keep a handle for reading the state.
+impl<T: StockCounter> StockCounter for Arc<Mutex<T>> {
+    fn labels_printed(&mut self, count: u32) {
+        if let Ok(mut inner) = self.lock() {
+            inner.labels_printed(count);
+        }
+    }
+}
+
+type LowStockCallback = Box<dyn FnMut(u32) + Send>;
+
+/// Estimates labels left on the loaded roll (roll size minus printed count) and
+/// calls `on_low` once when the estimate drops to or below the threshold.
+pub struct RollTracker {
+    roll_size: u32,
+    printed: u32,
+    low_threshold: u32,
+    warned: bool,
+    on_low: Option<LowStockCallback>,
+}
+
+impl RollTracker {
+    pub fn new(roll_size: u32, low_threshold: u32) -> Self {
+        RollTracker { roll_size, printed: 0, low_threshold, warned: false, on_low: None }
+    }
+
+    /// Callback receives the estimated remaining labels.
+    pub fn on_low<F: FnMut(u32) + Send + 'static>(mut self, f: F) -> Self {
+        self.on_low = Some(Box::new(f));
+        self
+    }
+
+    pub fn printed(&self) -> u32 {
+        self.printed
+    }
+
+    pub fn remaining(&self) -> u32 {
+        self.roll_size.saturating_sub(self.printed)
+    }
+
+    pub fn is_low(&self) -> bool {
+        self.remaining() <= self.low_threshold
+    }
+
+    /// A fresh roll of the same size was loaded.
+    pub fn new_roll(&mut self) {
+        self.printed = 0;
+        self.warned = false;
+    }
+
+    pub fn new_roll_of(&mut self, roll_size: u32) {
+        self.roll_size = roll_size;
+        self.new_roll();
+    }
+}
+
+impl StockCounter for RollTracker {
+    fn labels_printed(&mut self, count: u32) {
+        self.printed = self.printed.saturating_add(count);
+        if self.is_low() && !self.warned {
+            self.warned = true;
+            let remaining = self.remaining();
+            if let Some(cb) = &mut self.on_low {
+                cb(remaining);
+            }
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::dedup::RecentJobs;
+use crate::queue::JobId;
+
+pub mod tcp;
+
+/// Split a job into several writes with a pause in between, so a printer with
+/// a small receive buffer (LP-2824) isn't handed a huge `GW` payload at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chunking {
+    /// Bytes per write.
+    pub size: usize,
+    /// Pause after every chunk but the last.
+    pub delay: Duration,
+}
+
+impl Chunking {
+    pub fn new(size: usize, delay: Duration) -> Self {
+        Chunking { size: size.max(1), delay }
+    }
+}
+
+// The whole job as one chunk without chunking
+pub(crate) fn chunks<'d>(data: &'d [u8], chunking: Option<&Chunking>) -> std::slice::Chunks<'d, u8> {
+    data.chunks(chunking.map_or(data.len(), |c| c.size).max(1))
+}
+
+// Write chunk by chunk, flushing and pausing in between
+pub(crate) fn write_chunked<W: Write>(w: &mut W, data: &[u8], chunking: Option<&Chunking>) -> Result<(), Box<dyn Error>> {
+    let mut parts = chunks(data, chunking).peekable();
+    while let Some(part) = parts.next() {
+        w.write_all(part)?;
+        if let (Some(c), Some(_)) = (chunking, parts.peek()) {
+            w.flush()?;
+            std::thread::sleep(c.delay);
+        }
+    }
+    w.flush()?;
+    Ok(())
+}
+
+/// Anything that can deliver a finished EPL2 job (raw bytes) to a printer.
+pub trait Transport {
+    /// Send the whole job. An error means the job may not have been printed.
+    fn send(&mut self, data: &[u8]) -> Result<(), Box<dyn Error>>;
+}
+
+/// Plain closures work as transports, which keeps tests and ad-hoc glue short.
+impl<F> Transport for F
+where
+    F: FnMut(&[u8]) -> Result<(), Box<dyn Error>>,
+{
+    fn send(&mut self, data: &[u8]) -> Result<(), Box<dyn Error>> {
+        self(data)
+    }
+}
+
+/// Sends every job with its `GW` bitmaps redrawn as `LO` lines (see
+/// `epl::gw_to_lines`), for print servers or filters that aren't 8-bit clean.
+pub struct AsciiOnly<T> {
+    pub inner: T,
+}
+
+pub fn ascii_only<T: Transport>(inner: T) -> AsciiOnly<T> {
+    AsciiOnly { inner }
+}
+
+impl<T: Transport> Transport for AsciiOnly<T> {
+    fn send(&mut self, data: &[u8]) -> Result<(), Box<dyn Error>> {
+        self.inner.send(&crate::epl::gw_to_lines(data))
+    }
+}
+
+/// Drops a job identical to one sent less than `window` ago (a double-clicked
+/// print button), see `dedup`. A job only counts as sent once `inner`
+/// accepted it, so resending after a transport error goes through.
+pub struct Dedup<T> {
+    pub inner: T,
+    recent: RecentJobs,
+    sent: JobId,
+    skipped: u64,
+    reject: bool,
+}
+
+pub fn dedup<T: Transport>(inner: T, window: Duration) -> Dedup<T> {
+    Dedup { inner, recent: RecentJobs::new(window), sent: 0, skipped: 0, reject: false }
+}
+
+impl<T> Dedup<T> {
+    /// Fail duplicates with an error instead of skipping them silently.
+    pub fn reject_duplicates(mut self) -> Self {
+        self.reject = true;
+        self
+    }
+
+    /// Duplicates dropped so far.
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+}
+
+impl<T: Transport> Transport for Dedup<T> {
+    fn send(&mut self, data: &[u8]) -> Result<(), Box<dyn Error>> {
+        if let Some((_, ago)) = self.recent.find(data) {
+            self.skipped += 1;
+            if self.reject {
+                return Err(format!("duplicate of a job sent {:.1}s ago", ago.as_secs_f32()).into());
+            }
+            return Ok(());
+        }
+        self.inner.send(data)?;
+        self.sent += 1;
+        self.recent.record(data, self.sent);
+        Ok(())
+    }
+}
+
+/// Win32 spooler queue addressed by printer name (RAW datatype).
+#[cfg(all(target_os = "windows", feature = "windows-spooler"))]
+pub struct WindowsSpooler {
+    pub printer_name: String,
+    /// Several `WritePrinter` calls within one spooler document.
+    pub chunking: Option<Chunking>,
+}
+
+#[cfg(all(target_os = "windows", feature = "windows-spooler"))]
+impl WindowsSpooler {
+    pub fn new(printer_name: &str) -> Self {
+        WindowsSpooler { printer_name: printer_name.to_string(), chunking: None }
+    }
+
+    pub fn chunked(mut self, chunking: Chunking) -> Self {
+        self.chunking = Some(chunking);
+        self
+    }
+}
+
+#[cfg(all(target_os = "windows", feature = "windows-spooler"))]
+impl Transport for WindowsSpooler {
+    fn send(&mut self, data: &[u8]) -> Result<(), Box<dyn Error>> {
+        crate::printer::send_raw_to_printer_chunked(&self.printer_name, data, self.chunking.as_ref())
+    }
+}
+
+/// Printer character device, e.g. `/dev/usb/lp0` from the Linux `usblp` driver.
+pub struct LpDevice {
+    pub path: PathBuf,
+    pub chunking: Option<Chunking>,
+}
+
+/// Writer for a printer character device (the device is opened per job).
+pub fn lp_device(path: impl AsRef<Path>) -> LpDevice {
+    LpDevice { path: path.as_ref().to_path_buf(), chunking: None }
+}
+
+impl LpDevice {
+    pub fn chunked(mut self, chunking: Chunking) -> Self {
+        self.chunking = Some(chunking);
+        self
+    }
+}
+
+impl Transport for LpDevice {
+    fn send(&mut self, data: &[u8]) -> Result<(), Box<dyn Error>> {
+        let mut dev = OpenOptions::new().write(true).open(&self.path)
+            .map_err(|e| format!("open {}: {}", self.path.display(), e))?;
+        write_chunked(&mut dev, data, self.chunking.as_ref())
+    }
+}
+
+/// CUPS raw queue, submitted like `lp -d <queue> -o raw` with the job piped on stdin.
+/// CUPS spools the whole job and paces the device itself, so there is no chunking option.
+#[cfg(unix)]
+pub struct CupsRaw {
+    pub queue: String,
+    /// CUPS server (`lp -h`), `None` = local scheduler.
+    pub server: Option<String>,
+}
+
+#[cfg(unix)]
+pub fn cups_raw(queue: &str) -> CupsRaw {
+    CupsRaw { queue: queue.to_string(), server: None }
+}
+
+#[cfg(unix)]
+impl CupsRaw {
+    pub fn server(mut self, server: &str) -> Self {
+        self.server = Some(server.to_string());
+        self
+    }
+}
+
+#[cfg(unix)]
+impl Transport for CupsRaw {
+    fn send(&mut self, data: &[u8]) -> Result<(), Box<dyn Error>> {
+        use std::process::{Command, Stdio};
+
+        let mut cmd = Command::new("lp");
+        if let Some(server) = &self.server {
+            cmd.args(["-h", server]);
+        }
+        cmd.args(["-d", &self.queue, "-o", "raw", "-s"]) // no file argument: lp reads stdin
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
+
+        let mut child = cmd.spawn().map_err(|e| format!("spawn lp: {}", e))?;
+        // Take stdin so it is closed (EOF) before we wait
+        child.stdin.take().ok_or("lp stdin unavailable")?.write_all(data)?;
+        let out = child.wait_with_output()?;
+        if !out.status.success() {
+            let msg = String::from_utf8_lossy(&out.stderr);
+            return Err(format!("lp failed ({}): {}", out.status, msg.trim()).into());
+        }
+        Ok(())
+    }
+}
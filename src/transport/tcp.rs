@@ -0,0 +1,71 @@
+use std::error::Error;
+use std::net::{IpAddr, Shutdown, SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+use super::{write_chunked, Chunking, Transport};
+
+/// Raw port of Zebra print servers.
+pub const RAW_PORT: u16 = 9100;
+
+/// Networked printer reached over a raw TCP socket (one connection per job).
+#[derive(Debug, Clone)]
+pub struct TcpPrinter {
+    pub addr: SocketAddr,
+    pub timeout: Duration,
+    pub chunking: Option<Chunking>,
+}
+
+impl TcpPrinter {
+    pub fn new(addr: SocketAddr) -> Self {
+        TcpPrinter { addr, timeout: Duration::from_secs(5), chunking: None }
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn chunked(mut self, chunking: Chunking) -> Self {
+        self.chunking = Some(chunking);
+        self
+    }
+}
+
+/// Resolve `addr` (`"10.0.0.7"`, `"printer.local:9100"`); port 9100 is assumed if missing.
+pub fn printer(addr: &str) -> Result<TcpPrinter, Box<dyn Error>> {
+    if let Ok(ip) = addr.parse::<IpAddr>() {
+        return Ok(TcpPrinter::new(SocketAddr::new(ip, RAW_PORT)));
+    }
+    let resolved = if addr.contains(':') { addr.to_socket_addrs() } else { (addr, RAW_PORT).to_socket_addrs() };
+    let addr = resolved?.next().ok_or_else(|| format!("cannot resolve {}", addr))?;
+    Ok(TcpPrinter::new(addr))
+}
+
+impl TcpPrinter {
+    /// Open and close a connection, sending nothing: proves the print server
+    /// is reachable without printing. Returns how long the connect took.
+    pub fn ping(&self) -> Result<Duration, Box<dyn Error>> {
+        let start = Instant::now();
+        let stream = TcpStream::connect_timeout(&self.addr, self.timeout)
+            .map_err(|e| format!("connect {}: {}", self.addr, e))?;
+        let elapsed = start.elapsed();
+        let _ = stream.shutdown(Shutdown::Both);
+        Ok(elapsed)
+    }
+}
+
+/// `TcpPrinter::ping` for `addr` as accepted by `printer`.
+pub fn ping(addr: &str) -> Result<Duration, Box<dyn Error>> {
+    printer(addr)?.ping()
+}
+
+impl Transport for TcpPrinter {
+    fn send(&mut self, data: &[u8]) -> Result<(), Box<dyn Error>> {
+        let mut stream = TcpStream::connect_timeout(&self.addr, self.timeout)
+            .map_err(|e| format!("connect {}: {}", self.addr, e))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+        write_chunked(&mut stream, data, self.chunking.as_ref())?;
+        let _ = stream.shutdown(Shutdown::Write);
+        Ok(())
+    }
+}
@@ -0,0 +1,74 @@
+//! The original byte-returning API, kept source compatible.
+//!
+//! These functions are thin wrappers over the label engine: they build the same
+//! `Label` as `build_two_product_job`/`build_four_product_job` and drop the
+//! warnings, so fixes to the engine reach v1 callers without code changes.
+//! They still panic if `font_bytes` isn't a usable font.
+//!
+//! # Migrating
+//!
+//! Parse the font once and pass `Product`s; the returned `LabelJob` carries the
+//! same bytes plus layout warnings:
+//!
+//! ```ignore
+//! // v1
+//! let bytes = build_two_product_label_with_brand(FONT, "Brand", "Milk", "12", "622...", "Tea", "30", "622...");
+//!
+//! // engine
+//! let ctx = FontContext::new(FONT)?;
+//! let job = build_two_product_job(&ctx, "Brand", &[
+//!     Product::new("Milk", "12", "622..."),
+//!     Product::new("Tea", "30", "622..."),
+//! ]);
+//! for w in &job.warnings { eprintln!("{}", w); }
+//! transport.send(&job.bytes)?;
+//! ```
+//!
+//! Quantities of several products go through `batch::build_batch`, which packs
+//! them onto 2-up/4-up sheets instead of one call per label.
+
+use crate::{build_four_product_job, build_two_product_job, FontContext, Product};
+
+/// Build a single EPL2 print job for two products (original working implementation).
+/// - `font_bytes`: embedded Arabic font bytes 
+/// - `name1/price1/barcode1` + `name2/price2/barcode2`
+///
+/// Returns raw bytes ready to send to the printer (USB raw write).
+#[allow(clippy::too_many_arguments)]
+pub fn build_two_product_label_with_brand(
+    font_bytes: &[u8],
+    brand: &str,
+    name1: &str, price1: &str, barcode1: &str,
+    name2: &str, price2: &str, barcode2: &str,
+) -> Vec<u8> {
+    let products = [
+        Product::new(name1, price1, barcode1),
+        Product::new(name2, price2, barcode2),
+    ];
+    let ctx = FontContext::new(font_bytes).expect("bad font");
+    build_two_product_job(&ctx, brand, &products).bytes
+}
+
+/// Build a single EPL2 print job for four products in 2x2 grid.
+/// - `font_bytes`: embedded Arabic font bytes 
+/// - Four sets of `name/price/barcode` for each quadrant
+///
+/// Returns raw bytes ready to send to the printer (USB raw write).
+#[allow(clippy::too_many_arguments)]
+pub fn build_four_product_label_with_brand(
+    font_bytes: &[u8],
+    brand: &str,
+    name1: &str, price1: &str, barcode1: &str,
+    name2: &str, price2: &str, barcode2: &str,
+    name3: &str, price3: &str, barcode3: &str,
+    name4: &str, price4: &str, barcode4: &str,
+) -> Vec<u8> {
+    let products = [
+        Product::new(name1, price1, barcode1),
+        Product::new(name2, price2, barcode2),
+        Product::new(name3, price3, barcode3),
+        Product::new(name4, price4, barcode4),
+    ];
+    let ctx = FontContext::new(font_bytes).expect("bad font");
+    build_four_product_job(&ctx, brand, &products).bytes
+}
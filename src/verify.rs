@@ -0,0 +1,130 @@
+use std::error::Error;
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
+
+use crate::barcode::Symbology;
+use crate::epl::{command_lines, with_copies};
+use crate::transport::Transport;
+
+/// A barcode scanner the operator (or a fixed-mount reader) points at the label
+/// that just came out of the printer.
+pub trait ScanSource {
+    /// Next decoded read, or `None` if nothing was scanned within `timeout`.
+    fn scan(&mut self, timeout: Duration) -> Option<String>;
+}
+
+impl<F> ScanSource for F
+where
+    F: FnMut(Duration) -> Option<String>,
+{
+    fn scan(&mut self, timeout: Duration) -> Option<String> {
+        self(timeout)
+    }
+}
+
+/// Reads pushed by another thread (e.g. a serial or HID reader loop).
+impl ScanSource for Receiver<String> {
+    fn scan(&mut self, timeout: Duration) -> Option<String> {
+        self.recv_timeout(timeout).ok()
+    }
+}
+
+/// Outcome for one expected code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanResult {
+    Match { code: String },
+    Mismatch { expected: String, scanned: String },
+    NoRead { expected: String },
+}
+
+/// Report of `Verifier::print_verified`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verification {
+    /// Results of the last attempt, in the order of the job's barcodes.
+    pub results: Vec<ScanResult>,
+    /// Extra single labels printed after a failed check.
+    pub reprints: u32,
+    pub passed: bool,
+}
+
+/// Closed-loop check of printed barcodes: print, compare each scan with the
+/// code the job was meant to carry, reprint one label on mismatch.
+#[derive(Debug, Clone)]
+pub struct Verifier {
+    pub timeout: Duration,
+    pub max_reprints: u32,
+}
+
+impl Default for Verifier {
+    fn default() -> Self {
+        Verifier { timeout: Duration::from_secs(10), max_reprints: 1 }
+    }
+}
+
+impl Verifier {
+    /// Send `job` and verify the printer-drawn (`B`) barcodes of its first label.
+    pub fn print_verified<T, S>(&self, job: &[u8], transport: &mut T, scanner: &mut S) -> Result<Verification, Box<dyn Error>>
+    where
+        T: Transport + ?Sized,
+        S: ScanSource + ?Sized,
+    {
+        self.print_verified_with(job, &expected_codes(job), transport, scanner)
+    }
+
+    /// Like `print_verified` with an explicit list of expected codes (needed for
+    /// barcodes sent as `GW` bitmaps, which can't be read back from the job).
+    pub fn print_verified_with<T, S>(
+        &self,
+        job: &[u8],
+        expected: &[String],
+        transport: &mut T,
+        scanner: &mut S,
+    ) -> Result<Verification, Box<dyn Error>>
+    where
+        T: Transport + ?Sized,
+        S: ScanSource + ?Sized,
+    {
+        transport.send(job)?;
+        let mut reprints = 0;
+        loop {
+            let results: Vec<ScanResult> = expected.iter().map(|e| self.check(e, scanner)).collect();
+            let passed = results.iter().all(|r| matches!(r, ScanResult::Match { .. }));
+            if passed || reprints >= self.max_reprints {
+                return Ok(Verification { results, reprints, passed });
+            }
+            transport.send(&with_copies(job, 1))?;
+            reprints += 1;
+        }
+    }
+
+    fn check<S: ScanSource + ?Sized>(&self, expected: &str, scanner: &mut S) -> ScanResult {
+        match scanner.scan(self.timeout) {
+            Some(read) if normalize_read(&read) == expected => ScanResult::Match { code: expected.to_string() },
+            Some(read) => ScanResult::Mismatch { expected: expected.to_string(), scanned: normalize_read(&read).to_string() },
+            None => ScanResult::NoRead { expected: expected.to_string() },
+        }
+    }
+}
+
+/// Codes a scanner should report for the job's `B` commands, in job order.
+/// EAN/UPC data gets its check digit, as the printer adds it.
+pub fn expected_codes(job: &[u8]) -> Vec<String> {
+    command_lines(job).iter()
+        .filter_map(|l| {
+            let args = l.strip_prefix('B')?;
+            let (params, data) = args.split_once(",\"")?;
+            let symbology = params.split(',').nth(3)?;
+            let data = data.strip_suffix('"')?.to_string();
+            Some(Symbology::from_epl_code(symbology).map_or(data.clone(), |s| s.with_check_digit(&data)))
+        })
+        .collect()
+}
+
+// Trim line endings and an AIM symbology identifier (`]E0`, `]C1`, ...) if the scanner sends one
+fn normalize_read(read: &str) -> &str {
+    let read = read.trim();
+    match read.strip_prefix(']') {
+        Some(rest) if rest.len() >= 2 && rest.is_char_boundary(2) => &rest[2..],
+        _ => read,
+    }
+}
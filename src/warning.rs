@@ -0,0 +1,53 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+/// Non-fatal problem found while building a label. The job is still printable;
+/// callers decide whether to log, show, or ignore these.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Warning {
+    /// Product name didn't fit next to the price; `clipped_dots` of it were cut off.
+    TruncatedName { product: usize, name: String, clipped_dots: u32 },
+    /// Barcode element has less free space than the symbology's quiet zone.
+    TightQuietZone { element: usize, side: Side, available: u32, required: u32 },
+    /// Bitmap element is mostly black (banding, slow printing, head wear).
+    HighDotDensity { element: usize, ratio_percent: u32 },
+    /// Element extends `overflow` dots past the label edge on `side`; the
+    /// printer clips it (see `Label::check_bounds`).
+    OutOfBounds { element: usize, side: Side, overflow: u32 },
+    /// Product's barcode data didn't suit the expected symbology; `note` says
+    /// what was printed instead (see `BuildOptions::auto_barcodes`).
+    BarcodeFallback { product: usize, note: String },
+    /// Product's brand, text and barcode need `overflow` dots more than its
+    /// cell has (see `layout::Flow`), or its barcode is `overflow` dots wider
+    /// than its column; they run into the next cell or the label edge.
+    CellOverflow { product: usize, overflow: u32 },
+    /// Label is mirrored (`Label::mirror`) but the printer can't flip this
+    /// element; `note` says what prints unmirrored or is left out.
+    NotMirrored { element: usize, note: &'static str },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::TruncatedName { product, name, clipped_dots } =>
+                write!(f, "product {}: name \"{}\" truncated by {} dots", product + 1, name, clipped_dots),
+            Warning::TightQuietZone { element, side, available, required } =>
+                write!(f, "element {}: {:?} quiet zone is {} dots, needs {}", element, side, available, required),
+            Warning::HighDotDensity { element, ratio_percent } =>
+                write!(f, "element {}: {}% of dots are black", element, ratio_percent),
+            Warning::OutOfBounds { element, side, overflow } =>
+                write!(f, "element {}: {} dots past the {} edge of the label", element, overflow, format!("{:?}", side).to_lowercase()),
+            Warning::BarcodeFallback { product, note } => write!(f, "product {}: {}", product + 1, note),
+            Warning::CellOverflow { product, overflow } =>
+                write!(f, "product {}: content runs {} dots past its cell", product + 1, overflow),
+            Warning::NotMirrored { element, note } => write!(f, "element {}: {}", element, note),
+        }
+    }
+}
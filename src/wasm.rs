@@ -0,0 +1,103 @@
+//! wasm-bindgen wrapper (feature `wasm`) for browser-side label design:
+//! build a job from the page's data and get the EPL bytes (for WebUSB) plus a
+//! PNG preview. Only the rendering path is used here, no file or network IO.
+//!
+//! ```text
+//! wasm-pack build --target web --features wasm
+//! ```
+
+use std::io::Cursor;
+
+use image::ImageFormat;
+use wasm_bindgen::prelude::*;
+
+use crate::font::FontContext;
+use crate::preview::render_preview;
+use crate::product::Product;
+
+/// A built job and its preview.
+#[wasm_bindgen]
+pub struct RenderedLabel {
+    epl: Vec<u8>,
+    png: Vec<u8>,
+    warnings: Vec<String>,
+}
+
+#[wasm_bindgen]
+impl RenderedLabel {
+    /// Raw EPL2 job, ready for `USBDevice.transferOut`.
+    #[wasm_bindgen(getter)]
+    pub fn epl(&self) -> Vec<u8> {
+        self.epl.clone()
+    }
+
+    /// PNG of the first label form.
+    #[wasm_bindgen(getter)]
+    pub fn png(&self) -> Vec<u8> {
+        self.png.clone()
+    }
+
+    /// Layout warnings (truncated names, tight quiet zones, ...), human readable.
+    #[wasm_bindgen(getter)]
+    pub fn warnings(&self) -> Vec<String> {
+        self.warnings.clone()
+    }
+}
+
+/// Two products stacked vertically; `names`, `prices` and `barcodes` hold 2 entries each.
+#[wasm_bindgen(js_name = buildTwoProductLabel)]
+pub fn build_two_product_label(
+    font: &[u8],
+    brand: &str,
+    names: Vec<String>,
+    prices: Vec<String>,
+    barcodes: Vec<String>,
+) -> Result<RenderedLabel, JsError> {
+    let ctx = FontContext::new(font).map_err(|e| JsError::new(&e))?;
+    let products: [Product; 2] = products(names, prices, barcodes)?;
+    rendered(crate::build_two_product_job(&ctx, brand, &products))
+}
+
+/// Four products in a 2x2 grid; 4 entries per array.
+#[wasm_bindgen(js_name = buildFourProductLabel)]
+pub fn build_four_product_label(
+    font: &[u8],
+    brand: &str,
+    names: Vec<String>,
+    prices: Vec<String>,
+    barcodes: Vec<String>,
+) -> Result<RenderedLabel, JsError> {
+    let ctx = FontContext::new(font).map_err(|e| JsError::new(&e))?;
+    let products: [Product; 4] = products(names, prices, barcodes)?;
+    rendered(crate::build_four_product_job(&ctx, brand, &products))
+}
+
+/// PNG preview of any EPL2 job (e.g. one edited by hand).
+#[wasm_bindgen(js_name = previewPng)]
+pub fn preview_png(job: &[u8]) -> Result<Vec<u8>, JsError> {
+    png(job)
+}
+
+fn products<const N: usize>(names: Vec<String>, prices: Vec<String>, barcodes: Vec<String>) -> Result<[Product; N], JsError> {
+    if names.len() != N || prices.len() != N || barcodes.len() != N {
+        return Err(JsError::new(&format!("expected {} names, prices and barcodes", N)));
+    }
+    let mut cells = names.iter().zip(&prices).zip(&barcodes).map(|((n, p), b)| Product::new(n, p, b));
+    Ok(std::array::from_fn(|_| cells.next().unwrap_or_default()))
+}
+
+fn rendered(job: crate::LabelJob) -> Result<RenderedLabel, JsError> {
+    Ok(RenderedLabel {
+        png: png(&job.bytes)?,
+        warnings: job.warnings.iter().map(|w| w.to_string()).collect(),
+        epl: job.bytes,
+    })
+}
+
+fn png(job: &[u8]) -> Result<Vec<u8>, JsError> {
+    let mut out = Vec::new();
+    render_preview(job)
+        .write_to(&mut Cursor::new(&mut out), ImageFormat::Png)
+        .map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(out)
+}
@@ -0,0 +1,186 @@
+use zebra_epl2_printer::barcode::{normalize_ean13, normalize_ean8, normalize_upca};
+use zebra_epl2_printer::Symbology;
+
+type Normalize = fn(String) -> Result<String, String>;
+
+// GS1 check: digits weighted 3, 1, 3, ... from the right (check digit
+// weight 1) sum to a multiple of 10
+fn gs1_valid(code: &str) -> bool {
+    let sum: u32 = code.chars().rev().enumerate()
+        .map(|(i, c)| c.to_digit(10).unwrap() * if i % 2 == 1 { 3 } else { 1 })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+fn digits(seed: &mut u64, n: usize) -> String {
+    (0..n).map(|_| {
+        *seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        char::from(b'0' + ((*seed >> 33) % 10) as u8)
+    }).collect()
+}
+
+#[test]
+fn check_digits_follow_gs1() {
+    let mut seed = 11;
+    let cases: [(usize, Symbology, Normalize); 3] = [
+        (12, Symbology::Ean13, normalize_ean13),
+        (7, Symbology::Ean8, normalize_ean8),
+        (11, Symbology::UpcA, normalize_upca),
+    ];
+    for (len, symbology, normalize) in cases {
+        for _ in 0..2000 {
+            let data = digits(&mut seed, len);
+            let full = normalize(data.clone()).unwrap();
+            assert_eq!(full.len(), len + 1);
+            assert!(full.starts_with(&data));
+            assert!(gs1_valid(&full), "{:?} {}", symbology, full);
+            assert_eq!(symbology.with_check_digit(&data), full);
+            // Already complete data validates and comes back unchanged
+            assert_eq!(normalize(full.clone()).unwrap(), full);
+            assert_eq!(symbology.with_check_digit(&full), full);
+        }
+    }
+}
+
+#[test]
+fn a_single_wrong_digit_is_rejected() {
+    let mut seed = 5;
+    for _ in 0..500 {
+        let full = normalize_ean13(digits(&mut seed, 12)).unwrap();
+        for i in 0..13 {
+            let mut bad: Vec<u8> = full.bytes().collect();
+            bad[i] = b'0' + (bad[i] - b'0' + 1) % 10;
+            let bad = String::from_utf8(bad).unwrap();
+            assert!(normalize_ean13(bad.clone()).is_err(), "{}", bad);
+            assert_eq!(Symbology::Ean13.with_check_digit(&bad), bad);
+        }
+    }
+}
+
+#[cfg(feature = "qr")]
+#[test]
+fn qr_renders_square_modules_with_finder_patterns() {
+    use zebra_epl2_printer::barcode_bitmap::{render_barcode, BitmapBarcodeOptions};
+    use zebra_epl2_printer::QrRenderer;
+
+    let opts = BitmapBarcodeOptions { module: 3, hri: false, ..Default::default() };
+    let image = render_barcode(&QrRenderer::default(), "https://example.com/p/42", &opts, None).unwrap();
+    assert_eq!(image.width(), image.height());
+    assert_eq!(image.width() % 3, 0);
+    // Top-left finder pattern: dark corner module
+    assert!(image.get_pixel(0, 0).0[0] < 128);
+}
+
+#[test]
+fn price_embedded_codes_round_trip() {
+    use zebra_epl2_printer::barcode::{ean13_price_embedded_with, parse_price_embedded, PriceEmbedding};
+
+    let layouts = [
+        PriceEmbedding::default(),
+        PriceEmbedding { prefix: "20".into(), price_digits: 4, price_check: true },
+        PriceEmbedding { prefix: "29".into(), price_digits: 5, price_check: true },
+    ];
+    for layout in &layouts {
+        for (item, price) in [("1", 0), ("4711", 1999), ("9999", 9999)] {
+            let code = ean13_price_embedded_with(item, price, layout).unwrap();
+            assert!(code.len() == 13 && gs1_valid(&code) && code.starts_with(&layout.prefix), "{}", code);
+            let padded = format!("{:0>w$}", item, w = layout.item_digits());
+            assert_eq!(parse_price_embedded(&code, layout), Ok((padded, price)), "{}", code);
+        }
+    }
+}
+
+#[test]
+fn price_check_digit_is_embedded_and_verified() {
+    use zebra_epl2_printer::barcode::{ean13_price_embedded_with, parse_price_embedded, PriceEmbedding};
+
+    // GS1 General Specifications 7.9.3: price 2875 has verifier digit 9
+    let layout = PriceEmbedding { prefix: "20".into(), price_digits: 4, price_check: true };
+    let code = ean13_price_embedded_with("123", 2875, &layout).unwrap();
+    assert_eq!(&code[..12], "200012392875");
+
+    // Another verifier digit under a valid EAN-13 check digit is still rejected
+    let tampered = normalize_ean13("200012382875".into()).unwrap();
+    assert_eq!(parse_price_embedded(&tampered, &layout), Err("invalid price check digit".into()));
+    assert!(ean13_price_embedded_with("123", 10_000, &layout).is_err());
+}
+
+#[test]
+fn itf14_gets_or_checks_its_check_digit() {
+    use zebra_epl2_printer::barcode::normalize_itf14;
+
+    assert_eq!(normalize_itf14("1540014128876".into()), Ok("15400141288763".into()));
+    assert_eq!(normalize_itf14("1 54 00141 28876 3".into()), Ok("15400141288763".into()));
+    assert!(normalize_itf14("15400141288764".into()).is_err());
+    assert!(normalize_itf14("154001412887".into()).is_err());
+}
+
+#[test]
+fn itf14_bars_sit_inside_the_bearer_frame_behind_quiet_zones() {
+    use zebra_epl2_printer::label::{Element, Itf14, Label};
+
+    let itf = Itf14::new(10, 20, "1540014128876").unwrap();
+    let mut label = Label::new(832, 400);
+    itf.add_to(&mut label);
+    let (w, h) = itf.size();
+    let quiet = Symbology::Itf14.quiet_zone().0 * itf.narrow;
+
+    let Element::Frame { x, y, x1, y1, thickness } = label.elements[0] else { panic!("{:?}", label.elements[0]) };
+    assert_eq!((x, y, x1, thickness), (10, 20, 10 + w, itf.bearer));
+    assert_eq!(y1 - y, itf.height + 2 * itf.bearer);
+
+    let Element::Barcode(bars) = &label.elements[1] else { panic!("{:?}", label.elements[1]) };
+    assert_eq!(bars.x - x, thickness + quiet);
+    assert_eq!(x1 - (bars.x + bars.width()), thickness + quiet);
+    assert_eq!((bars.y, bars.height), (y + thickness, itf.height));
+
+    // HRI below the frame, within the outer size
+    let Element::Text(hri) = &label.elements[2] else { panic!("{:?}", label.elements[2]) };
+    assert_eq!(hri.data, "15400141288763");
+    assert!(hri.y > y1 && hri.y + hri.size().1 <= y + h);
+}
+
+#[test]
+fn code39_mod43_check_character() {
+    use zebra_epl2_printer::barcode::{code39_mod43, normalize_code39, verify_code39_mod43};
+
+    assert_eq!(code39_mod43("CODE39"), Ok('W'));
+    assert_eq!(code39_mod43(""), Ok('0'));
+    // Z, -, . are 35, 36, 37: 108 mod 43 = 22
+    assert_eq!(code39_mod43("Z-."), Ok('M'));
+    assert!(code39_mod43("code39").is_err());
+
+    assert_eq!(normalize_code39("CODE39", true), Ok("CODE39W".into()));
+    assert_eq!(normalize_code39("CODE39", false), Ok("CODE39".into()));
+    assert!(verify_code39_mod43("CODE39W").is_ok());
+    assert!(verify_code39_mod43("CODE39X").is_err());
+    assert!(verify_code39_mod43("").is_err());
+}
+
+#[test]
+fn auto_picks_the_symbology_from_the_data() {
+    use zebra_epl2_printer::Barcode;
+
+    let cases = [
+        ("96385074", Symbology::Ean8, "9638507"),
+        ("9638507", Symbology::Ean8, "9638507"),
+        ("9638-5074", Symbology::Ean8, "9638507"),
+        ("036000291452", Symbology::UpcA, "03600029145"),
+        ("03600029145", Symbology::UpcA, "03600029145"),
+        // 12 digits without a valid UPC check digit are an EAN-13 body
+        ("400638133393", Symbology::Ean13, "400638133393"),
+        ("4006381333931", Symbology::Ean13, "400638133393"),
+        ("12345", Symbology::Code128, "12345"),
+        ("SKU-42a", Symbology::Code128, "SKU-42a"),
+    ];
+    for (input, symbology, data) in cases {
+        let code = Barcode::auto(input).unwrap();
+        assert_eq!((code.symbology, code.data.as_str(), code.note.is_some()), (symbology, data, false), "{}", input);
+    }
+    assert_eq!(Barcode::auto("4006381333931").unwrap().scanned(), "4006381333931");
+
+    // A wrong check digit scans as given, with a note saying why
+    let code = Barcode::auto("4006381333932").unwrap();
+    assert_eq!((code.symbology, code.data.as_str()), (Symbology::Code128, "4006381333932"));
+    assert!(code.note.unwrap().contains("EAN-13"));
+}
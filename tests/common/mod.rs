@@ -0,0 +1,34 @@
+#![allow(dead_code)]
+
+use zebra_epl2_printer::{FontContext, Product};
+
+/// Fonts the label tests are pinned to; the repo ships none (see `src/font`).
+const FONTS: &[&str] = &[
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/TTF/DejaVuSans.ttf",
+];
+
+/// DejaVu Sans, or `None` (test skipped) on machines without it.
+pub fn font() -> Option<Vec<u8>> {
+    let found = FONTS.iter().find_map(|p| std::fs::read(p).ok());
+    if found.is_none() {
+        eprintln!("DejaVuSans.ttf not found, skipping");
+    }
+    found
+}
+
+pub fn with_font(test: impl FnOnce(&FontContext)) {
+    if let Some(bytes) = font() {
+        test(&FontContext::new(&bytes).unwrap());
+    }
+}
+
+pub fn products<const N: usize>() -> [Product; N] {
+    let all = [
+        Product::new("جبنة رومي", "45.50", "6221234567890"),
+        Product::new("لبن", "12", "123"),
+        Product::new("عصير برتقال طازج كبير", "8.25", "62212345"),
+        Product::new("Milk 1L", "9", "5901234123457"),
+    ];
+    std::array::from_fn(|i| all[i].clone())
+}
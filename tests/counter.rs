@@ -0,0 +1,18 @@
+use zebra_epl2_printer::SerialCounter;
+
+#[test]
+fn counter_formats_and_steps() {
+    let numbers: Vec<String> = SerialCounter::new(8).step(2).pad(3).prefix("SN-").take(3).collect();
+    assert_eq!(numbers, ["SN-008", "SN-010", "SN-012"]);
+}
+
+#[test]
+fn counter_stops_instead_of_overflowing() {
+    let numbers: Vec<String> = SerialCounter::new(i64::MAX - 1).collect();
+    assert_eq!(numbers, [(i64::MAX - 1).to_string(), i64::MAX.to_string()]);
+
+    let mut down = SerialCounter::new(i64::MIN + 3).step(-2);
+    assert_eq!(down.by_ref().count(), 2);
+    assert_eq!(down.next(), None);
+    assert_eq!(down.value, None);
+}
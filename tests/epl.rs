@@ -0,0 +1,70 @@
+use zebra_epl2_printer::epl::{parse, EplCommand};
+use zebra_epl2_printer::Symbology;
+
+// Small deterministic generator; the properties below run over a few
+// thousand cases without pulling in a property testing crate.
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self, n: u32) -> u32 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        ((self.0 >> 33) % n as u64) as u32
+    }
+
+    fn text(&mut self) -> String {
+        let chars = ['a', 'Z', '0', ' ', ',', '"', '\\', 'ل', 'ب', '-'];
+        (0..self.next(12)).map(|_| chars[self.next(chars.len() as u32) as usize]).collect()
+    }
+}
+
+fn command(rng: &mut Lcg) -> EplCommand {
+    let (x, y) = (rng.next(832), rng.next(2000));
+    match rng.next(5) {
+        0 => EplCommand::Line { x, y, width: rng.next(800), height: rng.next(400) },
+        1 => EplCommand::Frame { x, y, thickness: rng.next(10), x1: x + rng.next(400), y1: y + rng.next(400) },
+        2 => EplCommand::Text {
+            x, y,
+            rotation: rng.next(4),
+            font: (1 + rng.next(5)).to_string(),
+            h_mult: 1 + rng.next(8),
+            v_mult: 1 + rng.next(9),
+            reverse: rng.next(2) == 1,
+            data: rng.text(),
+        },
+        3 => {
+            let code = ["1", "E30", "E80", "UA0", "3"][rng.next(5) as usize];
+            EplCommand::Barcode {
+                x, y,
+                rotation: 0,
+                code: code.to_string(),
+                symbology: Symbology::from_epl_code(code),
+                narrow: 1 + rng.next(4),
+                wide: 2 + rng.next(8),
+                height: 1 + rng.next(200),
+                hri: rng.next(2) == 1,
+                data: rng.text(),
+            }
+        }
+        _ => EplCommand::Print { copies: 1 + rng.next(99) },
+    }
+}
+
+#[test]
+fn display_and_parse_round_trip() {
+    let mut rng = Lcg(7);
+    for _ in 0..5000 {
+        let cmd = command(&mut rng);
+        let line = format!("{}\r\n", cmd);
+        assert_eq!(parse(line.as_bytes()), vec![cmd.clone()], "{}", line);
+    }
+}
+
+#[test]
+fn parse_keeps_every_line_of_a_job() {
+    let job = b"N\r\nq832\r\nQ406,24\r\nLO1,2,3,4\r\nxyz\r\nP1\r\n";
+    let cmds = parse(job);
+    assert_eq!(cmds.len(), 6);
+    assert_eq!(cmds[4], EplCommand::Other("xyz".into()));
+    let redisplayed: String = cmds.iter().map(|c| format!("{}\r\n", c)).collect();
+    assert_eq!(redisplayed.as_bytes(), job);
+}
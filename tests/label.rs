@@ -0,0 +1,292 @@
+mod common;
+
+use common::{products, with_font};
+use zebra_epl2_printer::label::{BarcodeCmd, Element, Label, TextCmd};
+use zebra_epl2_printer::warning::Side;
+use zebra_epl2_printer::*;
+
+#[test]
+fn content_moved_onto_the_label_is_reported_left_and_top() {
+    let mut label = Label::new(400, 200);
+    label.graphic_at(-5, -3, image::GrayImage::new(10, 10));
+    assert_eq!(label.overflows(), [(0, Side::Left, 5), (0, Side::Top, 3)]);
+    let err = label.check_bounds().unwrap_err();
+    assert!(err.contains("5 dots past the left edge"), "{}", err);
+}
+
+#[test]
+fn barcode_wider_than_its_cell_overflows_left() {
+    with_font(|ctx| {
+        let mut items = products::<4>();
+        items[0].barcode = "ABCDEFGHIJKLMNOPQRSTUVWXYZ0123".into();
+        let opts = BuildOptions { auto_barcodes: true, ..BuildOptions::default() };
+        let job = build_four_product_job_with(ctx, "ماركت", &items, &opts);
+        assert!(job.warnings.iter().any(|w| matches!(w, Warning::OutOfBounds { side: Side::Left, .. })), "{:?}", job.warnings);
+        assert!(try_build_four_product_job(ctx, "ماركت", &items, &opts).is_err());
+    });
+}
+
+#[test]
+fn batches_use_the_build_options() {
+    use zebra_epl2_printer::batch::{build_batch, BatchItem, Layout};
+
+    with_font(|ctx| {
+        let items: Vec<BatchItem> = products::<4>().into_iter().map(|p| BatchItem::new(p, 3)).collect();
+        let opts = BuildOptions { darkness: None, direction: Direction::Rtl, ..BuildOptions::default() };
+        let jobs = build_batch(ctx, "ماركت", &items, Layout::FourUp, &opts);
+        assert!(!jobs.is_empty());
+        for job in &jobs {
+            assert!(!String::from_utf8_lossy(&job.bytes).lines().any(|l| l.starts_with('D')));
+        }
+        #[cfg(feature = "rayon")]
+        {
+            let parallel = zebra_epl2_printer::batch::build_parallel(ctx, "ماركت", &items, Layout::FourUp, &opts);
+            assert_eq!(parallel.iter().map(|j| &j.bytes).collect::<Vec<_>>(), jobs.iter().map(|j| &j.bytes).collect::<Vec<_>>());
+        }
+    });
+}
+
+#[test]
+fn every_symbology_draws_as_wide_as_the_printer_prints_it() {
+    use zebra_epl2_printer::barcode::{Barcode, Symbology};
+
+    let codes = [
+        (Symbology::Ean13, "400638133393"),
+        (Symbology::Ean8, "9638507"),
+        (Symbology::UpcA, "03600029145"),
+        (Symbology::Code128, "AB-12345678x"),
+        (Symbology::Code93, "CODE 93"),
+        (Symbology::Code39 { check: false }, "CODE-39"),
+        (Symbology::Code39 { check: true }, "CODE-39"),
+        (Symbology::Itf14, "15400141288763"),
+    ];
+    for (symbology, data) in codes {
+        for narrow in [2, 3] {
+            let cmd = BarcodeCmd::from_barcode(0, 0, narrow, 40, &Barcode { symbology, data: data.into(), note: None });
+            let bars = cmd.render_bars().unwrap_or_else(|| panic!("{:?} {}", symbology, data));
+            assert_eq!(bars.width(), cmd.width(), "{:?} at {}", symbology, narrow);
+        }
+    }
+}
+
+#[test]
+fn mirrored_labels_reject_what_they_cannot_flip() {
+    let mut label = Label::new(400, 200);
+    label.mirror = true;
+    label.elements.push(Element::Text(TextCmd::new(10, 10, 2, "LOT 42")));
+    label.barcode(BarcodeCmd { hri: Hri::Rendered, ..BarcodeCmd::ean13(10, 60, 2, 50, "400638133393") });
+    let err = label.check_mirror().unwrap_err();
+    assert!(err.contains("element 0 (text)") && !err.contains("element 1"), "{}", err);
+
+    with_font(|ctx| label.render_text(ctx));
+    assert!(label.check_mirror().is_ok());
+}
+
+#[test]
+fn mirrored_stamps_are_flipped() {
+    with_font(|ctx| {
+        let stamped = |mirror| {
+            let mut label = Label::new(400, 200);
+            label.mirror = mirror;
+            label.stamp(0, 0, 200, 40, 24.0, "lot");
+            Stamper::new(ctx).value("lot", "LOT 42").apply(&label.to_epl())
+        };
+        let gw = |job: Vec<u8>| job.split(|&b| b == b'\n').find(|l| l.starts_with(b"GW")).map(<[u8]>::to_vec).unwrap();
+        let (plain, mirrored) = (gw(stamped(false)), gw(stamped(true)));
+        assert_ne!(plain, mirrored);
+    });
+}
+
+#[test]
+fn mirrored_builds_pass_the_mirror_check() {
+    with_font(|ctx| {
+        let opts = BuildOptions { mirror: true, ..BuildOptions::default() };
+        assert!(try_build_two_product_job(ctx, "ماركت", &products(), &opts).is_ok());
+        assert!(try_build_four_product_job(ctx, "ماركت", &products(), &opts).is_ok());
+    });
+}
+
+#[test]
+fn plan_prints_full_sheets_as_copies_and_packs_the_rest() {
+    use zebra_epl2_printer::batch::{plan, BatchItem, Layout, Sheet};
+
+    let items: Vec<BatchItem> = products::<3>().into_iter().zip([9, 2, 1]).map(|(p, q)| BatchItem::new(p, q)).collect();
+    assert_eq!(plan(&items, Layout::FourUp), [
+        Sheet { cells: vec![Some(0); 4], copies: 2 },
+        Sheet { cells: vec![Some(0), Some(1), Some(1), Some(2)], copies: 1 },
+    ]);
+
+    // Only the last sheet has blanks: 5 + 1 stickers waste 2 cells of 2 labels
+    let items: Vec<BatchItem> = products::<2>().into_iter().zip([5, 1]).map(|(p, q)| BatchItem::new(p, q)).collect();
+    let sheets = plan(&items, Layout::FourUp);
+    assert_eq!(sheets.last().unwrap().cells, [Some(0), Some(1), None, None]);
+    let labels: u32 = sheets.iter().map(|s| s.copies).sum();
+    let blanks: u32 = sheets.iter().map(|s| s.copies * s.cells.iter().filter(|c| c.is_none()).count() as u32).sum();
+    assert_eq!((labels, blanks), (2, 2));
+    assert_eq!(plan(&items, Layout::TwoUp).iter().map(|s| s.copies).sum::<u32>(), 3);
+}
+
+#[test]
+fn optimize_reports_waste_against_naive_ordering() {
+    use zebra_epl2_printer::batch::{optimize, BatchItem, Layout};
+
+    let batch = |qty: &[u32]| -> Vec<BatchItem> { qty.iter().map(|&q| BatchItem::new(Product::default(), q)).collect() };
+
+    // Remainders 3+1 and 3+1 fill two sheets; one by one needs four
+    let report = optimize(&batch(&[3, 3, 1, 1]), Layout::FourUp);
+    assert_eq!((report.labels, report.wasted_cells), (2, 0));
+    assert_eq!((report.naive_labels, report.naive_wasted_cells), (4, 8));
+    for sheet in &report.sheets {
+        assert!(sheet.cells.iter().all(Option::is_some));
+    }
+
+    // 3 + 3 can't pair up: the spill leaves the blanks on the last sheet only
+    let report = optimize(&batch(&[3, 3]), Layout::FourUp);
+    assert_eq!((report.labels, report.wasted_cells), (2, 2));
+    assert_eq!((report.naive_labels, report.naive_wasted_cells), (2, 2));
+    assert_eq!(report.sheets[1].cells, [Some(1), Some(1), None, None]);
+
+    // Whole sheets are copies of one form
+    let report = optimize(&batch(&[8, 2]), Layout::TwoUp);
+    assert_eq!(report.sheets[0].copies, 4);
+    assert_eq!((report.labels, report.wasted_cells), (5, 0));
+}
+
+#[test]
+fn gw_to_lines_redraws_bitmaps_as_merged_runs() {
+    use zebra_epl2_printer::epl::{gw_bytes, gw_to_lines, image_to_row_bytes};
+
+    // 4x3 block at (2, 0) and a 2x1 run at (10, 1) on a 16x4 bitmap
+    let img = image::GrayImage::from_fn(16, 4, |x, y| {
+        let black = ((2..6).contains(&x) && y < 3) || ((10..12).contains(&x) && y == 1);
+        image::Luma([if black { 0 } else { 255 }])
+    });
+    let (w, h, rows) = image_to_row_bytes(&img);
+    let mut job = b"N\r\n".to_vec();
+    gw_bytes(&mut job, 100, 50, w, h, &rows);
+    job.extend_from_slice(b"P1\r\n");
+    let lines = gw_to_lines(&job);
+    assert_eq!(String::from_utf8(lines).unwrap(), "N\r\nLO110,51,2,1\r\nLO102,50,4,3\r\nP1\r\n");
+
+    // Jobs without bitmaps pass through unchanged
+    assert_eq!(gw_to_lines(b"N\r\nLO1,2,3,4\r\nP1\r\n"), b"N\r\nLO1,2,3,4\r\nP1\r\n");
+}
+
+#[test]
+fn parse_finds_the_barcodes_and_bitmaps_of_a_two_up_job() {
+    use zebra_epl2_printer::epl::{parse, EplCommand};
+
+    with_font(|ctx| {
+        let items = products::<2>();
+        let job = build_two_product_job(ctx, "ماركت", &items);
+        let cmds = parse(&job.bytes);
+
+        let barcodes: Vec<_> = cmds.iter().filter_map(|c| match c {
+            EplCommand::Barcode { symbology, data, .. } => Some((*symbology, data.clone())),
+            _ => None,
+        }).collect();
+        assert_eq!(barcodes.len(), 2);
+        for ((symbology, data), product) in barcodes.iter().zip(&items) {
+            assert_eq!(*symbology, Some(Symbology::Ean13));
+            assert_eq!(*data, zebra_epl2_printer::barcode::ensure_valid_ean13(&product.barcode));
+        }
+
+        let graphics: Vec<_> = cmds.iter().filter(|c| matches!(c, EplCommand::Graphic { .. })).collect();
+        assert_eq!(graphics.len(), 4);
+        for g in graphics {
+            let EplCommand::Graphic { bytes_per_row, rows, payload_len, .. } = g else { unreachable!() };
+            assert_eq!(*payload_len, (bytes_per_row * rows) as usize);
+        }
+        assert_eq!(cmds.first(), Some(&EplCommand::Clear));
+        assert_eq!(cmds.last(), Some(&EplCommand::Print { copies: 1 }));
+
+        // Every command but the bitmaps displays as the line it came from
+        let lines: Vec<String> = cmds.iter().filter(|c| !matches!(c, EplCommand::Graphic { .. })).map(|c| c.to_string()).collect();
+        let text = String::from_utf8_lossy(&job.bytes);
+        for line in &lines {
+            assert!(text.contains(&format!("{}\r\n", line)), "{}", line);
+        }
+    });
+}
+
+#[test]
+fn trailing_spaces_count_towards_the_width() {
+    with_font(|ctx| {
+        let (w, _) = ctx.measure("12.50", 30.0);
+        let (w1, _) = ctx.measure("12.50 ", 30.0);
+        let (w2, _) = ctx.measure("12.50  ", 30.0);
+        let space = w2 - w1;
+        assert!(space > 0 && w1 > w, "{} {} {}", w, w1, w2);
+        assert!((w1 - w).abs_diff(space) <= 1, "{} {} {}", w, w1, w2);
+
+        for text in ["12.50", "12.50  ", " ج.م ", "سعر"] {
+            assert_eq!(ctx.render_line(text, 30.0, 0).width(), ctx.measure(text, 30.0).0, "{:?}", text);
+        }
+        assert_eq!(ctx.render_line("12.50 ", 30.0, 3).width(), w1 + 6);
+    });
+}
+
+#[test]
+fn estimate_and_job_limit_split_at_forms() {
+    let form = |x: u32| {
+        let mut label = Label::new(400, 200);
+        label.graphic(x, 10, image::GrayImage::new(64, 20));
+        label.to_epl()
+    };
+    let forms = [form(1), form(2), form(3)];
+    let job = forms.concat();
+
+    let stats = estimate(&job);
+    assert_eq!((stats.bytes, stats.forms, stats.largest_form), (job.len(), 3, forms[0].len()));
+    assert_eq!(stats.gw_bytes, 3 * 8 * 20);
+    assert_eq!(stats.commands, zebra_epl2_printer::epl::parse(&job).len());
+
+    let limit = JobLimit::new(2 * forms[0].len());
+    assert!(limit.check(&job).is_err());
+    assert_eq!(limit.check(&forms[0]), Ok(vec![forms[0].clone()]));
+    assert_eq!(limit.split().check(&job), Ok(vec![[forms[0].clone(), forms[1].clone()].concat(), forms[2].clone()]));
+
+    // A single form over the limit can't be split
+    assert!(JobLimit::new(forms[0].len() - 1).split().check(&job).is_err());
+}
+
+#[test]
+fn outline_keeps_only_the_edges_of_the_filled_glyphs() {
+    use zebra_epl2_printer::font::TextStyle;
+
+    with_font(|ctx| {
+        let black = |img: &image::GrayImage| img.pixels().filter(|p| p.0[0] < 128).count();
+        let filled = ctx.render_line_styled("عرض خاص", 80.0, 0, &TextStyle::default());
+        let thin = ctx.render_line_styled("عرض خاص", 80.0, 0, &TextStyle::outline(1));
+        let thick = ctx.render_line_styled("عرض خاص", 80.0, 0, &TextStyle::outline(3));
+        assert_eq!(filled.dimensions(), thin.dimensions());
+
+        // Outline dots are a subset of the filled ones, fewer the thinner the stroke
+        for img in [&thin, &thick] {
+            assert!(img.enumerate_pixels().all(|(x, y, p)| p.0[0] >= 128 || filled.get_pixel(x, y).0[0] < 128));
+        }
+        assert!(0 < black(&thin) && black(&thin) < black(&thick) && black(&thick) < black(&filled));
+
+        // A stroke wider than any glyph is the glyph itself
+        assert_eq!(ctx.render_line_styled("عرض خاص", 80.0, 0, &TextStyle::outline(40)), filled);
+    });
+}
+
+#[test]
+fn flow_place_errors_when_blocks_run_past_the_limit() {
+    use zebra_epl2_printer::layout::{Block, Cell, Flow};
+
+    let cell = Cell::new(0, 10, 100, 60);
+    let flow = Flow::new(cell).then(Block::new(40, 30), 0).then(Block::new(40, 20), 5);
+    assert_eq!(flow.place(), Ok(vec![(30, 10), (30, 45)]));
+
+    // 10 + 30 + 5 + 20 = 65 fits under the cell bottom (70) but not under 60
+    let err = flow.clone().limit(60).place().unwrap_err();
+    assert_eq!(err, "2 blocks run 5 dots past y = 60");
+
+    // A bigger block pushes the ones under it down rather than overlapping them
+    let flow = Flow::new(cell).then(Block::new(40, 50), 0).then(Block::new(40, 20), -2);
+    assert_eq!(flow.overflow(), 8);
+    assert_eq!(flow.positions()[1], (30, 58));
+    assert!(flow.place().is_err());
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use common::{products, with_font};
+use zebra_epl2_printer::*;
+
+#[test]
+fn four_up_with_large_margins_reports_narrow_columns() {
+    with_font(|ctx| {
+        let grid = GridStyle { gap_x: 8, gap_y: 4, outer_margin: 30, draw_separators: true };
+        let job = build_four_product_job_with_grid(ctx, "ماركت", &products(), &grid);
+        assert!(job.warnings.iter().any(|w| matches!(w, Warning::CellOverflow { .. })));
+
+        let opts = BuildOptions { grid, ..BuildOptions::default() };
+        let err = try_build_four_product_job(ctx, "ماركت", &products(), &opts).unwrap_err();
+        assert!(err.contains("wider than its"), "{}", err);
+    });
+}
+
+#[test]
+fn four_up_survives_margins_wider_than_the_label() {
+    with_font(|ctx| {
+        let grid = GridStyle { outer_margin: 400, ..GridStyle::default() };
+        let job = build_four_product_job_with_grid(ctx, "ماركت", &products(), &grid);
+        assert!(!job.bytes.is_empty());
+    });
+}
+
+#[test]
+fn default_layouts_meet_barcode_quiet_zones() {
+    with_font(|ctx| {
+        let two = build_two_product_job(ctx, "ماركت", &products());
+        let four = build_four_product_job(ctx, "ماركت", &products());
+        for w in two.warnings.iter().chain(&four.warnings) {
+            assert!(!matches!(w, Warning::TightQuietZone { .. }), "{}", w);
+        }
+    });
+}
+
+#[test]
+fn mirror_option_flips_everything_on_the_job() {
+    with_font(|ctx| {
+        let plain = build_two_product_job(ctx, "ماركت", &products());
+        let opts = BuildOptions { mirror: true, ..BuildOptions::default() };
+        let mirrored = build_two_product_job_with(ctx, "ماركت", &products(), &opts);
+        assert_ne!(plain.bytes, mirrored.bytes);
+        // Barcodes, their digits and text all become flipped bitmaps
+        assert!(!String::from_utf8_lossy(&mirrored.bytes).lines().any(|l| l.starts_with('B') || l.starts_with('A')));
+        assert!(!mirrored.warnings.iter().any(|w| matches!(w, Warning::NotMirrored { .. })));
+        assert!(!plain.warnings.iter().any(|w| matches!(w, Warning::NotMirrored { .. })));
+    });
+}
+
+#[test]
+fn brand_spacing_and_justification_are_build_options() {
+    with_font(|ctx| {
+        let plain = build_two_product_job(ctx, "BRAND", &products());
+        let opts = BuildOptions { brand_letter_spacing: 4.0, ..BuildOptions::default() };
+        assert_ne!(build_two_product_job_with(ctx, "BRAND", &products(), &opts).bytes, plain.bytes);
+        let opts = BuildOptions { justify_names: true, ..BuildOptions::default() };
+        assert_ne!(build_two_product_job_with(ctx, "BRAND", &products(), &opts).bytes, plain.bytes);
+    });
+}
+
+#[test]
+fn rtl_survives_a_price_wider_than_its_side() {
+    with_font(|ctx| {
+        let mut items = products::<4>();
+        items[0].price = "123456789.99".into();
+        for direction in [Direction::Ltr, Direction::Rtl] {
+            let opts = BuildOptions { direction, ..BuildOptions::default() };
+            assert!(!build_four_product_job_with(ctx, "ماركت", &items, &opts).bytes.is_empty());
+        }
+    });
+}
@@ -0,0 +1,73 @@
+use std::error::Error;
+
+use zebra_epl2_printer::checkpoint::BatchRunner;
+use zebra_epl2_printer::*;
+
+// Status source replaying `replies`, then `Ready`
+fn statuses(replies: Vec<PrinterStatus>) -> impl FnMut() -> Result<PrinterStatus, Box<dyn Error>> {
+    let mut replies = replies.into_iter();
+    move || Ok(replies.next().unwrap_or(PrinterStatus::Ready))
+}
+
+#[test]
+fn run_monitored_never_resends_a_job_it_could_not_confirm() {
+    let mut queue = JobQueue::new();
+    let rx = queue.status_channel();
+    queue.push(b"N\r\nP1\r\n".to_vec()).unwrap();
+    queue.push(b"N\r\nP2\r\n".to_vec()).unwrap();
+
+    let mut sends = Vec::new();
+    let mut transport = |d: &[u8]| -> Result<(), Box<dyn Error>> { sends.push(d.to_vec()); Ok(()) };
+    // Ready before the first job, busy right after it
+    let mut status = statuses(vec![PrinterStatus::Ready, PrinterStatus::Error(1)]);
+    assert_eq!(queue.run_monitored(&mut transport, &mut status), Err(PrinterStatus::Error(1)));
+    assert_eq!(queue.run_monitored(&mut transport, &mut status), Ok(1));
+
+    assert_eq!(sends, [b"N\r\nP1\r\n".to_vec(), b"N\r\nP2\r\n".to_vec()]);
+    let unconfirmed: Vec<JobStatus> = rx.try_iter().map(|(_, s)| s).filter(|s| matches!(s, JobStatus::Unconfirmed { .. })).collect();
+    assert_eq!(unconfirmed, [JobStatus::Unconfirmed { status: PrinterStatus::Error(1) }]);
+}
+
+#[test]
+fn run_monitored_keeps_a_job_the_transport_refused() {
+    let mut queue = JobQueue::new().retry_policy(RetryPolicy { max_attempts: 1, ..RetryPolicy::default() });
+    queue.push(b"N\r\nP1\r\n".to_vec()).unwrap();
+    let mut refuse = |_: &[u8]| -> Result<(), Box<dyn Error>> { Err("offline".into()) };
+    assert!(queue.run_monitored(&mut refuse, &mut statuses(vec![])).is_err());
+    assert_eq!(queue.len(), 1);
+}
+
+#[test]
+fn batch_runner_moves_past_an_unconfirmed_job() {
+    let path = std::env::temp_dir().join(format!("epl2-batch-{}.ckpt", std::process::id()));
+    let runner = BatchRunner::new("order-1", &path);
+    let jobs = vec![b"A".to_vec(), b"B".to_vec(), b"C".to_vec()];
+    let mut sends = Vec::new();
+    let mut transport = |d: &[u8]| -> Result<(), Box<dyn Error>> { sends.push(d.to_vec()); Ok(()) };
+
+    let mut status = statuses(vec![PrinterStatus::Ready, PrinterStatus::Ready, PrinterStatus::PaperOut]);
+    match runner.run(&jobs, &mut transport, &mut status).unwrap() {
+        BatchOutcome::Paused { next, status, unconfirmed } => {
+            assert_eq!((next, status, unconfirmed), (2, PrinterStatus::PaperOut, Some(1)));
+        }
+        other => panic!("{:?}", other),
+    }
+    assert!(matches!(runner.run(&jobs, &mut transport, &mut status).unwrap(), BatchOutcome::Completed { sent: 1 }));
+    assert_eq!(sends, [b"A".to_vec(), b"B".to_vec(), b"C".to_vec()]);
+}
+
+#[test]
+fn spool_ids_continue_after_failed_and_expired_jobs() {
+    let dir = std::env::temp_dir().join(format!("epl2-spool-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("0000000003.job"), b"N\r\nP1\r\n").unwrap();
+    std::fs::write(dir.join("0000000007.failed"), b"old").unwrap();
+    std::fs::write(dir.join("0000000005.expired"), b"old").unwrap();
+
+    let mut queue = JobQueue::with_spool(&dir).unwrap();
+    assert_eq!(queue.pending(), [3]);
+    assert_eq!(queue.push(b"N\r\nP2\r\n".to_vec()).unwrap(), 8);
+    assert_eq!(std::fs::read(dir.join("0000000007.failed")).unwrap(), b"old");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
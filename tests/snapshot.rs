@@ -0,0 +1,52 @@
+mod common;
+
+use common::{products, with_font};
+use zebra_epl2_printer::snapshot::canonicalize;
+use zebra_epl2_printer::*;
+
+// Layout drift guard: the built jobs must match the committed snapshots
+// byte for byte once canonicalized (`UPDATE_SNAPSHOTS=1` to accept a change).
+#[test]
+fn two_up_job_matches_snapshot() {
+    with_font(|ctx| {
+        let job = build_two_product_job(ctx, "ماركت", &products());
+        assert_snapshot("tests/snapshots/two_up.snap", &job.bytes);
+    });
+}
+
+#[test]
+fn four_up_job_matches_snapshot() {
+    with_font(|ctx| {
+        let job = build_four_product_job(ctx, "ماركت", &products());
+        assert_snapshot("tests/snapshots/four_up.snap", &job.bytes);
+    });
+}
+
+#[test]
+fn builds_are_deterministic() {
+    with_font(|ctx| {
+        let a = build_four_product_job(ctx, "ماركت", &products());
+        let b = build_four_product_job(ctx, "ماركت", &products());
+        assert_eq!(a.bytes, b.bytes);
+        assert_eq!(fingerprint(&a.bytes), fingerprint(&b.bytes));
+    });
+}
+
+#[test]
+fn canonicalize_sorts_black_drawing_only() {
+    let a = b"N\r\nLO10,10,5,5\r\nA1,1,0,1,1,1,N,\"x\"\r\nP1\r\n";
+    let b = b"N\r\nA1,1,0,1,1,1,N,\"x\"\r\nLO10,10,5,5\r\nP1\r\n";
+    assert_eq!(canonicalize(a), canonicalize(b));
+}
+
+#[test]
+fn canonicalize_keeps_order_dependent_commands_in_place() {
+    // A white line or reverse text over a black box prints differently from
+    // the box drawn over them
+    for over in ["LW0,0,20,20", "LE0,0,20,20", "A0,0,0,1,1,1,R,\"x\""] {
+        let before = format!("N\r\nLO0,0,20,20\r\n{}\r\nP1\r\n", over);
+        let after = format!("N\r\n{}\r\nLO0,0,20,20\r\nP1\r\n", over);
+        assert_ne!(canonicalize(before.as_bytes()), canonicalize(after.as_bytes()), "{}", over);
+        assert_eq!(canonicalize(before.as_bytes()), before.as_bytes());
+    }
+}